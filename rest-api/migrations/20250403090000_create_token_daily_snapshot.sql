@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS token_daily_snapshot
+(
+    token_address VARCHAR(128) NOT NULL,
+    record_date   BIGINT       NOT NULL, -- unix timestamp of the start of the day (UTC)
+    marketcap     NUMERIC(30, 10),
+    volume_24h    NUMERIC(30, 0),
+    liquidity     NUMERIC(30, 0),
+    holders       INT,
+    price         NUMERIC(20, 10),
+    recorded_at   TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (token_address, record_date)
+);
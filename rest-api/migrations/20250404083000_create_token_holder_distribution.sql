@@ -0,0 +1,10 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS token_holder_distribution
+(
+    token_address VARCHAR(128)     NOT NULL,
+    category      VARCHAR(16)      NOT NULL, -- shrimp, fish, dolphin, shark, whale
+    percentage    DOUBLE PRECISION NOT NULL, -- share of the supply held by the bucket
+    amount        BIGINT           NOT NULL, -- number of holders in the bucket
+    updated_at    TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (token_address, category)
+);
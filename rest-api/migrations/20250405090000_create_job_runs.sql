@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS job_runs
+(
+    id          BIGSERIAL PRIMARY KEY,
+    job_name    VARCHAR(64) NOT NULL,
+    started_at  TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL,
+    duration_ms BIGINT      NOT NULL,
+    outcome     VARCHAR(16) NOT NULL, -- succeeded, failed
+    error       TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_job_name_started_at ON job_runs (job_name, started_at DESC);
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE token_watch ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMPTZ;
@@ -0,0 +1,9 @@
+-- Add migration script here
+ALTER TABLE token_watch
+    ADD COLUMN IF NOT EXISTS failure_count    INT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS last_error       TEXT,
+    ADD COLUMN IF NOT EXISTS next_attempt_at  TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS dead_lettered_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_token_watch_failure_count ON token_watch (failure_count)
+    WHERE failure_count > 0;
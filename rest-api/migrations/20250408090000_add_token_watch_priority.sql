@@ -0,0 +1,9 @@
+-- Add migration script here
+-- 0 = hot (recent market mover trade, detail view or trending), 1 = active, 2 = long tail
+ALTER TABLE token_watch
+    ADD COLUMN IF NOT EXISTS priority       SMALLINT NOT NULL DEFAULT 2,
+    ADD COLUMN IF NOT EXISTS priority_until TIMESTAMPTZ;
+
+UPDATE token_watch SET priority = 1 WHERE last_active >= NOW() - INTERVAL '1 hour';
+
+CREATE INDEX IF NOT EXISTS idx_token_watch_priority_updated_at ON token_watch (priority, updated_at);
@@ -0,0 +1,6 @@
+-- NUMERIC(10,6) rounds the price of most memecoins to 0 and overflows from 10^4, the batch price
+-- endpoint stores the metrics of any token.
+ALTER TABLE token_metrics
+    ALTER COLUMN price TYPE NUMERIC,
+    ALTER COLUMN price_change_percent TYPE NUMERIC,
+    ALTER COLUMN volume_change_percent TYPE NUMERIC;
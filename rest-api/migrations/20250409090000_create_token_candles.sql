@@ -0,0 +1,25 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS token_candles
+(
+    token_address VARCHAR(128)     NOT NULL,
+    resolution    VARCHAR(4)       NOT NULL, -- 1m, 5m, 15m, 1H, 4H, 1D, 1W
+    unix_time     BIGINT           NOT NULL, -- start of the candle
+    open          DOUBLE PRECISION NOT NULL,
+    high          DOUBLE PRECISION NOT NULL,
+    low           DOUBLE PRECISION NOT NULL,
+    close         DOUBLE PRECISION NOT NULL,
+    volume        DOUBLE PRECISION NOT NULL,
+    updated_at    TIMESTAMPTZ      NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (token_address, resolution, unix_time)
+);
+
+-- span of closed candles fetched without gaps, candles missing inside it had no trades
+CREATE TABLE IF NOT EXISTS token_candle_coverage
+(
+    token_address VARCHAR(128) NOT NULL,
+    resolution    VARCHAR(4)   NOT NULL,
+    covered_from  BIGINT       NOT NULL,
+    covered_to    BIGINT       NOT NULL,
+    updated_at    TIMESTAMPTZ  NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (token_address, resolution)
+);
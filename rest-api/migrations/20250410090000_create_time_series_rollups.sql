@@ -0,0 +1,162 @@
+-- Time series are keyed by unix seconds, the integer "now" lets TimescaleDB policies age them.
+CREATE OR REPLACE FUNCTION unix_now() RETURNS BIGINT
+    LANGUAGE SQL STABLE AS
+$$
+SELECT EXTRACT(EPOCH FROM NOW())::BIGINT
+$$;
+
+-- Rollups:
+--   token_prices_daily (address, bucket, open, high, low, close), read by the vibecheck history
+--   token_metrics_hourly, token_metrics_daily (token_address, bucket, open, high, low, close,
+--   volume_usd, samples), the history of token_metrics past its raw window, not read by the API
+-- With TimescaleDB they are continuous aggregates over hypertables, on vanilla Postgres plain
+-- views with the same columns.
+DO
+$$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb')
+        AND EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'timescaledb')
+        AND current_setting('shared_preload_libraries') LIKE '%timescaledb%' THEN
+        CREATE EXTENSION IF NOT EXISTS timescaledb;
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb') THEN
+        RAISE NOTICE 'timescaledb is not available, rollups are plain views';
+
+        CREATE OR REPLACE VIEW token_metrics_hourly AS
+        SELECT token_address,
+               update_unix_time - update_unix_time % 3600                  AS bucket,
+               (ARRAY_AGG(price ORDER BY update_unix_time))[1]             AS open,
+               MAX(price)                                                  AS high,
+               MIN(price)                                                  AS low,
+               (ARRAY_AGG(price ORDER BY update_unix_time DESC))[1]        AS close,
+               (ARRAY_AGG(volume_usd ORDER BY update_unix_time DESC))[1]   AS volume_usd,
+               COUNT(*)                                                    AS samples
+        FROM token_metrics
+        GROUP BY token_address, bucket;
+
+        CREATE OR REPLACE VIEW token_metrics_daily AS
+        SELECT token_address,
+               update_unix_time - update_unix_time % 86400                 AS bucket,
+               (ARRAY_AGG(price ORDER BY update_unix_time))[1]             AS open,
+               MAX(price)                                                  AS high,
+               MIN(price)                                                  AS low,
+               (ARRAY_AGG(price ORDER BY update_unix_time DESC))[1]        AS close,
+               (ARRAY_AGG(volume_usd ORDER BY update_unix_time DESC))[1]   AS volume_usd,
+               COUNT(*)                                                    AS samples
+        FROM token_metrics
+        GROUP BY token_address, bucket;
+
+        CREATE OR REPLACE VIEW token_prices_daily AS
+        SELECT address,
+               unixtime - unixtime % 86400                   AS bucket,
+               (ARRAY_AGG(price ORDER BY unixtime))[1]       AS open,
+               MAX(price)                                    AS high,
+               MIN(price)                                    AS low,
+               (ARRAY_AGG(price ORDER BY unixtime DESC))[1]  AS close
+        FROM token_prices
+        GROUP BY address, bucket;
+
+        RETURN;
+    END IF;
+
+    -- the time column has to be part of every unique index of a hypertable
+    ALTER TABLE token_metrics
+        DROP CONSTRAINT IF EXISTS token_metrics_pkey,
+        ADD PRIMARY KEY (id, update_unix_time);
+
+    PERFORM create_hypertable('token_metrics', 'update_unix_time',
+                              chunk_time_interval => 604800::BIGINT,
+                              migrate_data => true, if_not_exists => true);
+    PERFORM create_hypertable('token_prices', 'unixtime',
+                              chunk_time_interval => 2592000::BIGINT,
+                              migrate_data => true, if_not_exists => true);
+    PERFORM create_hypertable('token_volume_history', 'record_date',
+                              chunk_time_interval => 2592000::BIGINT,
+                              migrate_data => true, if_not_exists => true);
+    PERFORM set_integer_now_func('token_metrics', 'unix_now', replace_if_exists => true);
+    PERFORM set_integer_now_func('token_prices', 'unix_now', replace_if_exists => true);
+    PERFORM set_integer_now_func('token_volume_history', 'unix_now', replace_if_exists => true);
+
+    CREATE MATERIALIZED VIEW IF NOT EXISTS token_metrics_hourly
+        WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+    SELECT token_address,
+           time_bucket(3600::BIGINT, update_unix_time)  AS bucket,
+           FIRST(price, update_unix_time)               AS open,
+           MAX(price)                                   AS high,
+           MIN(price)                                   AS low,
+           LAST(price, update_unix_time)                AS close,
+           LAST(volume_usd, update_unix_time)           AS volume_usd,
+           COUNT(*)                                     AS samples
+    FROM token_metrics
+    GROUP BY token_address, bucket
+    WITH NO DATA;
+
+    CREATE MATERIALIZED VIEW IF NOT EXISTS token_metrics_daily
+        WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+    SELECT token_address,
+           time_bucket(86400::BIGINT, update_unix_time) AS bucket,
+           FIRST(price, update_unix_time)               AS open,
+           MAX(price)                                   AS high,
+           MIN(price)                                   AS low,
+           LAST(price, update_unix_time)                AS close,
+           LAST(volume_usd, update_unix_time)           AS volume_usd,
+           COUNT(*)                                     AS samples
+    FROM token_metrics
+    GROUP BY token_address, bucket
+    WITH NO DATA;
+
+    CREATE MATERIALIZED VIEW IF NOT EXISTS token_prices_daily
+        WITH (timescaledb.continuous, timescaledb.materialized_only = false) AS
+    SELECT address,
+           time_bucket(86400::BIGINT, unixtime) AS bucket,
+           FIRST(price, unixtime)               AS open,
+           MAX(price)                           AS high,
+           MIN(price)                           AS low,
+           LAST(price, unixtime)                AS close
+    FROM token_prices
+    GROUP BY address, bucket
+    WITH NO DATA;
+
+    -- refresh the recent buckets, older ones only change through late inserts
+    PERFORM add_continuous_aggregate_policy('token_metrics_hourly',
+                                            start_offset => 259200::BIGINT,
+                                            end_offset => 3600::BIGINT,
+                                            schedule_interval => INTERVAL '30 minutes',
+                                            if_not_exists => true);
+    PERFORM add_continuous_aggregate_policy('token_metrics_daily',
+                                            start_offset => 1209600::BIGINT,
+                                            end_offset => 86400::BIGINT,
+                                            schedule_interval => INTERVAL '6 hours',
+                                            if_not_exists => true);
+    PERFORM add_continuous_aggregate_policy('token_prices_daily',
+                                            start_offset => 2592000::BIGINT,
+                                            end_offset => 86400::BIGINT,
+                                            schedule_interval => INTERVAL '6 hours',
+                                            if_not_exists => true);
+
+    ALTER TABLE token_metrics SET (timescaledb.compress,
+        timescaledb.compress_segmentby = 'token_address',
+        timescaledb.compress_orderby = 'update_unix_time DESC');
+    ALTER TABLE token_prices SET (timescaledb.compress,
+        timescaledb.compress_segmentby = 'address',
+        timescaledb.compress_orderby = 'unixtime DESC');
+    ALTER TABLE token_volume_history SET (timescaledb.compress,
+        timescaledb.compress_segmentby = 'token_address',
+        timescaledb.compress_orderby = 'record_date DESC');
+    PERFORM add_compression_policy('token_metrics', compress_after => 604800::BIGINT,
+                                   if_not_exists => true);
+    PERFORM add_compression_policy('token_prices', compress_after => 2592000::BIGINT,
+                                   if_not_exists => true);
+    PERFORM add_compression_policy('token_volume_history', compress_after => 2592000::BIGINT,
+                                   if_not_exists => true);
+
+    -- raw metrics are kept 30 days, hourly rollups a year and daily rollups forever
+    PERFORM add_retention_policy('token_metrics', drop_after => 2592000::BIGINT,
+                                 if_not_exists => true);
+    PERFORM add_retention_policy('token_metrics_hourly', drop_after => 31536000::BIGINT,
+                                 if_not_exists => true);
+    PERFORM add_retention_policy('token_volume_history', drop_after => 31536000::BIGINT,
+                                 if_not_exists => true);
+END
+$$;
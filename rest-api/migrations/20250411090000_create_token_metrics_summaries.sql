@@ -0,0 +1,71 @@
+-- token_metrics rows older than the raw window are folded into these by the retention job
+CREATE TABLE IF NOT EXISTS token_metrics_hourly_summary
+(
+    token_address VARCHAR(126)    NOT NULL,
+    bucket        BIGINT          NOT NULL, -- unix time of the start of the hour
+    open          NUMERIC,
+    high          NUMERIC,
+    low           NUMERIC,
+    close         NUMERIC,
+    volume_usd    NUMERIC(30, 10) NOT NULL, -- latest 24h volume of the bucket
+    samples       BIGINT          NOT NULL,
+    PRIMARY KEY (token_address, bucket)
+);
+
+CREATE TABLE IF NOT EXISTS token_metrics_daily_summary
+(
+    token_address VARCHAR(126)    NOT NULL,
+    bucket        BIGINT          NOT NULL, -- unix time of the start of the day (UTC)
+    open          NUMERIC,
+    high          NUMERIC,
+    low           NUMERIC,
+    close         NUMERIC,
+    volume_usd    NUMERIC(30, 10) NOT NULL,
+    samples       BIGINT          NOT NULL,
+    PRIMARY KEY (token_address, bucket)
+);
+
+-- Without TimescaleDB the rollup views read the summaries for the downsampled past and
+-- aggregate the raw rows for the rest, TimescaleDB keeps its continuous aggregates.
+DO
+$$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb') THEN
+        RETURN;
+    END IF;
+
+    DROP VIEW IF EXISTS token_metrics_hourly;
+    CREATE VIEW token_metrics_hourly AS
+    SELECT token_address, bucket, open, high, low, close, volume_usd, samples
+    FROM token_metrics_hourly_summary
+    UNION ALL
+    SELECT token_address,
+           update_unix_time - update_unix_time % 3600                  AS bucket,
+           (ARRAY_AGG(price ORDER BY update_unix_time))[1]             AS open,
+           MAX(price)                                                  AS high,
+           MIN(price)                                                  AS low,
+           (ARRAY_AGG(price ORDER BY update_unix_time DESC))[1]        AS close,
+           (ARRAY_AGG(volume_usd ORDER BY update_unix_time DESC))[1]   AS volume_usd,
+           COUNT(*)                                                    AS samples
+    FROM token_metrics
+    GROUP BY token_address, 2;
+
+    DROP VIEW IF EXISTS token_metrics_daily;
+    CREATE VIEW token_metrics_daily AS
+    SELECT token_address, bucket, open, high, low, close, volume_usd, samples
+    FROM token_metrics_daily_summary
+    UNION ALL
+    SELECT token_address,
+           update_unix_time - update_unix_time % 86400                 AS bucket,
+           (ARRAY_AGG(price ORDER BY update_unix_time))[1]             AS open,
+           MAX(price)                                                  AS high,
+           MIN(price)                                                  AS low,
+           (ARRAY_AGG(price ORDER BY update_unix_time DESC))[1]        AS close,
+           (ARRAY_AGG(volume_usd ORDER BY update_unix_time DESC))[1]   AS volume_usd,
+           COUNT(*)                                                    AS samples
+    FROM token_metrics
+    GROUP BY token_address, 2;
+END
+$$;
+
+CREATE INDEX IF NOT EXISTS idx_token_watch_last_active ON token_watch (last_active);
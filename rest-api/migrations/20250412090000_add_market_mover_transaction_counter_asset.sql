@@ -0,0 +1,5 @@
+-- Add migration script here
+-- transaction_type is now one of buy, sell, transfer, airdrop, lp_add, lp_remove
+ALTER TABLE market_movers_transaction
+    ADD COLUMN IF NOT EXISTS counter_asset  VARCHAR(128),    -- mint paid or received, SOL/USDC for most trades
+    ADD COLUMN IF NOT EXISTS counter_amount NUMERIC(30, 10); -- amount of counter_asset
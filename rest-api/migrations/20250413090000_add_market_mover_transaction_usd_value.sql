@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE market_movers_transaction
+    ADD COLUMN IF NOT EXISTS price_usd    NUMERIC(38, 18), -- USD price of the token at block_time
+    ADD COLUMN IF NOT EXISTS value_usd    NUMERIC(30, 10), -- USD value of amount at block_time
+    ADD COLUMN IF NOT EXISTS price_source VARCHAR(16);     -- trade, database or provider
@@ -0,0 +1,13 @@
+-- Add migration script here
+-- Raw Helius webhook transactions, kept to replay them through a fixed parser
+CREATE TABLE IF NOT EXISTS webhook_events (
+    signature    VARCHAR(256) PRIMARY KEY,
+    payload      JSONB NOT NULL,
+    block_time   BIGINT,
+    received_at  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    processed_at TIMESTAMPTZ,           -- NULL until its moves are stored
+    attempts     INT NOT NULL DEFAULT 0,
+    last_error   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_webhook_events_block_time ON webhook_events (block_time);
@@ -1,13 +1,16 @@
-use crate::alpha_move::token_score::TokenMetric;
 use crate::alpha_move::transaction;
 use crate::app::AppState;
+use crate::error::ApiError;
+use crate::events::Event;
 use crate::response::HttpPaginationResponse;
 use axum::extract::{Query, State};
-use axum::http::StatusCode;
+use axum::response::sse::{self, KeepAlive, Sse};
 use axum::Json;
 use bigdecimal::{BigDecimal, ToPrimitive};
 use chrono::Utc;
+use futures::Stream;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
 use tracing::error;
 use validator::Validate;
 
@@ -28,6 +31,16 @@ pub struct MoverTransactionResponse {
     #[serde(rename = "actionType")]
     pub transaction_type: Option<String>,
     pub amount: BigDecimal,
+    /// Mint paid or received for the token, SOL or USDC for most trades.
+    pub counter_asset: Option<String>,
+    pub counter_amount: Option<BigDecimal>,
+    /// USD price of the token when the transaction landed.
+    pub price_usd: Option<BigDecimal>,
+    /// USD value of `amount` when the transaction landed.
+    pub value_usd: Option<BigDecimal>,
+    /// Where `price_usd` comes from: `trade` when implied by the SOL or USDC leg, `database` or
+    /// `provider` when looked up.
+    pub price_source: Option<String>,
     #[serde(rename = "time")]
     pub block_time: i64,
     pub slot: i64,
@@ -89,22 +102,18 @@ pub struct TokenScoreResponse {
 pub async fn get_mover_transaction(
     State(app): State<AppState>,
     Query(query): Query<PaginationQuery>,
-) -> Result<Json<HttpPaginationResponse<Vec<MoverTransactionResponse>>>, (StatusCode, String)> {
-    if let Err(validation_errors) = query.validate() {
-        return Err((StatusCode::BAD_REQUEST, validation_errors.to_string()));
-    }
+) -> Result<Json<HttpPaginationResponse<Vec<MoverTransactionResponse>>>, ApiError> {
+    query.validate()?;
 
     let transactions = transaction::fetch_mover_transactions(&app.pool, query.limit, query.offset)
         .await
         .map_err(|e| {
             error!("Failed to fetch mover transactions: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            ApiError::from(e)
         })?;
 
     // Execute query to count total rows in market_movers_transaction.
-    let total = transaction::count_mover_transaction(&app)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let total = transaction::count_mover_transaction(&app).await?;
 
     let transactions = transactions
         .iter()
@@ -114,6 +123,11 @@ pub async fn get_mover_transaction(
             wallet_address: a.wallet_address.clone(),
             transaction_type: a.transaction_type.clone(),
             amount: a.amount.clone(),
+            counter_asset: a.counter_asset.clone(),
+            counter_amount: a.counter_amount.clone(),
+            price_usd: a.price_usd.clone(),
+            value_usd: a.value_usd.clone(),
+            price_source: a.price_source.clone(),
             block_time: a.block_time,
             slot: a.slot,
             token_name: a.token_name.clone(),
@@ -148,3 +162,24 @@ pub async fn get_mover_transaction(
         total,
     }))
 }
+
+/// Market mover transactions as the webhook of any instance receives them, as `alphamove`
+/// server-sent events.
+pub async fn stream_mover_transactions(
+    State(app): State<AppState>,
+) -> Sse<impl Stream<Item = Result<sse::Event, axum::Error>>> {
+    let events = app.events.subscribe();
+    let stream = futures::stream::unfold(events, |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event @ Event::MarketMoverTransaction { .. }) => {
+                    let event = sse::Event::default().event("alphamove").json_data(event);
+                    return Some((event, events));
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -1,5 +1,6 @@
 use bigdecimal::BigDecimal;
 
+#[allow(dead_code)]
 #[derive(serde::Serialize, Debug, sqlx::FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct TokenMetric {
@@ -13,6 +14,7 @@ pub struct TokenMetric {
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[allow(dead_code)]
 pub async fn fetch_token_metrics_by_addresses(
     pool: &sqlx::Pool<sqlx::Postgres>,
     addresses: Vec<String>,
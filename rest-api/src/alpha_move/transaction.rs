@@ -11,6 +11,11 @@ pub struct MoverTransaction {
     #[serde(rename = "actionType")]
     pub transaction_type: Option<String>,
     pub amount: BigDecimal,
+    pub counter_asset: Option<String>,
+    pub counter_amount: Option<BigDecimal>,
+    pub price_usd: Option<BigDecimal>,
+    pub value_usd: Option<BigDecimal>,
+    pub price_source: Option<String>,
     #[serde(rename = "time")]
     pub block_time: i64,
     pub slot: i64,
@@ -66,6 +71,11 @@ pub async fn fetch_mover_transactions(
             mm.wallet_address,
             mm.transaction_type,
             mm.amount,
+            mm.counter_asset,
+            mm.counter_amount,
+            mm.price_usd,
+            mm.value_usd,
+            mm.price_source,
             mm.block_time,
             mm.slot,
             mm.additional,
@@ -1,217 +1,566 @@
-use crate::config::{BirdeyeConfig, DatabaseConfig};
+use crate::config::{
+    AdminConfig, BirdeyeConfig, CacheConfig, DatabaseConfig, DistributionConfig, HeliusConfig,
+    JobConfig, ProviderConfig, PriceConfig, ProviderHttpConfig, RedisConfig, RetentionConfig,
+    TokenWatchConfig, WebhookConfig,
+};
+use crate::cache::{CacheGroup, ResponseCache};
+use crate::events::{Event, EventBus};
 use crate::fearandgreed::{batch_insert_fear_and_greed, FearAndGreed, FearAndGreedSdk};
+use crate::jobs::{IntervalJob, JobManager};
 use crate::price::{self, PriceSdk, TimeFilters};
+use crate::redis_store::RedisStore;
 use crate::thirdparty::alternative_api::AlternativeClient;
-use crate::thirdparty::defi::DefiClient;
-use crate::thirdparty::{BirdEyeClient, MoniClient};
+use crate::thirdparty::defi::{DefiClient, VolumeSdk};
+use crate::thirdparty::helius::{HeliusClient, LocalWebhook, WebhookSdk};
+use crate::thirdparty::http::ProviderHttp;
+use crate::thirdparty::limiter::{Lane, Limiter};
+use crate::thirdparty::mock::MockClient;
+use crate::thirdparty::{BirdEyeClient, MoniClient, MunScoreSdk, SafetyClient, SafetySdk};
 use crate::token::TokenSdk;
-use crate::{time_util, token, volume};
+use crate::{
+    alpha_move, auth, fearandgreed, jobs, market_mover, retention, thirdparty, time_util, token,
+    volume, webhook,
+};
+use anyhow::Context;
 use axum::{
     body::{Body, Bytes},
-    extract::Request,
+    extract::{DefaultBodyLimit, Request},
     http::StatusCode,
-    middleware::Next,
+    middleware::{self, Next},
     response::{IntoResponse, Response},
+    routing::{get, patch, post},
+    Router,
 };
 use chrono::Utc;
 use envconfig::Envconfig;
 use http_body_util::BodyExt;
 use reqwest::Client;
 use sqlx::{PgPool, Pool, Postgres};
+use std::collections::BTreeMap;
+use std::future::Future;
 use std::sync::Arc;
-use tokio_cron_scheduler::{Job, JobScheduler};
-use tracing::{error, info};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Clone)]
 pub struct AppState {
     pub version: i32,
-    pub bird_eye_client: BirdEyeClient,
-    pub alternative_client: AlternativeClient,
-    pub moni_client: Arc<MoniClient>,
+    pub token_sdk: Arc<dyn TokenSdk>,
+    pub price_sdk: Arc<dyn PriceSdk>,
+    pub fear_and_greed_sdk: Arc<dyn FearAndGreedSdk>,
+    pub mun_score_sdk: Arc<dyn MunScoreSdk>,
+    pub safety_sdk: Arc<dyn SafetySdk>,
+    pub volume_sdk: Arc<dyn VolumeSdk>,
+    pub webhook_sdk: Arc<dyn WebhookSdk>,
+    pub limiters: BTreeMap<&'static str, Arc<Limiter>>,
     pub pool: Pool<Postgres>,
-    pub client: reqwest::Client,
+    pub distribution_config: DistributionConfig,
+    pub token_watch_config: TokenWatchConfig,
+    pub price_config: PriceConfig,
+    pub webhook_config: WebhookConfig,
+    pub admin_config: AdminConfig,
+    pub cache: ResponseCache,
+    pub events: EventBus,
+    pub jobs: JobManager,
     // pub helius: Arc<Helius>,
 }
 const ALTERNATIVE_BASE_URL: &str = "https://api.alternative.me/fng/";
-pub const SOL_ADDRESS: &str = "So11111111111111111111111111111111111111112";
-pub const SOLANA: &str = "solana";
 
-impl AppState {
-    pub async fn new() -> Self {
-        init_tracing();
+/// Adapters plugged into the third party ports of [`AppState`].
+#[derive(Clone)]
+pub struct Providers {
+    pub token_sdk: Arc<dyn TokenSdk>,
+    pub price_sdk: Arc<dyn PriceSdk>,
+    pub fear_and_greed_sdk: Arc<dyn FearAndGreedSdk>,
+    pub mun_score_sdk: Arc<dyn MunScoreSdk>,
+    pub safety_sdk: Arc<dyn SafetySdk>,
+    pub volume_sdk: Arc<dyn VolumeSdk>,
+    /// Webhook streaming the transactions of the market movers.
+    pub webhook_sdk: Arc<dyn WebhookSdk>,
+    /// Limiter of each rate limited provider, kept to report their usage.
+    pub limiters: BTreeMap<&'static str, Arc<Limiter>>,
+    /// Redis shared with the other instances, `None` to keep every state local.
+    pub redis: Option<RedisStore>,
+}
+
+impl Providers {
+    /// Live adapters sharing `client`, API keys and HTTP settings are read from the environment.
+    /// With `redis` the provider budgets are shared by the instances.
+    pub fn live(client: Client, redis: Option<RedisStore>) -> Self {
         let BirdeyeConfig {
             birdeye_api_key,
             base_url,
             moni_api_key,
+            requests_per_sec,
+            compute_units_per_minute,
         } = BirdeyeConfig::init_from_env().unwrap();
+        let http = ProviderHttpConfig::init_from_env().unwrap();
+        let limiter = |name, requests_per_sec, compute_units_per_minute| {
+            let limiter = Limiter::new(requests_per_sec, compute_units_per_minute);
+            let limiter = match &redis {
+                Some(redis) => limiter.shared(redis.clone(), name),
+                None => limiter,
+            };
+            (name, Arc::new(limiter))
+        };
+        let limiters = BTreeMap::from([
+            limiter("birdeye", requests_per_sec, compute_units_per_minute),
+            limiter("moni", http.moni_requests_per_sec, 0),
+            limiter("safety", http.safety_requests_per_sec, 0),
+            limiter("defillama", http.defillama_requests_per_sec, 0),
+        ]);
+        let provider = |name, timeout_secs| {
+            let provider = ProviderHttp::new(name, client.clone(), timeout_secs, &http);
+            match limiters.get(name) {
+                Some(limiter) => provider.with_limiter(limiter.clone()),
+                None => provider,
+            }
+        };
+        let webhook_sdk: Arc<dyn WebhookSdk> = match HeliusConfig::init_from_env().unwrap() {
+            HeliusConfig {
+                api_key: Some(api_key),
+                webhook_id: Some(webhook_id),
+                base_url,
+            } => Arc::new(HeliusClient::new(
+                provider("helius", http.helius_timeout_secs),
+                &base_url,
+                &api_key,
+                &webhook_id,
+            )),
+            _ => {
+                warn!(
+                    "HELIUS_API_KEY or HELIUS_WEBHOOK_ID is not set, market movers are not synced"
+                );
+                Arc::new(LocalWebhook::default())
+            }
+        };
+        let bird_eye_client = Arc::new(BirdEyeClient::new(
+            &base_url,
+            &birdeye_api_key,
+            provider("birdeye", http.birdeye_timeout_secs),
+        ));
+        Self {
+            token_sdk: bird_eye_client.clone(),
+            price_sdk: bird_eye_client,
+            fear_and_greed_sdk: Arc::new(AlternativeClient::new(
+                ALTERNATIVE_BASE_URL.into(),
+                31,
+                provider("alternative", http.alternative_timeout_secs),
+            )),
+            mun_score_sdk: Arc::new(MoniClient::new(
+                moni_api_key,
+                provider("moni", http.moni_timeout_secs),
+            )),
+            safety_sdk: Arc::new(SafetyClient::new(provider(
+                "safety",
+                http.safety_timeout_secs,
+            ))),
+            volume_sdk: Arc::new(DefiClient {
+                http: provider("defillama", http.defillama_timeout_secs),
+            }),
+            webhook_sdk,
+            limiters,
+            redis,
+        }
+    }
+
+    /// Fixture backed adapters, see [`MockClient`].
+    pub fn mock() -> Self {
+        let mock = Arc::new(MockClient);
+        Self {
+            token_sdk: mock.clone(),
+            price_sdk: mock.clone(),
+            fear_and_greed_sdk: mock.clone(),
+            mun_score_sdk: mock.clone(),
+            safety_sdk: mock.clone(),
+            volume_sdk: mock,
+            webhook_sdk: Arc::new(LocalWebhook::default()),
+            limiters: BTreeMap::from([("birdeye", Arc::new(Limiter::new(u32::MAX, 0)))]),
+            redis: None,
+        }
+    }
+}
+
+pub const SOL_ADDRESS: &str = "So11111111111111111111111111111111111111112";
+pub const SOLANA: &str = "solana";
+
+impl AppState {
+    pub async fn new() -> Self {
+        init_tracing();
         // let helius_api_key: &str = "your_api_key";
         // let cluster: Cluster= Cluster::MainnetBeta;
-        let client = Client::new();
+        let ProviderConfig { mock_providers } = ProviderConfig::init_from_env().unwrap();
+        let providers = if mock_providers {
+            info!("MOCK_PROVIDERS is enabled, serving recorded fixtures");
+            Providers::mock()
+        } else {
+            let redis = RedisStore::connect(&RedisConfig::init_from_env().unwrap())
+                .await
+                .expect("should connect to redis");
+            Providers::live(Client::new(), redis)
+        };
+        let webhook_config = WebhookConfig::init_from_env().unwrap();
+        if webhook_config.secrets.is_empty() {
+            warn!("HELIUS_WEBHOOK_SECRETS is not set, every webhook call is rejected");
+        }
+        let admin_config = AdminConfig::init_from_env().unwrap();
+        if admin_config.api_keys.is_empty() {
+            warn!("ADMIN_API_KEYS is not set, every admin call is rejected");
+        }
+        Self::with_providers(
+            init_pg_pool().await,
+            providers,
+            DistributionConfig::init_from_env().unwrap(),
+            TokenWatchConfig::init_from_env().unwrap(),
+            PriceConfig::init_from_env().unwrap(),
+            CacheConfig::init_from_env().unwrap(),
+            webhook_config,
+            admin_config,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_providers(
+        pool: Pool<Postgres>,
+        providers: Providers,
+        distribution_config: DistributionConfig,
+        token_watch_config: TokenWatchConfig,
+        price_config: PriceConfig,
+        cache_config: CacheConfig,
+        webhook_config: WebhookConfig,
+        admin_config: AdminConfig,
+    ) -> Self {
+        let Providers {
+            token_sdk,
+            price_sdk,
+            fear_and_greed_sdk,
+            mun_score_sdk,
+            safety_sdk,
+            volume_sdk,
+            webhook_sdk,
+            limiters,
+            redis,
+        } = providers;
+        let jobs = JobManager::new().with_history(pool.clone());
         Self {
             version: 0,
-            bird_eye_client: BirdEyeClient::new(&base_url, &birdeye_api_key),
-            alternative_client: AlternativeClient::new(ALTERNATIVE_BASE_URL.into(), 31),
-            pool: init_pg_pool().await,
-            moni_client: Arc::new(MoniClient::new(moni_api_key, client.clone())),
-            client, // helius: Arc::new(Helius::new(api_key, cluster).unwrap()),
+            token_sdk,
+            price_sdk,
+            fear_and_greed_sdk,
+            mun_score_sdk,
+            safety_sdk,
+            volume_sdk,
+            webhook_sdk,
+            limiters,
+            pool,
+            distribution_config,
+            token_watch_config,
+            price_config,
+            webhook_config,
+            admin_config,
+            cache: ResponseCache::new(&cache_config, redis.clone()),
+            events: EventBus::new(redis),
+            jobs,
+            // helius: Arc::new(Helius::new(api_key, cluster).unwrap()),
         }
     }
 
+    /// Register the background jobs with [`AppState::jobs`] and schedule them.
     pub async fn start_worker(app: Arc<Self>) {
-        let sched = JobScheduler::new().await.unwrap();
-        let pool = app.pool.clone();
-        let alternative_client = app.alternative_client.clone();
-        let app1 = app.clone();
-        Self::run(
-            &pool,
-            &DefiClient {
-                client: Client::new(),
-            },
-            &alternative_client,
-        )
-        .await;
-        sched
-            .add(
-                Job::new_async("0 0 * * * *", move |_uuid, mut _l| {
-                    let app = app1.clone();
-                    let defi_client = DefiClient {
-                        client: Client::new(),
-                    };
-                    let alternative_client = alternative_client.clone();
-                    Box::pin(async move {
-                        AppState::run(&app.pool, &defi_client, &alternative_client).await;
-                    })
-                })
-                .unwrap(),
-            )
-            .await
-            .unwrap();
-
-        let app2 = app.clone();
-        Self::token_price_histories(&app2).await;
-        sched
-            .add(
-                Job::new_async("0 0 * * * *", move |_uuid, mut _l| {
-                    let app = app2.clone();
-                    Box::pin(async move {
-                        Self::token_price_histories(&app).await;
-                    })
-                })
-                .unwrap(),
-            )
-            .await
-            .unwrap();
-        let app3 = app.clone();
-        Self::sol_price(&app3).await;
-        sched
-            .add(
-                Job::new_async("0 0 * * * *", move |_uuid, mut _l| {
-                    let app = app3.clone();
-                    Box::pin(async move {
-                        Self::sol_price(&app).await;
-                    })
-                })
-                .unwrap(),
-            )
-            .await
-            .unwrap();
-
-        let mindshare_state = app.clone();
-        Self::mind_share(&app).await;
-        sched
-            .add(
-                Job::new_async("0 0 * * * *", move |_uuid, mut _l| {
-                    let app = mindshare_state.clone();
-                    Box::pin(async move {
-                        Self::mind_share(&app).await;
-                    })
-                })
-                .unwrap(),
-            )
-            .await
-            .unwrap();
+        let config = JobConfig::init_from_env().unwrap();
+        let retention_config = RetentionConfig::init_from_env().unwrap();
+        for job in [
+            job(
+                &app,
+                "market_sentiment",
+                &config.market_sentiment_cron,
+                config.market_sentiment_timeout_secs,
+                |app| async move {
+                    Self::run(
+                        &app.pool,
+                        app.volume_sdk.as_ref(),
+                        app.fear_and_greed_sdk.as_ref(),
+                    )
+                    .await?;
+                    app.invalidate_cache(CacheGroup::MarketSentiment).await;
+                    Ok(())
+                },
+            ),
+            job(
+                &app,
+                "sol_price_history",
+                &config.sol_price_history_cron,
+                config.sol_price_history_timeout_secs,
+                |app| async move { Self::token_price_histories(&app).await },
+            ),
+            job(
+                &app,
+                "sol_price",
+                &config.sol_price_cron,
+                config.sol_price_timeout_secs,
+                |app| async move { Self::sol_price(&app).await },
+            ),
+            job(
+                &app,
+                "mindshare",
+                &config.mindshare_cron,
+                config.mindshare_timeout_secs,
+                |app| async move {
+                    Self::mind_share(&app).await?;
+                    app.invalidate_cache(CacheGroup::Mindshare).await;
+                    Ok(())
+                },
+            ),
+            job(
+                &app,
+                "token_watch",
+                &config.token_watch_cron,
+                config.token_watch_timeout_secs,
+                |app| async move { token::process_token_watch(&app).await },
+            ),
+            job(
+                &app,
+                "retention",
+                &config.retention_cron,
+                config.retention_timeout_secs,
+                move |app| {
+                    let config = retention_config.clone();
+                    async move {
+                        retention::run_retention(&app.pool, &config, Utc::now()).await?;
+                        Ok(())
+                    }
+                },
+            ),
+        ] {
+            app.jobs.add(job.run_on_startup(config.run_on_startup));
+        }
 
-        if let Err(err) = sched.start().await {
+        if let Err(err) = app.jobs.start().await {
             error!("start cron job error {err}");
         }
+        Self::follow_events(&app);
     }
 
-    async fn token_price_histories(app: &Arc<AppState>) {
-        match app
-            .bird_eye_client
+    /// Drop the responses of `group` on every instance.
+    pub async fn invalidate_cache(&self, group: CacheGroup) {
+        self.cache.invalidate(group).await;
+        self.events.publish(Event::CacheInvalidated { group }).await;
+    }
+
+    /// Forward the events of the other instances and drop the responses they invalidated.
+    fn follow_events(app: &Arc<Self>) {
+        let mut events = app.events.subscribe();
+        let cache = app.cache.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(Event::CacheInvalidated { group }) => cache.invalidate_local(group),
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("missed {missed} events, dropping every cached response");
+                        cache.invalidate_local(CacheGroup::Mindshare);
+                        cache.invalidate_local(CacheGroup::MarketSentiment);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+        let bus = app.events.clone();
+        tokio::spawn(async move {
+            loop {
+                match bus.forward_remote().await {
+                    Ok(()) => return,
+                    Err(err) => warn!("event subscription lost: {err:#}"),
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        });
+    }
+
+    async fn token_price_histories(app: &Arc<AppState>) -> anyhow::Result<()> {
+        let sol_price = app
+            .price_sdk
             .get_price_by_time_filter(SOL_ADDRESS, TimeFilters::OneDay)
             .await
-        {
-            Err(e) => error!("upsert Refresh metric error {e}"),
-            Ok(sol_price) => {
-                match price::insert_token_prices(&app.pool, sol_price, SOL_ADDRESS).await {
-                    Ok(_) => info!("refresh price history successfully"),
-                    Err(err) => error!("refresh price history failed {err}"),
-                };
-            }
-        };
+            .context("fetch price history")?;
+        price::insert_token_prices(&app.pool, sol_price, SOL_ADDRESS)
+            .await
+            .context("insert price history")?;
+        info!("refresh price history successfully");
+        Ok(())
     }
 
-    async fn run(pool: &PgPool, defi_client: &DefiClient, alternative_client: &AlternativeClient) {
-        let Ok(resp) = defi_client.get_blockchain_volum(SOLANA).await else {
-            return;
-        };
+    async fn run(
+        pool: &PgPool,
+        defi_client: &dyn VolumeSdk,
+        alternative_client: &dyn FearAndGreedSdk,
+    ) -> anyhow::Result<()> {
+        let resp = defi_client
+            .get_blockchain_volum(SOLANA)
+            .await
+            .context("fetch blockchain volume")?;
 
         info!("fetching volume {resp:?}");
-        if let Err(e) = volume::upsert_metrics(pool, resp, SOLANA).await {
-            error!("upsert Refresh metric error {e}");
-            return;
-        }
+        volume::upsert_metrics(pool, resp, SOLANA)
+            .await
+            .context("upsert blockchain volume")?;
 
-        let Ok(resp) = alternative_client.get_fear_and_greed(31).await else {
-            return;
-        };
+        let resp = alternative_client
+            .get_fear_and_greed(31)
+            .await
+            .context("fetch fear and greed")?;
 
         let result = resp.iter().map(FearAndGreed::from).collect();
         info!("fetching volume {result:?}");
-        if let Err(e) = batch_insert_fear_and_greed(pool, &result).await {
-            error!("upsert Refresh metric error {e}");
-            return;
-        }
+        batch_insert_fear_and_greed(pool, &result)
+            .await
+            .context("insert fear and greed")?;
+        Ok(())
     }
 
-    async fn sol_price(p0: &Arc<AppState>) {
-        // ✅ 2. If not found, call 3rd-party API
-        let resp = p0.bird_eye_client.get_price(SOL_ADDRESS).await;
-        let Ok(metric) = resp else {
-            error!("fetch price error {}", resp.unwrap_err());
-            return;
-        };
+    async fn sol_price(p0: &Arc<AppState>) -> anyhow::Result<()> {
+        let metric = p0
+            .price_sdk
+            .get_price(SOL_ADDRESS)
+            .await
+            .context("fetch price")?;
 
-        // ✅ 3. Store the metric in the database
-        if let Err(err) = price::store_metric_in_db(&p0.pool, &metric, SOL_ADDRESS).await {
-            error!("store price failed {err}");
-        };
+        price::store_metric_in_db(&p0.pool, &metric, SOL_ADDRESS)
+            .await
+            .context("store price")?;
         info!("fetch price stored successfully");
+        Ok(())
     }
 
-    async fn mind_share(state: &Arc<AppState>) {
-        match state.bird_eye_client.get_trending(0, 20).await {
-            Ok(trending_token) => {
-                info!("get trending tokens {trending_token:?}");
-                if let Err(e) = token::upsert_token_meta(&state.pool, &trending_token).await {
-                    error!("upsert trending token error {e}");
-                }
-                let record_at = time_util::get_start_of_day(Utc::now()).timestamp();
-                if let Err(e) =
-                    token::upsert_daily_volume(&state.pool, &trending_token, record_at).await
-                {
-                    error!("upsert trending token error {e}");
-                }
-            }
-            Err(err) => {
-                error!("get trending tokens {err}");
-            }
-        };
+    async fn mind_share(state: &Arc<AppState>) -> anyhow::Result<()> {
+        let trending_token = state
+            .token_sdk
+            .get_trending(0, 20)
+            .await
+            .context("get trending tokens")?;
+        info!("get trending tokens {trending_token:?}");
+        if let Err(e) = token::upsert_token_meta(&state.pool, &trending_token).await {
+            error!("upsert trending token error {e}");
+        }
+        let record_at = time_util::get_start_of_day(Utc::now()).timestamp();
+        token::upsert_daily_volume(&state.pool, &trending_token, record_at)
+            .await
+            .context("upsert trending token volume")?;
+        let addresses: Vec<String> = trending_token.iter().map(|t| t.address.clone()).collect();
+        token::promote_token_watch(
+            &state.pool,
+            &addresses,
+            token::WatchPriority::Hot,
+            state.token_watch_config.hot_hold_secs,
+        )
+        .await
+        .context("promote trending tokens in token watch")?;
+        Ok(())
     }
 }
 
+/// An [`IntervalJob`] running `task` against the shared state.
+fn job<F, Fut>(
+    app: &Arc<AppState>,
+    name: &str,
+    cron_tab: &str,
+    timeout_secs: u64,
+    task: F,
+) -> IntervalJob
+where
+    F: Fn(Arc<AppState>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let app = app.clone();
+    IntervalJob::new(
+        name,
+        cron_tab,
+        Duration::from_secs(timeout_secs),
+        move || Lane::Background.scope(task(app.clone())),
+    )
+}
+
+/// The `/api/v1` routes served on top of `app_state`.
+pub fn router(app_state: AppState) -> Router {
+    let cors = CorsLayer::new()
+        .allow_origin(Any)
+        .allow_methods(Any)
+        .allow_headers(Any);
+    // the /admin routes and the market mover registry, all behind the admin key
+    let admin = Router::new()
+        .route("/admin/jobs", get(jobs::route::list_jobs))
+        .route("/admin/jobs/{name}/run", post(jobs::route::run_job))
+        .route("/admin/providers/usage", get(thirdparty::route::provider_usage))
+        .route(
+            "/admin/token-watch/stuck",
+            get(token::route::get_stuck_tokens),
+        )
+        .route(
+            "/admin/token-watch/{address}/retry",
+            post(token::route::retry_stuck_token),
+        )
+        .route(
+            "/market-movers",
+            get(market_mover::route::list_market_movers)
+                .post(market_mover::route::create_market_mover),
+        )
+        .route(
+            "/market-movers/import",
+            post(market_mover::route::import_market_movers),
+        )
+        .route(
+            "/market-movers/{wallet_address}",
+            patch(market_mover::route::update_market_mover)
+                .delete(market_mover::route::delete_market_mover),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth::require_admin,
+        ));
+    let router = Router::new()
+        .route("/price", get(price::route::get_prices))
+        .route("/price/{address}", get(price::route::get_price))
+        .route("/health", get(token::health::health))
+        .route("/mindshare", get(token::route::mindshare))
+        .route("/token", get(token::route::search_token))
+        .route("/token/trending", get(token::route::trending_token))
+        .route("/vibecheck", get(fearandgreed::route::vibe_check))
+        .route("/alphamoves", get(alpha_move::get_mover_transaction))
+        .route(
+            "/alphamoves/stream",
+            get(alpha_move::stream_mover_transactions),
+        )
+        .route("/token/{address}/details", get(token::route::get_token_bio))
+        .route("/token/{address}/candles", get(price::route::get_candles))
+        .route(
+            "/token/{address}/details/analytics",
+            get(token::route::get_token_analytics),
+        )
+        .route(
+            "/token/{address}/details/distributions",
+            get(token::route::get_token_distributions),
+        )
+        .merge(admin)
+        .layer(middleware::from_fn(print_request_response))
+        // added past the logging, which buffers the whole body before the size and the secret
+        // of the call are checked
+        .route(
+            "/webhook",
+            post(webhook::webhook_handler)
+                .layer(DefaultBodyLimit::max(app_state.webhook_config.max_payload_bytes))
+                .route_layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    webhook::authenticate,
+                )),
+        )
+        .with_state(app_state)
+        .layer(cors);
+    Router::new().nest("/api/v1", router)
+}
+
 async fn init_pg_pool() -> Pool<Postgres> {
     let DatabaseConfig { database_url, .. } = DatabaseConfig::init_from_env().unwrap();
     // 2) Create a connection pool
@@ -274,3 +623,847 @@ where
 
     Ok(bytes)
 }
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::thirdparty::mock::UNKNOWN_TOKEN;
+    use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+    use axum::http::HeaderValue;
+    use futures::StreamExt;
+    use serde_json::Value;
+    use std::convert::Infallible;
+    use sqlx::postgres::PgPoolOptions;
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    const ADDRESS: &str = "61V8vBaqAGMpgDQi4JcAwo1dmBGHsyhzodcPqnEVpump";
+
+    fn distribution_config() -> DistributionConfig {
+        DistributionConfig::init_from_hashmap(&HashMap::new()).unwrap()
+    }
+
+    fn token_watch_config() -> TokenWatchConfig {
+        TokenWatchConfig::init_from_hashmap(&HashMap::new()).unwrap()
+    }
+
+    fn webhook_config() -> WebhookConfig {
+        let env = HashMap::from([
+            ("HELIUS_WEBHOOK_SECRETS".to_string(), "current,previous".to_string()),
+            ("WEBHOOK_MAX_PAYLOAD_BYTES".to_string(), "1024".to_string()),
+        ]);
+        WebhookConfig::init_from_hashmap(&env).unwrap()
+    }
+
+    fn admin_config() -> AdminConfig {
+        let env = HashMap::from([("ADMIN_API_KEYS".to_string(), "admin".to_string())]);
+        AdminConfig::init_from_hashmap(&env).unwrap()
+    }
+
+    /// State backed by the fixture providers and a database that is never reachable.
+    fn offline_state() -> AppState {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://munki@127.0.0.1:1/munki")
+            .unwrap();
+        AppState::with_providers(
+            pool,
+            Providers::mock(),
+            distribution_config(),
+            token_watch_config(),
+            PriceConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            CacheConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            webhook_config(),
+            admin_config(),
+        )
+    }
+
+    /// The `TEST_DATABASE_URL` database, migrated.
+    pub async fn test_pool() -> PgPool {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let pool = PgPool::connect(&url).await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        pool
+    }
+
+    /// `prefix` with a suffix of its own so that tests do not share rows.
+    pub fn unique_name(prefix: &str) -> String {
+        format!("{prefix}_{}", Utc::now().timestamp_nanos_opt().unwrap())
+    }
+
+    /// State backed by the fixture providers and the migrated `TEST_DATABASE_URL` database.
+    async fn database_state() -> AppState {
+        AppState::with_providers(
+            test_pool().await,
+            Providers::mock(),
+            distribution_config(),
+            token_watch_config(),
+            PriceConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            CacheConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            webhook_config(),
+            admin_config(),
+        )
+    }
+
+    async fn call(state: &AppState, method: &str, uri: &str, body: Body) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into()));
+        (status, body)
+    }
+
+    async fn get(state: &AppState, uri: &str) -> (StatusCode, Value) {
+        call(state, "GET", uri, Body::empty()).await
+    }
+
+    async fn webhook(state: &AppState, secret: Option<&str>, body: Body) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method("POST")
+            .uri("/api/v1/webhook")
+            .header("content-type", "application/json");
+        if let Some(secret) = secret {
+            request = request.header("authorization", secret);
+        }
+        let response = router(state.clone())
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body = serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into()));
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn should_report_health() {
+        let (status, body) = get(&offline_state(), "/api/v1/health").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "Healthy");
+    }
+
+    #[tokio::test]
+    async fn should_report_provider_usage() {
+        let state = offline_state();
+        Lane::Background
+            .scope(state.limiters["birdeye"].acquire("token_overview", 30))
+            .await;
+
+        let uri = "/api/v1/admin/providers/usage";
+        let (status, body) = admin(&state, "GET", uri, "application/json", String::new()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let usage = &body["response"]["birdeye"]["token_overview"];
+        assert_eq!(usage["calls"], 1);
+        assert_eq!(usage["backgroundCalls"], 1);
+        assert_eq!(usage["computeUnits"], 30);
+    }
+
+    #[tokio::test]
+    async fn should_serve_price_from_price_provider() {
+        let (status, body) = get(&offline_state(), &format!("/api/v1/price/{SOL_ADDRESS}")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["price"].as_f64().unwrap() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn should_batch_prices_from_price_provider() {
+        let state = offline_state();
+
+        let uri =
+            format!("/api/v1/price?addresses={SOL_ADDRESS},%20{ADDRESS},{UNKNOWN_TOKEN},{ADDRESS}");
+        let (status, body) = get(&state, &uri).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let prices = body["response"].as_object().unwrap();
+        assert_eq!(prices.len(), 2);
+        assert_eq!(prices[ADDRESS]["source"], "provider");
+        assert_eq!(prices[ADDRESS]["stale"], false);
+        assert!(prices[SOL_ADDRESS]["price"].as_f64().unwrap() > 0.0);
+
+        for uri in ["/api/v1/price?addresses=", "/api/v1/price?addresses=abc"] {
+            let (status, _) = get(&state, uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn should_serve_candles_from_price_provider() {
+        let state = offline_state();
+
+        let (from, to) = (1743465600, 1743473700);
+        let uri = format!("/api/v1/token/{ADDRESS}/candles?interval=15m&from={from}&to={to}");
+        let (status, body) = get(&state, &uri).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"]["interval"], "15m");
+        let candles = body["response"]["candles"].as_array().unwrap();
+        assert_eq!(candles.len(), 10);
+        assert_eq!(candles[0]["unixTime"], from);
+        assert_eq!(candles[9]["unixTime"], to);
+
+        for uri in [
+            format!("/api/v1/token/{ADDRESS}/candles?interval=2h"),
+            format!("/api/v1/token/{ADDRESS}/candles?interval=1m&from=600&to=0"),
+            format!("/api/v1/token/{ADDRESS}/candles?interval=1m&from=0&to=60000"),
+            "/api/v1/token/abc/candles?interval=1m".to_string(),
+        ] {
+            let (status, body) = get(&state, &uri).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+            assert_eq!(body["error"]["kind"], "bad_request", "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_paging() {
+        let state = offline_state();
+
+        let (status, body) = get(&state, "/api/v1/token?q=arc&limit=0&offset=0").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["code"], 400);
+        assert_eq!(body["error"]["kind"], "validation");
+        let (status, _) = get(&state, "/api/v1/alphamoves?limit=101&offset=0").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn should_reject_malformed_webhook_payload() {
+        let body = Body::from(r#"{"signature": "not a list"}"#);
+        let (status, _) = webhook(&offline_state(), Some("previous"), body).await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn should_reject_unauthenticated_or_oversized_webhook() {
+        let state = offline_state();
+
+        for secret in [None, Some("stale"), Some("Bearer current")] {
+            let (status, body) = webhook(&state, secret, Body::from("[]")).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{secret:?}");
+            assert_eq!(body["error"]["kind"], "unauthorized", "{secret:?}");
+        }
+
+        let oversized = format!("[{}]", vec!["{}"; 1024].join(","));
+        let (status, _) = webhook(&state, Some("current"), Body::from(oversized)).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        // a body that never ends is answered as long as it is not read past the limit, the
+        // chunks yield so that the timeout fires when it is
+        let endless = || {
+            let chunks = futures::stream::repeat(()).then(|()| async {
+                tokio::task::yield_now().await;
+                Ok::<_, Infallible>(Bytes::from("{},"))
+            });
+            Body::from_stream(futures::stream::once(async { Ok(Bytes::from("[")) }).chain(chunks))
+        };
+        for (secret, expected) in [
+            (None, StatusCode::UNAUTHORIZED),
+            (Some("current"), StatusCode::PAYLOAD_TOO_LARGE),
+        ] {
+            let call = webhook(&state, secret, endless());
+            let (status, _) = tokio::time::timeout(Duration::from_secs(5), call)
+                .await
+                .expect("the webhook body should not be read in full");
+            assert_eq!(status, expected, "{secret:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fail_webhook_when_database_is_unreachable() {
+        let body = Body::from(r#"[{"signature": "sig"}]"#);
+        let (status, body) = webhook(&offline_state(), Some("current"), body).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"]["kind"], "database");
+    }
+
+    async fn admin(
+        state: &AppState,
+        method: &str,
+        uri: &str,
+        content_type: &str,
+        body: String,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", content_type)
+            .header(auth::API_KEY_HEADER, "admin")
+            .body(Body::from(body))
+            .unwrap();
+        let response = router(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn should_reject_market_mover_calls_without_api_key_or_known_role() {
+        let state = offline_state();
+
+        let (status, body) = get(&state, "/api/v1/market-movers?limit=10&offset=0").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"]["kind"], "unauthorized");
+
+        let mover = serde_json::json!({"walletAddress": ADDRESS, "role": "shrimp"}).to_string();
+        let uri = "/api/v1/market-movers";
+        let (status, body) = admin(&state, "POST", uri, "application/json", mover).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("unknown role shrimp"));
+        let uri = "/api/v1/market-movers/import";
+        let (status, _) = admin(&state, "POST", uri, "text/plain", String::new()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn should_reject_admin_calls_without_api_key() {
+        let state = offline_state();
+
+        for (method, uri) in [
+            ("GET", "/api/v1/admin/jobs"),
+            ("POST", "/api/v1/admin/jobs/market_sentiment/run"),
+            ("GET", "/api/v1/admin/providers/usage"),
+            ("GET", "/api/v1/admin/token-watch/stuck?limit=10&offset=0"),
+            ("POST", &format!("/api/v1/admin/token-watch/{ADDRESS}/retry")),
+        ] {
+            let (status, body) = call(&state, method, uri, Body::empty()).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED, "{uri}");
+            assert_eq!(body["error"]["kind"], "unauthorized", "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    async fn should_not_run_unknown_job() {
+        let uri = "/api/v1/admin/jobs/missing/run";
+        let json = "application/json";
+        let (status, body) = admin(&offline_state(), "POST", uri, json, String::new()).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["kind"], "not_found");
+        assert_eq!(body["error"]["message"], "job missing not found");
+    }
+
+    #[tokio::test]
+    async fn should_reject_invalid_mint_addresses() {
+        let state = offline_state();
+
+        for address in ["not-a-mint", "0OIl", "abc", &format!("{ADDRESS}{ADDRESS}")] {
+            for route in ["details", "details/analytics", "details/distributions"] {
+                let (status, body) = get(&state, &format!("/api/v1/token/{address}/{route}")).await;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{address}/{route}");
+                assert_eq!(body["error"]["kind"], "bad_request");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_fail_database_routes_when_database_is_unreachable() {
+        let state = offline_state();
+
+        for uri in [
+            "/api/v1/mindshare",
+            "/api/v1/token/trending",
+            "/api/v1/vibecheck",
+            "/api/v1/alphamoves?limit=10&offset=0",
+            &format!("/api/v1/token/{ADDRESS}/details"),
+            &format!("/api/v1/token/{ADDRESS}/details/analytics"),
+            &format!("/api/v1/token/{ADDRESS}/details/distributions"),
+        ] {
+            let (status, body) = get(&state, uri).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR, "{uri}");
+            assert_eq!(body["error"]["kind"], "database", "{uri}");
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_mindshare_and_trending_from_trending_provider() {
+        let state = database_state().await;
+        AppState::mind_share(&Arc::new(state.clone()))
+            .await
+            .unwrap();
+
+        let (status, body) = get(&state, "/api/v1/mindshare").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body["response"].as_array().unwrap().is_empty());
+        let (status, body) = get(&state, "/api/v1/token/trending").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body["response"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_search_token_provider() {
+        let state = database_state().await;
+
+        let (status, body) = get(&state, "/api/v1/token?q=arc&limit=10&offset=0").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(!body["response"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_token_details_analytics_and_distributions() {
+        let state = database_state().await;
+
+        let (status, body) = get(&state, &format!("/api/v1/token/{ADDRESS}/details")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"]["tokenAddress"], ADDRESS);
+        let priority: token::WatchPriority =
+            sqlx::query_scalar("SELECT priority FROM token_watch WHERE token_address = $1")
+                .bind(ADDRESS)
+                .fetch_one(&state.pool)
+                .await
+                .unwrap();
+        assert_eq!(priority, token::WatchPriority::Hot);
+
+        let (status, _) = get(
+            &state,
+            &format!("/api/v1/token/{ADDRESS}/details/analytics"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let uri = format!("/api/v1/token/{ADDRESS}/details/distributions");
+        let (status, body) = get(&state, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_not_find_analytics_of_unknown_token() {
+        let state = database_state().await;
+
+        let uri = format!("/api/v1/token/{UNKNOWN_TOKEN}/details/analytics");
+        let (status, _) = get(&state, &uri).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_not_find_details_of_token_unknown_to_provider() {
+        let state = database_state().await;
+
+        let uri = format!("/api/v1/token/{UNKNOWN_TOKEN}/details");
+        let (status, body) = get(&state, &uri).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"]["kind"], "not_found");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_vibecheck_from_volume_sentiment_and_price_providers() {
+        let state = database_state().await;
+        AppState::run(
+            &state.pool,
+            state.volume_sdk.as_ref(),
+            state.fear_and_greed_sdk.as_ref(),
+        )
+        .await
+        .unwrap();
+        AppState::sol_price(&Arc::new(state.clone())).await.unwrap();
+
+        let (status, body) = get(&state, "/api/v1/vibecheck").await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"]["chain"], SOLANA);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_alphamoves_and_accept_webhook() {
+        let state = database_state().await;
+
+        let (status, body) = get(&state, "/api/v1/alphamoves?limit=10&offset=0").await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["response"].is_array());
+
+        let (status, body) = webhook(&state, Some("current"), Body::from("[]")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "Webhook received");
+    }
+
+    async fn count_moves(pool: &PgPool, signature: &str) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM market_movers_transaction WHERE signature = $1")
+            .bind(signature)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_store_webhook_transactions_once_and_replay_them() {
+        let state = database_state().await;
+        let suffix = Utc::now().timestamp_nanos_opt().unwrap();
+        let (whale, signature) = (format!("whale_{suffix}"), format!("sig_{suffix}"));
+        let block_time = 1743465600 + suffix % 1_000_000;
+        sqlx::query("INSERT INTO market_mover (wallet_address, role) VALUES ($1, 'whale')")
+            .bind(&whale)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let transfer = serde_json::json!({
+            "signature": signature,
+            "slot": 1,
+            "timestamp": block_time,
+            "type": "TRANSFER",
+            "nativeTransfers": [],
+            "tokenTransfers": [{
+                "fromUserAccount": "sender",
+                "toUserAccount": whale,
+                "mint": ADDRESS,
+                "tokenAmount": 10.0,
+                "tokenStandard": "Fungible",
+            }],
+        });
+        let unparsed = serde_json::json!({"signature": format!("bad_{suffix}"), "slot": "one"});
+        let body = serde_json::json!([transfer, transfer, unparsed]).to_string();
+
+        // the second call is a retried delivery
+        for _ in 0..2 {
+            let (status, _) = webhook(&state, Some("current"), Body::from(body.clone())).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        assert_eq!(count_moves(&state.pool, &signature).await, 1);
+        let event = |signature: String| {
+            sqlx::query_as::<_, (i32, bool, Option<String>)>(
+                "SELECT attempts, processed_at IS NOT NULL, last_error
+                FROM webhook_events WHERE signature = $1",
+            )
+            .bind(signature)
+            .fetch_one(&state.pool)
+        };
+        assert_eq!(event(signature.clone()).await.unwrap(), (1, true, None));
+        let (attempts, processed, error) = event(format!("bad_{suffix}")).await.unwrap();
+        assert_eq!((attempts, processed), (2, false));
+        assert!(error.is_some());
+
+        sqlx::query("DELETE FROM market_movers_transaction WHERE signature = $1")
+            .bind(&signature)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+        let report = webhook::replay(&state, Some(block_time), Some(block_time + 1))
+            .await
+            .unwrap();
+        assert!(report.events >= 1);
+        assert_eq!(report.failed, 0);
+        assert_eq!(count_moves(&state.pool, &signature).await, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_run_job_on_demand_and_list_its_last_run() {
+        let state = database_state().await;
+        state.jobs.add(IntervalJob::new(
+            "router_test",
+            "0 0 * * * *",
+            Duration::from_secs(1),
+            || async { Err(anyhow::anyhow!("provider down")) },
+        ));
+
+        let json = "application/json";
+        let uri = "/api/v1/admin/jobs/router_test/run";
+        let (status, _) = admin(&state, "POST", uri, json, String::new()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        // The run is recorded once the background task finishes.
+        let mut job = Value::Null;
+        let jobs = "/api/v1/admin/jobs";
+        for _ in 0..100 {
+            let (status, body) = admin(&state, "GET", jobs, json, String::new()).await;
+            assert_eq!(status, StatusCode::OK);
+            job = body["response"][0].clone();
+            if !job["lastOutcome"].is_null() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(job["name"], "router_test");
+        assert_eq!(job["lastOutcome"], "failed");
+        assert_eq!(job["lastError"], "provider down");
+    }
+
+    /// Price provider that is down.
+    struct UnavailablePrice;
+
+    #[async_trait::async_trait]
+    impl PriceSdk for UnavailablePrice {
+        async fn get_price(&self, _token: &str) -> anyhow::Result<crate::thirdparty::TokenData> {
+            anyhow::bail!("birdeye is unavailable")
+        }
+
+        async fn get_prices(
+            &self,
+            _tokens: &[String],
+        ) -> anyhow::Result<HashMap<String, crate::thirdparty::TokenData>> {
+            anyhow::bail!("birdeye is unavailable")
+        }
+
+        async fn get_price_by_time_filter(
+            &self,
+            _token: &str,
+            _filter: TimeFilters,
+        ) -> anyhow::Result<crate::thirdparty::PriceHistory> {
+            anyhow::bail!("birdeye is unavailable")
+        }
+
+        async fn get_ohlcv(
+            &self,
+            _token: &str,
+            _interval: TimeFilters,
+            _time_from: i64,
+            _time_to: i64,
+        ) -> anyhow::Result<Vec<price::Candle>> {
+            anyhow::bail!("birdeye is unavailable")
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_stale_price_while_price_provider_is_down() {
+        let mut state = database_state().await;
+        let metric = Providers::mock()
+            .price_sdk
+            .get_price(SOL_ADDRESS)
+            .await
+            .unwrap();
+        price::store_metric_in_db(&state.pool, &metric, SOL_ADDRESS)
+            .await
+            .unwrap();
+        state.price_sdk = Arc::new(UnavailablePrice);
+
+        let (status, body) = get(&state, &format!("/api/v1/price/{SOL_ADDRESS}")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(body["price"].as_f64().unwrap() > 0.0);
+
+        let uri = format!("/api/v1/price?addresses={SOL_ADDRESS},{ADDRESS}");
+        let (status, body) = get(&state, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        let sol = &body["response"][SOL_ADDRESS];
+        assert_eq!(sol["source"], "database");
+        assert_eq!(sol["stale"], true);
+        assert!(body["response"].get(ADDRESS).is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_cached_candles_while_price_provider_is_down() {
+        let mut state = database_state().await;
+        let from = TimeFilters::OneHour.align(Utc::now().timestamp()) - 48 * 3600;
+        let to = from + 23 * 3600;
+        let uri = format!("/api/v1/token/{SOL_ADDRESS}/candles?interval=1H&from={from}&to={to}");
+
+        let (status, body) = get(&state, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        let fetched = body["response"]["candles"].clone();
+        assert_eq!(fetched.as_array().unwrap().len(), 24);
+
+        state.price_sdk = Arc::new(UnavailablePrice);
+        let (status, body) = get(&state, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"]["candles"], fetched);
+
+        let uri = format!("/api/v1/token/{UNKNOWN_TOKEN}/candles?interval=1H&from={from}&to={to}");
+        let (status, body) = get(&state, &uri).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["kind"], "upstream_unavailable");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_revalidate_cached_trending_tokens() {
+        let state = database_state().await;
+        let request = |etag: Option<&HeaderValue>| {
+            let mut request = Request::builder().uri("/api/v1/token/trending");
+            if let Some(etag) = etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = router(state.clone()).oneshot(request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().contains_key(CACHE_CONTROL));
+        let etag = response.headers()[ETAG].clone();
+
+        let response = router(state.clone()).oneshot(request(Some(&etag))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[ETAG], etag);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_serve_fresh_prices_from_database() {
+        let mut state = database_state().await;
+        let mut metric = Providers::mock()
+            .price_sdk
+            .get_price(SOL_ADDRESS)
+            .await
+            .unwrap();
+        metric.update_unix_time = Utc::now().timestamp();
+        metric.price = 123.5;
+        let address = UNKNOWN_TOKEN.to_string();
+        price::store_metric_in_db(&state.pool, &metric, &address)
+            .await
+            .unwrap();
+        state.price_sdk = Arc::new(UnavailablePrice);
+
+        let (status, body) = get(&state, &format!("/api/v1/price?addresses={address}")).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let price = &body["response"][address.as_str()];
+        assert_eq!(price["price"], 123.5);
+        assert_eq!(price["source"], "database");
+        assert_eq!(price["stale"], false);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_list_and_retry_stuck_tokens() {
+        let state = database_state().await;
+        let address = unique_name("stuck");
+        sqlx::query(
+            "INSERT INTO token_watch (token_address, failure_count, last_error, dead_lettered_at)
+            VALUES ($1, 1000, 'provider down', NOW())",
+        )
+        .bind(&address)
+        .execute(&state.pool)
+        .await
+        .unwrap();
+
+        let json = "application/json";
+        let uri = "/api/v1/admin/token-watch/stuck?limit=1&offset=0";
+        let (status, body) = admin(&state, "GET", uri, json, String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"][0]["tokenAddress"], address.as_str());
+        assert_eq!(body["response"][0]["lastError"], "provider down");
+
+        let uri = format!("/api/v1/admin/token-watch/{address}/retry");
+        let (status, _) = admin(&state, "POST", &uri, json, String::new()).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        let uri = "/api/v1/admin/token-watch/stuck?limit=100&offset=0";
+        let (_, body) = admin(&state, "GET", uri, json, String::new()).await;
+        let stuck = body["response"].as_array().unwrap();
+        assert!(stuck.iter().all(|t| t["tokenAddress"] != address.as_str()));
+
+        let uri = "/api/v1/admin/token-watch/missing/retry";
+        let (status, _) = admin(&state, "POST", uri, json, String::new()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    /// Webhook that cannot be updated.
+    struct UnavailableWebhook;
+
+    #[async_trait::async_trait]
+    impl WebhookSdk for UnavailableWebhook {
+        async fn set_addresses(&self, _addresses: &[String]) -> anyhow::Result<()> {
+            anyhow::bail!("helius is unavailable")
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_manage_market_movers_and_sync_webhook_addresses() {
+        let mut state = database_state().await;
+        let webhook = Arc::new(LocalWebhook::default());
+        state.webhook_sdk = webhook.clone();
+        let nanos = Utc::now().timestamp_nanos_opt().unwrap().to_le_bytes();
+        let wallet = |n: u8| {
+            let mut bytes = [n; 32];
+            bytes[..8].copy_from_slice(&nanos);
+            bs58::encode(bytes).into_string()
+        };
+        let json = "application/json";
+
+        let mover = serde_json::json!({"walletAddress": wallet(1), "role": "kol", "name": "Ansem"});
+        let uri = "/api/v1/market-movers";
+        let (status, body) = admin(&state, "POST", uri, json, mover.to_string()).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["response"]["role"], "KOL");
+        assert!(webhook.addresses().contains(&wallet(1)));
+        let (status, _) = admin(&state, "POST", uri, json, mover.to_string()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+
+        let uri = format!("/api/v1/market-movers/{}", wallet(1));
+        let update = serde_json::json!({"role": "smart_trader"}).to_string();
+        let (status, body) = admin(&state, "PATCH", &uri, json, update.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"]["role"], "smart_trader");
+        assert_eq!(body["response"]["name"], "Ansem");
+        let missing = format!("/api/v1/market-movers/{}", wallet(9));
+        let (status, _) = admin(&state, "PATCH", &missing, json, update).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let csv = format!(
+            "wallet_address,role,name,logo\n{},whale,,\n{},fund,Fund,\n",
+            wallet(1),
+            wallet(2)
+        );
+        let import = "/api/v1/market-movers/import";
+        let (status, body) = admin(&state, "POST", import, "text/csv", csv).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"], serde_json::json!({"created": 1, "updated": 1}));
+        let movers = serde_json::json!([{"walletAddress": wallet(3), "role": "insider"}]);
+        let (status, body) = admin(&state, "POST", import, json, movers.to_string()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["response"]["created"], 1);
+
+        let list = "/api/v1/market-movers?limit=100&offset=0&role=whale";
+        let (status, body) = admin(&state, "GET", list, json, String::new()).await;
+        assert_eq!(status, StatusCode::OK);
+        let listed: Vec<&str> = body["response"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|mover| mover["walletAddress"].as_str().unwrap())
+            .collect();
+        assert!(listed.contains(&wallet(1).as_str()));
+        assert!(!listed.contains(&wallet(2).as_str()));
+
+        let (status, _) = admin(&state, "DELETE", &uri, json, String::new()).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(!webhook.addresses().contains(&wallet(1)));
+        assert!(webhook.addresses().contains(&wallet(3)));
+        let (status, _) = admin(&state, "DELETE", &uri, json, String::new()).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // the registry is left as it was when the webhook cannot follow
+        state.webhook_sdk = Arc::new(UnavailableWebhook);
+        let mover = serde_json::json!({"walletAddress": wallet(4), "role": "whale"});
+        let uri = "/api/v1/market-movers";
+        let (status, body) = admin(&state, "POST", uri, json, mover.to_string()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["kind"], "upstream_unavailable");
+        let registered: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM market_mover WHERE wallet_address = $1")
+                .bind(wallet(4))
+                .fetch_one(&state.pool)
+                .await
+                .unwrap();
+        assert_eq!(registered, 0);
+    }
+}
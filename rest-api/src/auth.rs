@@ -0,0 +1,89 @@
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::fmt;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::app::AppState;
+use crate::error::ApiError;
+
+/// Header carrying the key of the admin calls.
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Values accepted for a credential, any of them authenticates a call. Comma separated in the
+/// configuration so that a new value can be rolled out before the old one is retired.
+#[derive(Clone, Default)]
+pub struct Secrets(Vec<String>);
+
+impl FromStr for Secrets {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(
+            s.split(',')
+                .map(str::trim)
+                .filter(|secret| !secret.is_empty())
+                .map(String::from)
+                .collect(),
+        ))
+    }
+}
+
+impl fmt::Debug for Secrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secrets({} redacted)", self.0.len())
+    }
+}
+
+impl Secrets {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn accepts(&self, value: &str) -> bool {
+        // every secret is compared in full so that the time taken does not tell which one is close
+        self.0.iter().fold(false, |accepted, secret| {
+            constant_time_eq(secret.as_bytes(), value.as_bytes()) | accepted
+        })
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Reject the admin calls without one of the configured keys in their `x-api-key` header.
+pub async fn require_admin(
+    State(app): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let accepted = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| app.admin_config.api_keys.accepts(value));
+    if !accepted {
+        warn!("rejected admin call to {}", request.uri().path());
+        return Err(ApiError::Unauthorized("missing or invalid api key".into()));
+    }
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_accept_any_rotated_secret() {
+        let secrets: Secrets = " current , previous,".parse().unwrap();
+
+        assert!(secrets.accepts("current"));
+        assert!(secrets.accepts("previous"));
+        assert!(!secrets.accepts("curren"));
+        assert!(!secrets.accepts(""));
+        assert!("".parse::<Secrets>().unwrap().is_empty());
+        assert_eq!(format!("{secrets:?}"), "Secrets(2 redacted)");
+    }
+}
@@ -0,0 +1,344 @@
+use std::future::Future;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+
+use axum::body::{Body, Bytes};
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::Response;
+use chrono::Utc;
+use moka::future::Cache;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::CacheConfig;
+use crate::error::ApiError;
+use crate::redis_store::RedisStore;
+
+/// Data a cached response is built from, named after the job refreshing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheGroup {
+    /// `/mindshare` and `/token/trending`, refreshed by the `mindshare` job.
+    Mindshare,
+    /// `/vibecheck`, refreshed by the `market_sentiment` job.
+    MarketSentiment,
+}
+
+impl CacheGroup {
+    fn as_str(self) -> &'static str {
+        match self {
+            CacheGroup::Mindshare => "mindshare",
+            CacheGroup::MarketSentiment => "market_sentiment",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    body: Bytes,
+    etag: String,
+    /// Unix time the response was built at, by this instance or another one.
+    cached_at: i64,
+}
+
+impl CachedResponse {
+    fn age(&self) -> Duration {
+        Duration::from_secs((Utc::now().timestamp() - self.cached_at).max(0) as u64)
+    }
+}
+
+/// A [`CachedResponse`] as stored in Redis.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SharedResponse {
+    body: String,
+    etag: String,
+    cached_at: i64,
+}
+
+/// Serialized responses of the hot read endpoints keyed by path and query, one cache per
+/// [`CacheGroup`] so that a job invalidates only the responses built from its data.
+///
+/// With Redis the responses are shared with the other instances, the local caches only save the
+/// round trip.
+#[derive(Clone)]
+pub struct ResponseCache {
+    mindshare: Cache<String, CachedResponse>,
+    market_sentiment: Cache<String, CachedResponse>,
+    mindshare_ttl: Duration,
+    market_sentiment_ttl: Duration,
+    redis: Option<RedisStore>,
+}
+
+impl ResponseCache {
+    pub fn new(config: &CacheConfig, redis: Option<RedisStore>) -> Self {
+        let cache = |ttl| {
+            Cache::builder()
+                .max_capacity(config.max_entries)
+                .time_to_live(ttl)
+                .build()
+        };
+        let mindshare_ttl = Duration::from_secs(config.mindshare_ttl_secs);
+        let market_sentiment_ttl = Duration::from_secs(config.market_sentiment_ttl_secs);
+        Self {
+            mindshare: cache(mindshare_ttl),
+            market_sentiment: cache(market_sentiment_ttl),
+            mindshare_ttl,
+            market_sentiment_ttl,
+            redis,
+        }
+    }
+
+    fn group(&self, group: CacheGroup) -> (&Cache<String, CachedResponse>, Duration) {
+        match group {
+            CacheGroup::Mindshare => (&self.mindshare, self.mindshare_ttl),
+            CacheGroup::MarketSentiment => (&self.market_sentiment, self.market_sentiment_ttl),
+        }
+    }
+
+    fn shared_key(redis: &RedisStore, group: CacheGroup, key: &str) -> String {
+        redis.key(&format!("cache:{}:{key}", group.as_str()))
+    }
+
+    /// Drop the responses of `group` here and in Redis, called once its job stored fresh data.
+    ///
+    /// The other instances keep their local copies until told through
+    /// [`crate::events::Event::CacheInvalidated`].
+    pub async fn invalidate(&self, group: CacheGroup) {
+        self.invalidate_local(group);
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        if let Err(err) = Self::delete_shared(redis, group).await {
+            warn!(
+                "invalidate shared {} responses failed: {err}",
+                group.as_str()
+            );
+        }
+    }
+
+    /// Drop the responses of `group` kept by this instance.
+    pub fn invalidate_local(&self, group: CacheGroup) {
+        self.group(group).0.invalidate_all();
+    }
+
+    async fn delete_shared(redis: &RedisStore, group: CacheGroup) -> redis::RedisResult<()> {
+        let mut connection = redis.connection();
+        let pattern = Self::shared_key(redis, group, "*");
+        let keys: Vec<String> = {
+            let mut scan = connection.scan_match::<_, String>(pattern).await?;
+            let mut keys = vec![];
+            while let Some(key) = scan.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+        if !keys.is_empty() {
+            connection.del::<_, ()>(keys).await?;
+        }
+        Ok(())
+    }
+
+    async fn lookup(&self, group: CacheGroup, key: &str) -> Option<CachedResponse> {
+        let (cache, ttl) = self.group(group);
+        if let Some(cached) = cache.get(key).await {
+            return Some(cached).filter(|cached| cached.age() < ttl);
+        }
+        let redis = self.redis.as_ref()?;
+        let shared: Option<String> = match redis
+            .connection()
+            .get(Self::shared_key(redis, group, key))
+            .await
+        {
+            Ok(shared) => shared,
+            Err(err) => {
+                warn!("read shared response failed: {err}");
+                None
+            }
+        };
+        let shared: SharedResponse = serde_json::from_str(&shared?).ok()?;
+        let cached = CachedResponse {
+            body: Bytes::from(shared.body),
+            etag: shared.etag,
+            cached_at: shared.cached_at,
+        };
+        cache.insert(key.to_string(), cached.clone()).await;
+        Some(cached).filter(|cached| cached.age() < ttl)
+    }
+
+    async fn store(&self, group: CacheGroup, key: String, cached: &CachedResponse) {
+        let (cache, ttl) = self.group(group);
+        cache.insert(key.clone(), cached.clone()).await;
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let shared = SharedResponse {
+            body: String::from_utf8_lossy(&cached.body).into_owned(),
+            etag: cached.etag.clone(),
+            cached_at: cached.cached_at,
+        };
+        let result: anyhow::Result<()> = async {
+            let value = serde_json::to_string(&shared)?;
+            let key = Self::shared_key(redis, group, &key);
+            redis
+                .connection()
+                .set_ex::<_, _, ()>(key, value, ttl.as_secs())
+                .await?;
+            Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            warn!("share response failed: {err}");
+        }
+    }
+
+    /// Serve the response cached under `key`, built by `build` on a miss. Errors are not cached.
+    ///
+    /// The response carries an `ETag` and a `Cache-Control` max age of the rest of its TTL, a
+    /// request whose `If-None-Match` lists the `ETag` is answered `304 Not Modified`.
+    pub async fn respond<T, F, Fut>(
+        &self,
+        group: CacheGroup,
+        key: String,
+        headers: &HeaderMap,
+        build: F,
+    ) -> Result<Response, ApiError>
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let ttl = self.group(group).1;
+        let cached = match self.lookup(group, &key).await {
+            Some(cached) => cached,
+            None => {
+                let body =
+                    Bytes::from(serde_json::to_vec(&build().await?).map_err(anyhow::Error::from)?);
+                let cached = CachedResponse {
+                    etag: etag(&body),
+                    body,
+                    cached_at: Utc::now().timestamp(),
+                };
+                self.store(group, key, &cached).await;
+                cached
+            }
+        };
+
+        let max_age = ttl.saturating_sub(cached.age()).as_secs();
+        let not_modified = headers
+            .get(IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| etag_matches(value, &cached.etag));
+        let (status, body) = if not_modified {
+            (StatusCode::NOT_MODIFIED, Body::empty())
+        } else {
+            (StatusCode::OK, Body::from(cached.body))
+        };
+        let mut response = Response::builder()
+            .status(status)
+            .header(ETAG, &cached.etag)
+            .header(CACHE_CONTROL, format!("public, max-age={max_age}"));
+        if !not_modified {
+            response = response.header(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        Ok(response.body(body).map_err(anyhow::Error::from)?)
+    }
+}
+
+fn etag(body: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Whether an `If-None-Match` value lists `etag`, weak validators included.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use envconfig::Envconfig;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn respond(cache: &ResponseCache, builds: &AtomicUsize, headers: &HeaderMap) -> Response {
+        cache
+            .respond(
+                CacheGroup::Mindshare,
+                "/mindshare".into(),
+                headers,
+                || async {
+                    builds.fetch_add(1, Ordering::SeqCst);
+                    Ok(vec!["token"])
+                },
+            )
+            .await
+            .unwrap()
+    }
+
+    fn config() -> CacheConfig {
+        CacheConfig::init_from_hashmap(&HashMap::new()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_serve_cached_response_until_invalidated() {
+        let cache = ResponseCache::new(&config(), None);
+        let builds = AtomicUsize::new(0);
+
+        let first = respond(&cache, &builds, &HeaderMap::new()).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers()[ETAG].clone();
+        assert!(first.headers()[CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .starts_with("public, max-age="));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, etag.clone());
+        let revalidated = respond(&cache, &builds, &headers).await;
+        assert_eq!(revalidated.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(revalidated.headers()[ETAG], etag);
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        cache.invalidate(CacheGroup::MarketSentiment).await;
+        respond(&cache, &builds, &HeaderMap::new()).await;
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+
+        cache.invalidate(CacheGroup::Mindshare).await;
+        respond(&cache, &builds, &HeaderMap::new()).await;
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn should_share_responses_through_redis() {
+        let redis = crate::redis_store::test::test_store().await;
+        let first = ResponseCache::new(&config(), Some(redis.clone()));
+        let second = ResponseCache::new(&config(), Some(redis));
+        let builds = AtomicUsize::new(0);
+
+        let built = respond(&first, &builds, &HeaderMap::new()).await;
+        let shared = respond(&second, &builds, &HeaderMap::new()).await;
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+        assert_eq!(shared.headers()[ETAG], built.headers()[ETAG]);
+
+        first.invalidate(CacheGroup::Mindshare).await;
+        second.invalidate_local(CacheGroup::Mindshare);
+        respond(&second, &builds, &HeaderMap::new()).await;
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn should_match_listed_and_weak_etags() {
+        assert!(etag_matches("\"a\", W/\"b\"", "\"b\""));
+        assert!(etag_matches("*", "\"b\""));
+        assert!(!etag_matches("\"a\"", "\"b\""));
+    }
+}
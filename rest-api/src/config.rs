@@ -1,5 +1,8 @@
 use envconfig::Envconfig;
 
+use crate::token::{Cutoffs, DistributionMode};
+use crate::auth::Secrets;
+
 #[derive(Envconfig)]
 pub struct Config {
     #[envconfig(from = "RPC_URL")]
@@ -16,6 +19,20 @@ pub struct BirdeyeConfig {
 
     #[envconfig(from = "BIRDEYE_API_URL")]
     pub base_url: String,
+
+    #[envconfig(from = "BIRDEYE_REQUESTS_PER_SEC", default = "15")]
+    pub requests_per_sec: u32,
+
+    /// Compute units Birdeye calls may spend per minute, 0 for no budget.
+    #[envconfig(from = "BIRDEYE_COMPUTE_UNITS_PER_MINUTE", default = "0")]
+    pub compute_units_per_minute: u32,
+}
+
+#[derive(Envconfig)]
+pub struct ProviderConfig {
+    /// Serve recorded fixtures instead of calling the live providers, no API key is needed.
+    #[envconfig(from = "MOCK_PROVIDERS", default = "false")]
+    pub mock_providers: bool,
 }
 
 #[derive(Envconfig)]
@@ -26,3 +43,272 @@ pub struct DatabaseConfig {
     #[envconfig(from = "RUN_MIGRATION", default = "true")]
     pub run_miration: bool,
 }
+
+#[derive(Envconfig, Clone)]
+pub struct DistributionConfig {
+    #[envconfig(from = "DISTRIBUTION_MODE", default = "usd")]
+    pub mode: DistributionMode,
+
+    #[envconfig(
+        from = "DISTRIBUTION_USD_CUTOFFS",
+        default = "1000,10000,100000,1000000"
+    )]
+    pub usd_cutoffs: Cutoffs,
+
+    #[envconfig(from = "DISTRIBUTION_SUPPLY_CUTOFFS", default = "0.001,0.01,0.1,1")]
+    pub supply_cutoffs: Cutoffs,
+
+    #[envconfig(from = "DISTRIBUTION_MAX_HOLDERS", default = "10000")]
+    pub max_holders: u32,
+
+    #[envconfig(from = "DISTRIBUTION_REFRESH_INTERVAL_SECS", default = "86400")]
+    pub refresh_interval_secs: i64,
+}
+
+/// Cron expressions (with seconds) and timeouts of the background jobs.
+#[derive(Envconfig, Clone)]
+pub struct JobConfig {
+    #[envconfig(from = "JOB_RUN_ON_STARTUP", default = "true")]
+    pub run_on_startup: bool,
+
+    #[envconfig(from = "JOB_MARKET_SENTIMENT_CRON", default = "0 0 * * * *")]
+    pub market_sentiment_cron: String,
+
+    #[envconfig(from = "JOB_MARKET_SENTIMENT_TIMEOUT_SECS", default = "120")]
+    pub market_sentiment_timeout_secs: u64,
+
+    #[envconfig(from = "JOB_SOL_PRICE_HISTORY_CRON", default = "0 0 * * * *")]
+    pub sol_price_history_cron: String,
+
+    #[envconfig(from = "JOB_SOL_PRICE_HISTORY_TIMEOUT_SECS", default = "120")]
+    pub sol_price_history_timeout_secs: u64,
+
+    #[envconfig(from = "JOB_SOL_PRICE_CRON", default = "0 0 * * * *")]
+    pub sol_price_cron: String,
+
+    #[envconfig(from = "JOB_SOL_PRICE_TIMEOUT_SECS", default = "60")]
+    pub sol_price_timeout_secs: u64,
+
+    #[envconfig(from = "JOB_MINDSHARE_CRON", default = "0 0 * * * *")]
+    pub mindshare_cron: String,
+
+    #[envconfig(from = "JOB_MINDSHARE_TIMEOUT_SECS", default = "120")]
+    pub mindshare_timeout_secs: u64,
+
+    #[envconfig(from = "JOB_TOKEN_WATCH_CRON", default = "0 * * * * *")]
+    pub token_watch_cron: String,
+
+    #[envconfig(from = "JOB_TOKEN_WATCH_TIMEOUT_SECS", default = "600")]
+    pub token_watch_timeout_secs: u64,
+
+    #[envconfig(from = "JOB_RETENTION_CRON", default = "0 30 3 * * *")]
+    pub retention_cron: String,
+
+    #[envconfig(from = "JOB_RETENTION_TIMEOUT_SECS", default = "900")]
+    pub retention_timeout_secs: u64,
+}
+
+/// Worker pool of the `token_watch` job.
+#[derive(Envconfig, Clone)]
+pub struct TokenWatchConfig {
+    #[envconfig(from = "TOKEN_WATCH_CONCURRENCY", default = "8")]
+    pub concurrency: usize,
+
+    #[envconfig(from = "TOKEN_WATCH_BATCH_SIZE", default = "50")]
+    pub batch_size: i64,
+
+    #[envconfig(from = "TOKEN_WATCH_CLAIM_LEASE_SECS", default = "900")]
+    pub claim_lease_secs: f64,
+
+    /// Refresh interval of hot tokens, see [`crate::token::WatchPriority`].
+    #[envconfig(from = "TOKEN_WATCH_HOT_INTERVAL_SECS", default = "300")]
+    pub hot_interval_secs: f64,
+
+    #[envconfig(from = "TOKEN_WATCH_ACTIVE_INTERVAL_SECS", default = "3600")]
+    pub active_interval_secs: f64,
+
+    #[envconfig(from = "TOKEN_WATCH_LONG_TAIL_INTERVAL_SECS", default = "86400")]
+    pub long_tail_interval_secs: f64,
+
+    /// Days since its last activity during which a long tail token is still refreshed.
+    #[envconfig(from = "TOKEN_WATCH_LONG_TAIL_ACTIVE_DAYS", default = "30")]
+    pub long_tail_active_days: i32,
+
+    /// How long a trade, a detail view or a trending rank keeps a token hot.
+    #[envconfig(from = "TOKEN_WATCH_HOT_HOLD_SECS", default = "3600")]
+    pub hot_hold_secs: f64,
+
+    /// Delay before retrying a token after its first failure, doubled on every further failure.
+    #[envconfig(from = "TOKEN_WATCH_RETRY_BASE_SECS", default = "60")]
+    pub retry_base_secs: f64,
+
+    #[envconfig(from = "TOKEN_WATCH_RETRY_MAX_SECS", default = "3600")]
+    pub retry_max_secs: f64,
+
+    /// Consecutive failures after which a token is dead-lettered and no longer refreshed.
+    #[envconfig(from = "TOKEN_WATCH_MAX_FAILURES", default = "8")]
+    pub max_failures: i32,
+}
+
+/// Timeouts, rates, retries and circuit breaker of the provider HTTP clients.
+#[derive(Envconfig, Clone)]
+pub struct ProviderHttpConfig {
+    #[envconfig(from = "BIRDEYE_TIMEOUT_SECS", default = "10")]
+    pub birdeye_timeout_secs: u64,
+
+    #[envconfig(from = "MONI_TIMEOUT_SECS", default = "10")]
+    pub moni_timeout_secs: u64,
+
+    #[envconfig(from = "SAFETY_TIMEOUT_SECS", default = "20")]
+    pub safety_timeout_secs: u64,
+
+    #[envconfig(from = "DEFILLAMA_TIMEOUT_SECS", default = "15")]
+    pub defillama_timeout_secs: u64,
+
+    #[envconfig(from = "ALTERNATIVE_TIMEOUT_SECS", default = "10")]
+    pub alternative_timeout_secs: u64,
+
+    #[envconfig(from = "HELIUS_TIMEOUT_SECS", default = "10")]
+    pub helius_timeout_secs: u64,
+
+    /// Calls per second to Moni, each attempt of a retried call counts.
+    #[envconfig(from = "MONI_REQUESTS_PER_SEC", default = "5")]
+    pub moni_requests_per_sec: u32,
+
+    #[envconfig(from = "SAFETY_REQUESTS_PER_SEC", default = "5")]
+    pub safety_requests_per_sec: u32,
+
+    #[envconfig(from = "DEFILLAMA_REQUESTS_PER_SEC", default = "10")]
+    pub defillama_requests_per_sec: u32,
+
+    #[envconfig(from = "PROVIDER_MAX_RETRIES", default = "2")]
+    pub max_retries: u32,
+
+    /// First retry delay, doubled on every further retry unless the provider sent `Retry-After`.
+    #[envconfig(from = "PROVIDER_RETRY_BASE_MS", default = "500")]
+    pub retry_base_ms: u64,
+
+    /// Longest delay worth waiting for, a longer `Retry-After` fails the call instead.
+    #[envconfig(from = "PROVIDER_RETRY_MAX_SECS", default = "10")]
+    pub retry_max_secs: u64,
+
+    /// Consecutive failed calls after which a provider is considered down.
+    #[envconfig(from = "PROVIDER_BREAKER_FAILURES", default = "5")]
+    pub breaker_failures: u32,
+
+    #[envconfig(from = "PROVIDER_BREAKER_COOLDOWN_SECS", default = "30")]
+    pub breaker_cooldown_secs: u64,
+}
+
+/// Freshness of the prices served from `token_metrics`.
+#[derive(Envconfig, Clone)]
+pub struct PriceConfig {
+    /// Age after which a stored price is refreshed from Birdeye, it is still served as stale
+    /// while Birdeye is unavailable.
+    #[envconfig(from = "PRICE_MAX_AGE_SECS", default = "60")]
+    pub max_age_secs: i64,
+
+    #[envconfig(from = "PRICE_MAX_ADDRESSES", default = "50")]
+    pub max_addresses: usize,
+
+    /// Farthest a stored price may be from a trade to value it, Birdeye candles are used
+    /// otherwise.
+    #[envconfig(from = "PRICE_HISTORICAL_MAX_DISTANCE_SECS", default = "3600")]
+    pub historical_max_distance_secs: i64,
+
+    /// Moves of a webhook call valued at once.
+    #[envconfig(from = "PRICE_VALUATION_CONCURRENCY", default = "8")]
+    pub valuation_concurrency: usize,
+}
+
+/// Windows of the `retention` job, see [`crate::retention`].
+#[derive(Envconfig, Clone)]
+pub struct RetentionConfig {
+    /// Days of raw `token_metrics` rows kept, older ones are folded into the hourly and daily
+    /// summaries.
+    #[envconfig(from = "RETENTION_RAW_METRICS_DAYS", default = "7")]
+    pub raw_metrics_days: i64,
+
+    /// Days of hourly summaries kept, daily summaries are kept forever.
+    #[envconfig(from = "RETENTION_HOURLY_SUMMARY_DAYS", default = "365")]
+    pub hourly_summary_days: i64,
+
+    /// Days of cached 1m, 5m and 15m candles kept, they are fetched again when requested.
+    #[envconfig(from = "RETENTION_INTRADAY_CANDLE_DAYS", default = "30")]
+    pub intraday_candle_days: i64,
+
+    /// Days without activity after which a token leaves `token_watch`.
+    #[envconfig(from = "RETENTION_TOKEN_WATCH_INACTIVE_DAYS", default = "90")]
+    pub token_watch_inactive_days: i64,
+
+    /// Only report what would be removed.
+    #[envconfig(from = "RETENTION_DRY_RUN", default = "false")]
+    pub dry_run: bool,
+}
+
+/// Response cache of the hot read endpoints, see [`crate::cache::ResponseCache`].
+#[derive(Envconfig, Clone)]
+pub struct CacheConfig {
+    /// Responses built from the `mindshare` job data, it also invalidates them when it completes.
+    #[envconfig(from = "CACHE_MINDSHARE_TTL_SECS", default = "3600")]
+    pub mindshare_ttl_secs: u64,
+
+    /// Responses built from the `market_sentiment` job data.
+    #[envconfig(from = "CACHE_MARKET_SENTIMENT_TTL_SECS", default = "3600")]
+    pub market_sentiment_ttl_secs: u64,
+
+    /// Most responses kept per cache.
+    #[envconfig(from = "CACHE_MAX_ENTRIES", default = "1000")]
+    pub max_entries: u64,
+}
+
+/// Redis shared by the instances of a deployment, see [`crate::redis_store::RedisStore`].
+#[derive(Envconfig, Clone)]
+pub struct RedisConfig {
+    /// `redis://` URL, the response cache, the Birdeye budget and the events stay local to the
+    /// instance when it is not set.
+    #[envconfig(from = "REDIS_URL")]
+    pub url: Option<String>,
+
+    /// Prefix of the keys and channels, lets deployments share a Redis.
+    #[envconfig(from = "REDIS_KEY_PREFIX", default = "munki")]
+    pub key_prefix: String,
+}
+
+/// Authentication and limits of `POST /webhook`, see [`crate::webhook::authenticate`].
+#[derive(Envconfig, Clone)]
+pub struct WebhookConfig {
+    /// Values accepted in the `Authorization` header set by Helius, comma separated so that a new
+    /// secret can be rolled out before the old one is retired. Every call is rejected without one.
+    #[envconfig(from = "HELIUS_WEBHOOK_SECRETS", default = "")]
+    pub secrets: Secrets,
+
+    /// Largest accepted body, Helius delivers up to 100 enhanced transactions per call.
+    #[envconfig(from = "WEBHOOK_MAX_PAYLOAD_BYTES", default = "2097152")]
+    pub max_payload_bytes: usize,
+}
+
+/// Keys of the admin routes, see [`crate::auth::require_admin`].
+#[derive(Envconfig, Clone)]
+pub struct AdminConfig {
+    /// Values accepted in the `x-api-key` header, comma separated. Every call is rejected
+    /// without one.
+    #[envconfig(from = "ADMIN_API_KEYS", default = "")]
+    pub api_keys: Secrets,
+}
+
+/// Helius webhook streaming the transactions of the market movers, see
+/// [`crate::thirdparty::helius::HeliusClient`].
+#[derive(Envconfig)]
+pub struct HeliusConfig {
+    /// The market movers are not synced to the webhook when the key or the webhook id is not
+    /// set.
+    #[envconfig(from = "HELIUS_API_KEY")]
+    pub api_key: Option<String>,
+
+    #[envconfig(from = "HELIUS_WEBHOOK_ID")]
+    pub webhook_id: Option<String>,
+
+    #[envconfig(from = "HELIUS_API_URL", default = "https://api.helius.xyz")]
+    pub base_url: String,
+}
@@ -0,0 +1,135 @@
+use axum::extract::rejection::QueryRejection;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use chrono::Utc;
+use thiserror::Error;
+use tracing::error;
+use validator::ValidationErrors;
+
+use crate::response::{ErrorBody, HttpErrorResponse};
+
+/// Error of an HTTP handler, rendered as an [`HttpErrorResponse`].
+///
+/// Database and internal errors are logged and answered with a generic message, the other
+/// variants carry a message meant for the client.
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    UpstreamUnavailable(String),
+    #[error("database query failed")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Validation(#[from] ValidationErrors),
+    #[error("internal error")]
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    /// Database errors keep their kind when they come wrapped in an `anyhow::Error`.
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<sqlx::Error>() {
+            Ok(error) => ApiError::Database(error),
+            Err(error) => ApiError::Internal(error),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) | ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::UpstreamUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Database(_) | ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::UpstreamUnavailable(_) => "upstream_unavailable",
+            ApiError::Database(_) => "database",
+            ApiError::Validation(_) => "validation",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match &self {
+            ApiError::Database(e) => error!("database error: {e}"),
+            ApiError::Internal(e) => error!("internal error: {e:#}"),
+            _ => {}
+        }
+        let status = self.status();
+        let body = HttpErrorResponse {
+            code: status.as_u16() as i32,
+            error: ErrorBody {
+                kind: self.kind(),
+                message: self.to_string(),
+            },
+            last_updated: Utc::now().timestamp(),
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+
+    async fn render(error: ApiError) -> (StatusCode, Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn should_render_error_envelope() {
+        let (status, body) = render(ApiError::NotFound("token abc not found".into())).await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["code"], 404);
+        assert_eq!(body["error"]["kind"], "not_found");
+        assert_eq!(body["error"]["message"], "token abc not found");
+        assert!(body["last_updated"].as_i64().unwrap() > 0);
+    }
+
+    #[tokio::test]
+    async fn should_not_leak_database_errors() {
+        let (status, body) = render(ApiError::from(sqlx::Error::PoolTimedOut)).await;
+
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"]["kind"], "database");
+        assert_eq!(body["error"]["message"], "database query failed");
+
+        let wrapped = anyhow::Error::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(ApiError::from(wrapped).kind(), "database");
+        let other = anyhow::anyhow!("boom");
+        assert_eq!(ApiError::from(other).kind(), "internal");
+    }
+}
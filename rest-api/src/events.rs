@@ -0,0 +1,188 @@
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+use crate::cache::CacheGroup;
+use crate::redis_store::RedisStore;
+
+/// Channel the events are published on, namespaced by the Redis prefix.
+const CHANNEL: &str = "events";
+
+/// Something that happened on one instance and that the others may act on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// The responses of `group` are stale, their job stored fresh data.
+    #[serde(rename_all = "camelCase")]
+    CacheInvalidated { group: CacheGroup },
+    /// A transaction of a market mover was received by the webhook.
+    #[serde(rename_all = "camelCase")]
+    MarketMoverTransaction {
+        signature: String,
+        token_address: String,
+        wallet_address: String,
+        transaction_type: String,
+        amount: f64,
+        counter_asset: Option<String>,
+        counter_amount: Option<f64>,
+        value_usd: Option<f64>,
+        block_time: i64,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    origin: String,
+    event: Event,
+}
+
+/// Events of the deployment, delivered to the subscribers of this instance and, with Redis, to
+/// those of the other instances through [`EventBus::forward_remote`].
+#[derive(Clone)]
+pub struct EventBus {
+    origin: String,
+    sender: broadcast::Sender<Event>,
+    redis: Option<RedisStore>,
+}
+
+impl EventBus {
+    pub fn new(redis: Option<RedisStore>) -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        let origin = format!(
+            "{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        );
+        Self {
+            origin,
+            sender,
+            redis,
+        }
+    }
+
+    /// The events published from now on, lagging subscribers miss the oldest ones.
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+
+    /// Deliver `event` to the local subscribers and publish it to the other instances.
+    pub async fn publish(&self, event: Event) {
+        let Some(redis) = &self.redis else {
+            let _ = self.sender.send(event);
+            return;
+        };
+        let envelope = Envelope {
+            origin: self.origin.clone(),
+            event,
+        };
+        let result: anyhow::Result<()> = async {
+            let message = serde_json::to_string(&envelope)?;
+            redis::cmd("PUBLISH")
+                .arg(redis.key(CHANNEL))
+                .arg(message)
+                .exec_async(&mut redis.connection())
+                .await?;
+            Ok(())
+        }
+        .await;
+        if let Err(err) = result {
+            warn!("publish event failed: {err}");
+        }
+        let _ = self.sender.send(envelope.event);
+    }
+
+    /// Deliver the events published by the other instances to the local subscribers, returns
+    /// when the subscription is lost. Does nothing without Redis.
+    pub async fn forward_remote(&self) -> anyhow::Result<()> {
+        let Some(redis) = &self.redis else {
+            return Ok(());
+        };
+        let mut pubsub = redis.subscribe(CHANNEL).await?;
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let envelope = message
+                .get_payload::<String>()
+                .map_err(anyhow::Error::from)
+                .and_then(|payload| Ok(serde_json::from_str::<Envelope>(&payload)?));
+            match envelope {
+                Ok(envelope) if envelope.origin != self.origin => {
+                    let _ = self.sender.send(envelope.event);
+                }
+                Ok(_) => {}
+                Err(err) => error!("invalid event on {}: {err}", redis.key(CHANNEL)),
+            }
+        }
+        anyhow::bail!("subscription to {} closed", redis.key(CHANNEL))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn transaction() -> Event {
+        Event::MarketMoverTransaction {
+            signature: "sig".into(),
+            token_address: "token".into(),
+            wallet_address: "wallet".into(),
+            transaction_type: "buy".into(),
+            amount: 1.5,
+            counter_asset: Some(crate::app::SOL_ADDRESS.into()),
+            counter_amount: Some(0.25),
+            value_usd: Some(37.5),
+            block_time: 1743465600,
+        }
+    }
+
+    #[test]
+    fn should_tag_events() {
+        let json = serde_json::to_value(Event::CacheInvalidated {
+            group: CacheGroup::MarketSentiment,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"type": "cacheInvalidated", "group": "market_sentiment"})
+        );
+        let json = serde_json::to_string(&transaction()).unwrap();
+        assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), transaction());
+    }
+
+    #[tokio::test]
+    async fn should_deliver_local_events_without_redis() {
+        let bus = EventBus::new(None);
+        let mut events = bus.subscribe();
+        bus.publish(transaction()).await;
+        assert_eq!(events.recv().await.unwrap(), transaction());
+        bus.forward_remote().await.unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn should_forward_events_of_other_instances() {
+        let redis = crate::redis_store::test::test_store().await;
+        let publisher = EventBus::new(Some(redis.clone()));
+        let follower = EventBus::new(Some(redis));
+        let mut published = publisher.subscribe();
+        let mut followed = follower.subscribe();
+        let forward = {
+            let (publisher, follower) = (publisher.clone(), follower.clone());
+            tokio::spawn(async move {
+                let _ = tokio::join!(publisher.forward_remote(), follower.forward_remote());
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        publisher.publish(transaction()).await;
+
+        let received = tokio::time::timeout(Duration::from_secs(5), followed.recv()).await;
+        assert_eq!(received.unwrap().unwrap(), transaction());
+        assert_eq!(published.recv().await.unwrap(), transaction());
+        // the publisher skips its own message coming back from Redis
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(published.try_recv().is_err());
+        forward.abort();
+    }
+}
@@ -1,5 +1,6 @@
 pub mod route;
 
+use async_trait::async_trait;
 use chrono::{DateTime, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, PgPool};
@@ -12,7 +13,8 @@ pub struct FearAndGreedApiResponse {
     pub chain: String,
 }
 
-pub trait FearAndGreedSdk {
+#[async_trait]
+pub trait FearAndGreedSdk: Send + Sync {
     async fn get_fear_and_greed(
         &self,
         limit: i8,
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use crate::app::{AppState, SOLANA, SOL_ADDRESS};
+use crate::cache::CacheGroup;
+use crate::error::ApiError;
 use crate::fearandgreed::{
     current_gear_and_fear_history, gear_and_fear_history_by_unixtime, upsert_fear_and_greed,
     FearAndGreedHistory,
@@ -10,7 +12,8 @@ use crate::response::HttpResponse;
 use crate::thirdparty::TokenData;
 use crate::{price, time_util, volume};
 use axum::extract::{Query, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, Uri};
+use axum::response::Response;
 use axum::Json;
 use bigdecimal::ToPrimitive;
 use chrono::{Duration, Timelike, Utc};
@@ -27,6 +30,7 @@ pub struct FearAndGreedQuery {
 //     31
 // }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FearAndGreedResponse {
@@ -34,10 +38,11 @@ pub struct FearAndGreedResponse {
     pub token_prices: HashMap<String, TokenData>,
 }
 
+#[allow(dead_code)]
 pub async fn get_fear_and_greed(
     State(app): State<AppState>,
     Query(_params): Query<FearAndGreedQuery>,
-) -> Result<Json<HttpResponse<FearAndGreedResponse>>, (StatusCode, String)> {
+) -> Result<Json<HttpResponse<FearAndGreedResponse>>, ApiError> {
     let now = Utc::now();
     let start_of_a_day = time_util::get_start_of_day(now);
     let last_week = start_of_a_day - Duration::days(7);
@@ -55,53 +60,32 @@ pub async fn get_fear_and_greed(
     .map(|items| items.into_iter().map(FearAndGreed::from).collect())
     .map_err(|e| {
         error!("gear_and_fear_history_by_unixtime error: {e}");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("get_fear_and_greed failed: {e}"),
-        )
+        ApiError::from(e)
     })?;
 
-    let Some(solana_price) = price::get_metric_from_db(&app.pool, SOL_ADDRESS)
+    let solana_price = price::get_metric_from_db(&app.pool, SOL_ADDRESS)
         .await
         .map_err(|e| {
-            error!("BirdEye get_price failed error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "get price failed".to_string(),
-            )
+            error!("get_metric_from_db error: {e}");
+            ApiError::from(e)
         })?
-    else {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "get price failed".to_string(),
-        ));
-    };
-
-    let Some(sol_vol) =
-        volume::get_volume_by_date(&app.pool, time_util::get_start_of_day(now), SOLANA)
-            .await
-            .map_err(|e| {
-                error!("BirdEye get_price failed error: {e}");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "get price failed".to_string(),
-                )
-            })?
-    else {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "get price failed".to_string(),
-        ));
-    };
+        .ok_or_else(|| ApiError::UpstreamUnavailable("SOL price is not available yet".into()))?;
+
+    let sol_vol = volume::get_volume_by_date(&app.pool, time_util::get_start_of_day(now), SOLANA)
+        .await
+        .map_err(|e| {
+            error!("get_volume_by_date error: {e}");
+            ApiError::from(e)
+        })?
+        .ok_or_else(|| {
+            ApiError::UpstreamUnavailable("DefiLlama SOL volume is not available yet".into())
+        })?;
 
     if let Some(resp) = current_gear_and_fear_history(&app.pool, start_of_a_day.timestamp())
         .await
         .map_err(|e| {
             error!("current_gear_and_fear_history error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Alternative get_fear_and_greed failed: {e}"),
-            )
+            ApiError::from(e)
         })?
     {
         info!("get data from database");
@@ -121,16 +105,12 @@ pub async fn get_fear_and_greed(
         let Some(resp) = get_fear_and_greed_by_timestamp(&app.pool, Utc::now().timestamp())
             .await
             .map_err(|e| {
-                error!("get_fear_and_greed error: {e}");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Alternative get_fear_and_greed failed: {e}"),
-                )
+                error!("get_fear_and_greed_by_timestamp error: {e}");
+                ApiError::from(e)
             })?
         else {
-            return Err((
-                StatusCode::NOT_FOUND,
-                "Alternative get_fear_and_greed not found".to_string(),
+            return Err(ApiError::NotFound(
+                "Alternative fear and greed index not found".into(),
             ));
         };
         let a = resp.value;
@@ -162,19 +142,16 @@ pub async fn get_fear_and_greed(
         let last_week = start_of_today - Duration::days(7);
 
         let prices =
-            price::get_token_prices_between(&app.pool, SOL_ADDRESS, last_week, start_of_today)
+            price::get_daily_prices_between(&app.pool, SOL_ADDRESS, last_week, start_of_today)
                 .await
                 .map_err(|e| {
-                    error!("BirdEye get_price failed error: {e}");
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "get price failed".to_string(),
-                    )
+                    error!("get_daily_prices_between error: {e}");
+                    ApiError::from(e)
                 })?;
         let mut changes = Vec::new();
         for i in 1..prices.len() {
-            let prev_price = prices[i - 1].price.to_f64().unwrap_or_default();
-            let curr_price = prices[i].price.to_f64().unwrap_or_default();
+            let prev_price = prices[i - 1].to_f64().unwrap_or_default();
+            let curr_price = prices[i].to_f64().unwrap_or_default();
             let change = ((curr_price - prev_price) / prev_price) * 100.0; // % change
             changes.push(change);
         }
@@ -236,7 +213,20 @@ pub struct VibeCheckResponse {
 pub async fn vibe_check(
     State(app): State<AppState>,
     Query(_params): Query<FearAndGreedQuery>,
-) -> Result<Json<HttpResponse<VibeCheckResponse>>, (StatusCode, String)> {
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    app.cache
+        .respond(
+            CacheGroup::MarketSentiment,
+            uri.to_string(),
+            &headers,
+            || vibe_check_response(&app),
+        )
+        .await
+}
+
+async fn vibe_check_response(app: &AppState) -> Result<HttpResponse<VibeCheckResponse>, ApiError> {
     let now = Utc::now();
     let start_of_a_day = time_util::get_start_of_day(now);
     let last_week = start_of_a_day - Duration::days(7);
@@ -254,53 +244,32 @@ pub async fn vibe_check(
     .map(|items| items.into_iter().map(FearAndGreed::from).collect())
     .map_err(|e| {
         error!("gear_and_fear_history_by_unixtime error: {e}");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("get_fear_and_greed failed: {e}"),
-        )
+        ApiError::from(e)
     })?;
 
-    let Some(solana_price) = price::get_metric_from_db(&app.pool, SOL_ADDRESS)
+    let solana_price = price::get_metric_from_db(&app.pool, SOL_ADDRESS)
         .await
         .map_err(|e| {
-            error!("BirdEye get_price failed error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "get price failed".to_string(),
-            )
+            error!("get_metric_from_db error: {e}");
+            ApiError::from(e)
         })?
-    else {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "get price failed".to_string(),
-        ));
-    };
-
-    let Some(sol_vol) =
-        volume::get_volume_by_date(&app.pool, time_util::get_start_of_day(now), SOLANA)
-            .await
-            .map_err(|e| {
-                error!("BirdEye get_price failed error: {e}");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    "get price failed".to_string(),
-                )
-            })?
-    else {
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "get price failed".to_string(),
-        ));
-    };
+        .ok_or_else(|| ApiError::UpstreamUnavailable("SOL price is not available yet".into()))?;
+
+    let sol_vol = volume::get_volume_by_date(&app.pool, time_util::get_start_of_day(now), SOLANA)
+        .await
+        .map_err(|e| {
+            error!("get_volume_by_date error: {e}");
+            ApiError::from(e)
+        })?
+        .ok_or_else(|| {
+            ApiError::UpstreamUnavailable("DefiLlama SOL volume is not available yet".into())
+        })?;
 
     if let Some(resp) = current_gear_and_fear_history(&app.pool, start_of_a_day.timestamp())
         .await
         .map_err(|e| {
             error!("current_gear_and_fear_history error: {e}");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Alternative get_fear_and_greed failed: {e}"),
-            )
+            ApiError::from(e)
         })?
     {
         info!("get data from database");
@@ -311,7 +280,7 @@ pub async fn vibe_check(
             chain,
         } = FearAndGreed::from(resp);
 
-        Ok(Json(HttpResponse {
+        Ok(HttpResponse {
             code: 200,
             response: VibeCheckResponse {
                 fear_and_greed: histories,
@@ -322,22 +291,18 @@ pub async fn vibe_check(
                 chain,
             },
             last_updated: Utc::now().timestamp(),
-        }))
+        })
     } else {
         info!("refresh data");
         let Some(resp) = get_fear_and_greed_by_timestamp(&app.pool, Utc::now().timestamp())
             .await
             .map_err(|e| {
-                error!("get_fear_and_greed error: {e}");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Alternative get_fear_and_greed failed: {e}"),
-                )
+                error!("get_fear_and_greed_by_timestamp error: {e}");
+                ApiError::from(e)
             })?
         else {
-            return Err((
-                StatusCode::NOT_FOUND,
-                "Alternative get_fear_and_greed not found".to_string(),
+            return Err(ApiError::NotFound(
+                "Alternative fear and greed index not found".into(),
             ));
         };
         let a = resp.value;
@@ -369,19 +334,16 @@ pub async fn vibe_check(
         let last_week = start_of_today - Duration::days(7);
 
         let prices =
-            price::get_token_prices_between(&app.pool, SOL_ADDRESS, last_week, start_of_today)
+            price::get_daily_prices_between(&app.pool, SOL_ADDRESS, last_week, start_of_today)
                 .await
                 .map_err(|e| {
-                    error!("BirdEye get_price failed error: {e}");
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        "get price failed".to_string(),
-                    )
+                    error!("get_daily_prices_between error: {e}");
+                    ApiError::from(e)
                 })?;
         let mut changes = Vec::new();
         for i in 1..prices.len() {
-            let prev_price = prices[i - 1].price.to_f64().unwrap_or_default();
-            let curr_price = prices[i].price.to_f64().unwrap_or_default();
+            let prev_price = prices[i - 1].to_f64().unwrap_or_default();
+            let curr_price = prices[i].to_f64().unwrap_or_default();
             let change = ((curr_price - prev_price) / prev_price) * 100.0; // % change
             changes.push(change);
         }
@@ -417,7 +379,7 @@ pub async fn vibe_check(
             error!("upsert fear and greed error: {e}");
         }
 
-        Ok(Json(HttpResponse {
+        Ok(HttpResponse {
             code: 200,
             response: VibeCheckResponse {
                 fear_and_greed: histories,
@@ -428,7 +390,7 @@ pub async fn vibe_check(
                 chain: greed.chain,
             },
             last_updated: Utc::now().timestamp(),
-        }))
+        })
     }
 }
 
@@ -0,0 +1,54 @@
+use sqlx::pool::PoolConnection;
+use sqlx::{PgPool, Postgres};
+use tracing::error;
+
+/// First key of the job advisory locks, keeps them apart from other advisory lock users.
+const JOB_LOCK_NAMESPACE: i32 = 0x6a6f62;
+
+/// Session level `pg_try_advisory_lock` on the job name, held on a dedicated connection so only
+/// one instance runs a job at a time. Postgres drops the lock if the instance dies.
+pub struct JobLock {
+    conn: Option<PoolConnection<Postgres>>,
+    name: String,
+}
+
+impl JobLock {
+    /// `None` if another session holds the lock.
+    pub async fn try_acquire(pool: &PgPool, name: &str) -> Result<Option<Self>, sqlx::Error> {
+        let mut conn = pool.acquire().await?;
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1, hashtext($2))")
+            .bind(JOB_LOCK_NAMESPACE)
+            .bind(name)
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok(locked.then(|| Self {
+            conn: Some(conn),
+            name: name.to_string(),
+        }))
+    }
+
+    pub async fn release(mut self) {
+        let Some(mut conn) = self.conn.take() else {
+            return;
+        };
+        if let Err(e) = sqlx::query("SELECT pg_advisory_unlock($1, hashtext($2))")
+            .bind(JOB_LOCK_NAMESPACE)
+            .bind(&self.name)
+            .execute(&mut *conn)
+            .await
+        {
+            error!("release lock of job {} failed: {e}", self.name);
+            // Close the session instead of returning it to the pool with the lock held.
+            drop(conn.detach());
+        }
+    }
+}
+
+impl Drop for JobLock {
+    fn drop(&mut self) {
+        // Not released, e.g. the run was cancelled: closing the session releases the lock.
+        if let Some(conn) = self.conn.take() {
+            drop(conn.detach());
+        }
+    }
+}
@@ -1,20 +1,494 @@
+mod lock;
+pub mod route;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tracing::{error, info, warn};
+
+use lock::JobLock;
+
+/// Scheduled runs started by another instance this close to the fire time count as the same tick.
+const CLOCK_SKEW_TOLERANCE: chrono::Duration = chrono::Duration::seconds(5);
+
+type JobFuture = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+type JobTask = Arc<dyn Fn() -> JobFuture + Send + Sync>;
+
+/// A named task fired on a cron expression, see [`JobManager`].
 pub struct IntervalJob {
+    pub name: String,
+    pub cron_tab: String,
+    pub timeout: Duration,
+    pub run_on_startup: bool,
+    task: JobTask,
+}
+
+impl IntervalJob {
+    pub fn new<F, Fut>(name: &str, cron_tab: &str, timeout: Duration, task: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            cron_tab: cron_tab.to_string(),
+            timeout,
+            run_on_startup: true,
+            task: Arc::new(move || Box::pin(task())),
+        }
+    }
+
+    pub fn run_on_startup(mut self, run_on_startup: bool) -> Self {
+        self.run_on_startup = run_on_startup;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobOutcome {
+    Succeeded,
+    Failed(String),
+    /// The previous run was still in progress.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobStatus {
+    pub name: String,
     pub cron_tab: String,
+    pub running: bool,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<i64>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Outcome of a finished run as stored in `job_runs`.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
+pub enum RunOutcome {
+    Succeeded,
+    Failed,
 }
 
+struct RegisteredJob {
+    job: IntervalJob,
+    running: AtomicBool,
+    status: Mutex<JobStatus>,
+}
+
+impl RegisteredJob {
+    /// Run the job unless it is already running here or, with a database, on another instance.
+    /// Scheduled runs carry their fire time and are also skipped when another instance already
+    /// ran that tick.
+    async fn execute(
+        &self,
+        pool: Option<&PgPool>,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> JobOutcome {
+        if self.running.swap(true, Ordering::AcqRel) {
+            warn!("job {} is still running, skipping", self.job.name);
+            return JobOutcome::Skipped;
+        }
+        let outcome = match pool {
+            Some(pool) => self.execute_as_leader(pool, scheduled_at).await,
+            None => self.run_task(None).await,
+        };
+        self.running.store(false, Ordering::Release);
+        outcome
+    }
+
+    async fn execute_as_leader(
+        &self,
+        pool: &PgPool,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> JobOutcome {
+        let name = &self.job.name;
+        let lock = match JobLock::try_acquire(pool, name).await {
+            Ok(Some(lock)) => lock,
+            Ok(None) => {
+                info!("job {name} is running on another instance, skipping");
+                return JobOutcome::Skipped;
+            }
+            Err(e) => {
+                error!("acquire lock of job {name} failed: {e}");
+                return JobOutcome::Failed(format!("acquire job lock: {e}"));
+            }
+        };
+        if let Some(scheduled_at) = scheduled_at {
+            match ran_since(pool, name, scheduled_at - CLOCK_SKEW_TOLERANCE).await {
+                Ok(true) => {
+                    info!("job {name} already ran on another instance, skipping");
+                    lock.release().await;
+                    return JobOutcome::Skipped;
+                }
+                Ok(false) => {}
+                Err(e) => error!("check runs of job {name} failed: {e}"),
+            }
+        }
+        let outcome = self.run_task(Some(pool)).await;
+        lock.release().await;
+        outcome
+    }
+
+    async fn run_task(&self, pool: Option<&PgPool>) -> JobOutcome {
+        let name = &self.job.name;
+        let started_at = Utc::now();
+        self.update(|status| {
+            status.running = true;
+            status.last_started_at = Some(started_at);
+        });
+
+        let outcome = match tokio::time::timeout(self.job.timeout, (self.job.task)()).await {
+            Ok(Ok(())) => JobOutcome::Succeeded,
+            Ok(Err(e)) => JobOutcome::Failed(format!("{e:#}")),
+            Err(_) => JobOutcome::Failed(format!("timed out after {:?}", self.job.timeout)),
+        };
+        let finished_at = Utc::now();
+        let duration_ms = (finished_at - started_at).num_milliseconds();
+        self.update(|status| {
+            status.running = false;
+            status.last_duration_ms = Some(duration_ms);
+            match &outcome {
+                JobOutcome::Succeeded => status.last_success_at = Some(Utc::now()),
+                JobOutcome::Failed(e) => {
+                    status.last_failure_at = Some(Utc::now());
+                    status.last_error = Some(e.clone());
+                }
+                JobOutcome::Skipped => {}
+            }
+        });
+
+        match &outcome {
+            JobOutcome::Failed(e) => error!("job {name} failed: {e}"),
+            _ => info!("job {name} finished in {duration_ms}ms"),
+        }
+        if let Some(pool) = pool {
+            let (run_outcome, error) = match &outcome {
+                JobOutcome::Failed(e) => (RunOutcome::Failed, Some(e.as_str())),
+                _ => (RunOutcome::Succeeded, None),
+            };
+            if let Err(e) = insert_job_run(
+                pool,
+                name,
+                started_at,
+                finished_at,
+                duration_ms,
+                run_outcome,
+                error,
+            )
+            .await
+            {
+                error!("record run of job {name} failed: {e}");
+            }
+        }
+        outcome
+    }
+
+    fn update(&self, f: impl FnOnce(&mut JobStatus)) {
+        f(&mut self.status.lock().unwrap());
+    }
+}
+
+/// Registry of the background jobs. Each job runs on its own cron expression, optionally once on
+/// startup, never overlaps with itself and is cancelled once it exceeds its timeout.
+#[derive(Clone, Default)]
 pub struct JobManager {
-    jobs: Vec<IntervalJob>
+    jobs: Arc<RwLock<Vec<Arc<RegisteredJob>>>>,
+    pool: Option<PgPool>,
 }
 
 impl JobManager {
-    pub async fn new() -> Self {
-        Self { jobs: vec![] }
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record every finished run in the `job_runs` table and elect a single instance to run each
+    /// job, see [`JobLock`].
+    pub fn with_history(mut self, pool: PgPool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    pub fn add(&self, job: IntervalJob) {
+        let status = JobStatus {
+            name: job.name.clone(),
+            cron_tab: job.cron_tab.clone(),
+            ..Default::default()
+        };
+        self.jobs.write().unwrap().push(Arc::new(RegisteredJob {
+            job,
+            running: AtomicBool::new(false),
+            status: Mutex::new(status),
+        }));
     }
 
-    pub fn add(&mut self, job: IntervalJob) {
-        self.jobs.push(job);
+    /// Schedule every registered job and spawn the startup runs.
+    pub async fn start(&self) -> anyhow::Result<JobScheduler> {
+        let sched = JobScheduler::new().await?;
+        let jobs = self.jobs.read().unwrap().clone();
+        for registered in jobs {
+            let scheduled = registered.clone();
+            let pool = self.pool.clone();
+            sched
+                .add(Job::new_async(
+                    registered.job.cron_tab.as_str(),
+                    move |_uuid, _l| {
+                        let job = scheduled.clone();
+                        let pool = pool.clone();
+                        Box::pin(async move {
+                            job.execute(pool.as_ref(), Some(Utc::now())).await;
+                        })
+                    },
+                )?)
+                .await?;
+            if registered.job.run_on_startup {
+                let pool = self.pool.clone();
+                tokio::spawn(async move {
+                    registered.execute(pool.as_ref(), Some(Utc::now())).await;
+                });
+            }
+        }
+        sched.start().await?;
+        Ok(sched)
     }
 
-    pub fn start(&self) {
+    /// Run a job immediately, `None` if no job has this name.
+    pub async fn run(&self, name: &str) -> Option<JobOutcome> {
+        let job = self.find(name)?;
+        Some(job.execute(self.pool.as_ref(), None).await)
+    }
+
+    /// Start a run of a job in the background, `false` if no job has this name.
+    pub fn trigger(&self, name: &str) -> bool {
+        let Some(job) = self.find(name) else {
+            return false;
+        };
+        let pool = self.pool.clone();
+        tokio::spawn(async move {
+            job.execute(pool.as_ref(), None).await;
+        });
+        true
+    }
+
+    pub fn status(&self, name: &str) -> Option<JobStatus> {
+        self.find(name)
+            .map(|job| job.status.lock().unwrap().clone())
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .map(|job| job.status.lock().unwrap().clone())
+            .collect()
+    }
+
+    fn find(&self, name: &str) -> Option<Arc<RegisteredJob>> {
+        self.jobs
+            .read()
+            .unwrap()
+            .iter()
+            .find(|job| job.job.name == name)
+            .cloned()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_job_run(
+    pool: &PgPool,
+    job_name: &str,
+    started_at: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    duration_ms: i64,
+    outcome: RunOutcome,
+    error: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO job_runs (job_name, started_at, finished_at, duration_ms, outcome, error)
+        VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(job_name)
+    .bind(started_at)
+    .bind(finished_at)
+    .bind(duration_ms)
+    .bind(outcome)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn ran_since(
+    pool: &PgPool,
+    job_name: &str,
+    since: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM job_runs WHERE job_name = $1 AND started_at >= $2)",
+    )
+    .bind(job_name)
+    .bind(since)
+    .fetch_one(pool)
+    .await
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRunSummary {
+    pub job_name: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub outcome: RunOutcome,
+    pub error: Option<String>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
+
+/// Latest run of every job that ran at least once, with its last success and failure times.
+pub async fn query_last_job_runs(pool: &PgPool) -> Result<Vec<JobRunSummary>, sqlx::Error> {
+    sqlx::query_as::<_, JobRunSummary>(
+        "SELECT r.job_name, r.started_at, r.finished_at, r.duration_ms, r.outcome, r.error,
+               s.last_success_at, s.last_failure_at
+        FROM (SELECT DISTINCT ON (job_name) job_name, started_at, finished_at, duration_ms, outcome, error
+              FROM job_runs
+              ORDER BY job_name, started_at DESC) r
+        JOIN (SELECT job_name,
+                     MAX(finished_at) FILTER (WHERE outcome = 'succeeded') AS last_success_at,
+                     MAX(finished_at) FILTER (WHERE outcome = 'failed')    AS last_failure_at
+              FROM job_runs
+              GROUP BY job_name) s USING (job_name)",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{test_pool, unique_name};
+    use anyhow::anyhow;
+
+    const EVERY_HOUR: &str = "0 0 * * * *";
+
+    #[tokio::test]
+    async fn should_record_success_and_failure() {
+        let manager = JobManager::new();
+        manager.add(IntervalJob::new(
+            "ok",
+            EVERY_HOUR,
+            Duration::from_secs(1),
+            || async { Ok(()) },
+        ));
+        manager.add(IntervalJob::new(
+            "broken",
+            EVERY_HOUR,
+            Duration::from_secs(1),
+            || async { Err(anyhow!("upstream down")) },
+        ));
+
+        assert_eq!(manager.run("ok").await, Some(JobOutcome::Succeeded));
+        assert_eq!(
+            manager.run("broken").await,
+            Some(JobOutcome::Failed("upstream down".into()))
+        );
+        assert_eq!(manager.run("missing").await, None);
+
+        let ok = manager.status("ok").unwrap();
+        assert!(ok.last_success_at.is_some() && ok.last_failure_at.is_none());
+        let broken = manager.status("broken").unwrap();
+        assert!(broken.last_success_at.is_none() && broken.last_failure_at.is_some());
+        assert_eq!(broken.last_error.as_deref(), Some("upstream down"));
+        assert!(!broken.running);
+    }
+
+    #[tokio::test]
+    async fn should_fail_job_exceeding_timeout() {
+        let manager = JobManager::new();
+        manager.add(IntervalJob::new(
+            "slow",
+            EVERY_HOUR,
+            Duration::from_millis(10),
+            || async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            },
+        ));
+
+        let outcome = manager.run("slow").await.unwrap();
+
+        assert!(matches!(outcome, JobOutcome::Failed(e) if e.starts_with("timed out")));
+        assert!(!manager.status("slow").unwrap().running);
+    }
+
+    #[tokio::test]
+    async fn should_skip_overlapping_run() {
+        let manager = JobManager::new();
+        manager.add(IntervalJob::new(
+            "busy",
+            EVERY_HOUR,
+            Duration::from_secs(1),
+            || async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok(())
+            },
+        ));
+
+        let (first, second) = tokio::join!(manager.run("busy"), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            manager.run("busy").await
+        });
+
+        assert_eq!(first, Some(JobOutcome::Succeeded));
+        assert_eq!(second, Some(JobOutcome::Skipped));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_run_job_on_a_single_instance() {
+        let pool = test_pool().await;
+        let name = unique_name("leader");
+        let instance = || {
+            let manager = JobManager::new().with_history(pool.clone());
+            manager.add(IntervalJob::new(
+                &name,
+                EVERY_HOUR,
+                Duration::from_secs(1),
+                || async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    Ok(())
+                },
+            ));
+            manager
+        };
+        let (first, second) = (instance(), instance());
+
+        let (a, b) = tokio::join!(first.run(&name), async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            second.run(&name).await
+        });
+        assert_eq!(a, Some(JobOutcome::Succeeded));
+        assert_eq!(b, Some(JobOutcome::Skipped));
+
+        // The same tick fired late on another instance is skipped, a manual run is not.
+        let tick = Utc::now() - chrono::Duration::seconds(1);
+        let job = second.find(&name).unwrap();
+        assert_eq!(
+            job.execute(Some(&pool), Some(tick)).await,
+            JobOutcome::Skipped
+        );
+        assert_eq!(second.run(&name).await, Some(JobOutcome::Succeeded));
     }
 }
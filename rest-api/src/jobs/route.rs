@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::app::AppState;
+use crate::error::ApiError;
+use crate::jobs::{query_last_job_runs, JobRunSummary, JobStatus, RunOutcome};
+use crate::response::HttpResponse;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobResponse {
+    pub name: String,
+    pub cron_tab: String,
+    pub running: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<i64>,
+    pub last_outcome: Option<RunOutcome>,
+    pub last_error: Option<String>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_failure_at: Option<DateTime<Utc>>,
+}
+
+impl JobResponse {
+    fn new(status: JobStatus, last_run: Option<JobRunSummary>) -> Self {
+        Self {
+            name: status.name,
+            cron_tab: status.cron_tab,
+            running: status.running,
+            last_run_at: last_run.as_ref().map(|r| r.started_at),
+            last_duration_ms: last_run.as_ref().map(|r| r.duration_ms),
+            last_outcome: last_run.as_ref().map(|r| r.outcome),
+            last_success_at: last_run.as_ref().and_then(|r| r.last_success_at),
+            last_failure_at: last_run.as_ref().and_then(|r| r.last_failure_at),
+            last_error: last_run.and_then(|r| r.error),
+        }
+    }
+}
+
+pub async fn list_jobs(
+    State(app): State<AppState>,
+) -> Result<Json<HttpResponse<Vec<JobResponse>>>, ApiError> {
+    let mut runs: HashMap<String, JobRunSummary> = query_last_job_runs(&app.pool)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch job runs: {e}");
+            ApiError::from(e)
+        })?
+        .into_iter()
+        .map(|run| (run.job_name.clone(), run))
+        .collect();
+    let jobs = app
+        .jobs
+        .statuses()
+        .into_iter()
+        .map(|status| {
+            let last_run = runs.remove(&status.name);
+            JobResponse::new(status, last_run)
+        })
+        .collect();
+    Ok(Json(HttpResponse {
+        code: 200,
+        response: jobs,
+        last_updated: Utc::now().timestamp(),
+    }))
+}
+
+pub async fn run_job(
+    State(app): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<(StatusCode, Json<HttpResponse<JobStatus>>), ApiError> {
+    let status = app
+        .jobs
+        .status(&name)
+        .ok_or_else(|| ApiError::NotFound(format!("job {name} not found")))?;
+    if status.running {
+        return Err(ApiError::Conflict(format!("job {name} is already running")));
+    }
+    app.jobs.trigger(&name);
+    info!("job {name} triggered on demand");
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(HttpResponse {
+            code: 202,
+            response: status,
+            last_updated: Utc::now().timestamp(),
+        }),
+    ))
+}
@@ -0,0 +1,347 @@
+pub mod route;
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::error;
+use validator::Validate;
+
+use crate::error::ApiError;
+use crate::thirdparty::helius::WebhookSdk;
+use crate::token::validate_address;
+
+/// First key of the advisory lock serializing the registry changes, so that the addresses sent
+/// to the webhook are those of the last change.
+const REGISTRY_LOCK_NAMESPACE: i32 = 0x6d6d76;
+
+/// Group of a market mover, the `alphaGroup` of its moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoverRole {
+    Whale,
+    Kol,
+    SmartTrader,
+    Fund,
+    Insider,
+    Developer,
+}
+
+const ROLES: [MoverRole; 6] = [
+    MoverRole::Whale,
+    MoverRole::Kol,
+    MoverRole::SmartTrader,
+    MoverRole::Fund,
+    MoverRole::Insider,
+    MoverRole::Developer,
+];
+
+impl MoverRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MoverRole::Whale => "whale",
+            MoverRole::Kol => "KOL",
+            MoverRole::SmartTrader => "smart_trader",
+            MoverRole::Fund => "fund",
+            MoverRole::Insider => "insider",
+            MoverRole::Developer => "developer",
+        }
+    }
+}
+
+impl FromStr for MoverRole {
+    type Err = ApiError;
+
+    /// Case insensitive, `kol` is stored as `KOL`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ROLES
+            .into_iter()
+            .find(|role| role.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "unknown role {s}, expected one of {}",
+                    ROLES.map(MoverRole::as_str).join(", ")
+                ))
+            })
+    }
+}
+
+/// A row of `market_mover`. Rows inserted by hand may have a role outside [`MoverRole`].
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketMover {
+    pub wallet_address: String,
+    pub role: String,
+    pub name: Option<String>,
+    pub logo: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A market mover to create or import. CSV imports name the columns `wallet_address`, `role`,
+/// `name` and `logo`.
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct NewMarketMover {
+    #[serde(alias = "wallet_address")]
+    pub wallet_address: String,
+    pub role: String,
+    #[validate(length(max = 256))]
+    pub name: Option<String>,
+    #[validate(length(max = 255))]
+    pub logo: Option<String>,
+}
+
+impl NewMarketMover {
+    /// Validate the mover and parse its role.
+    pub fn check(&self) -> Result<MoverRole, ApiError> {
+        self.validate()?;
+        validate_address(&self.wallet_address, "wallet")?;
+        self.role.parse()
+    }
+}
+
+/// Changes to a market mover, the fields left out are kept.
+#[derive(Debug, Deserialize, Validate)]
+pub struct MarketMoverUpdate {
+    pub role: Option<String>,
+    #[validate(length(max = 256))]
+    pub name: Option<String>,
+    #[validate(length(max = 255))]
+    pub logo: Option<String>,
+}
+
+/// Market movers created and updated by an import.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ImportReport {
+    pub created: u64,
+    pub updated: u64,
+}
+
+/// Validate the movers of an import and parse their roles, errors name the 1-based row.
+pub fn check_import(
+    movers: Vec<NewMarketMover>,
+) -> Result<Vec<(NewMarketMover, MoverRole)>, ApiError> {
+    let mut wallets = HashSet::new();
+    movers
+        .into_iter()
+        .enumerate()
+        .map(|(i, mover)| {
+            let row = |e: ApiError| ApiError::BadRequest(format!("row {}: {e}", i + 1));
+            let role = mover.check().map_err(row)?;
+            if !wallets.insert(mover.wallet_address.clone()) {
+                let duplicate = format!("{} is repeated", mover.wallet_address);
+                return Err(row(ApiError::BadRequest(duplicate)));
+            }
+            Ok((mover, role))
+        })
+        .collect()
+}
+
+/// Market movers of a CSV with a header row.
+pub fn parse_csv(body: &str) -> Result<Vec<NewMarketMover>, ApiError> {
+    csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_bytes())
+        .deserialize()
+        .enumerate()
+        .map(|(i, row)| row.map_err(|e| ApiError::BadRequest(format!("row {}: {e}", i + 1))))
+        .collect()
+}
+
+pub async fn list_market_movers(
+    pool: &PgPool,
+    role: Option<MoverRole>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<MarketMover>, i64), sqlx::Error> {
+    let role = role.map(MoverRole::as_str);
+    let movers = sqlx::query_as(
+        "SELECT wallet_address, role, name, logo, created_at FROM market_mover
+        WHERE $1::VARCHAR IS NULL OR role = $1
+        ORDER BY created_at DESC, wallet_address
+        LIMIT $2 OFFSET $3",
+    )
+    .bind(role)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    let total = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM market_mover WHERE $1::VARCHAR IS NULL OR role = $1",
+    )
+    .bind(role)
+    .fetch_one(pool)
+    .await?;
+    Ok((movers, total))
+}
+
+/// Start a change of the registry, waiting for the other changes to be committed.
+pub async fn begin_change(pool: &PgPool) -> Result<Transaction<'static, Postgres>, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("SELECT pg_advisory_xact_lock($1, 0)")
+        .bind(REGISTRY_LOCK_NAMESPACE)
+        .execute(&mut *tx)
+        .await?;
+    Ok(tx)
+}
+
+/// Send the addresses of the registry changed by `tx` to the webhook, then commit. The change is
+/// rolled back when the webhook cannot be updated so that both keep the same addresses.
+pub async fn commit_synced(
+    mut tx: Transaction<'static, Postgres>,
+    webhook: &dyn WebhookSdk,
+) -> Result<(), ApiError> {
+    let addresses: Vec<String> =
+        sqlx::query_scalar("SELECT wallet_address FROM market_mover ORDER BY wallet_address")
+            .fetch_all(&mut *tx)
+            .await?;
+    if let Err(e) = webhook.set_addresses(&addresses).await {
+        error!("Failed to sync webhook addresses: {e:#}");
+        return Err(ApiError::UpstreamUnavailable(format!(
+            "webhook addresses could not be synced: {e}"
+        )));
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Insert `mover`, `None` if its wallet is already registered.
+pub async fn insert_market_mover(
+    tx: &mut Transaction<'_, Postgres>,
+    mover: &NewMarketMover,
+    role: MoverRole,
+) -> Result<Option<MarketMover>, sqlx::Error> {
+    sqlx::query_as(
+        "INSERT INTO market_mover (wallet_address, role, name, logo) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (wallet_address) DO NOTHING
+        RETURNING wallet_address, role, name, logo, created_at",
+    )
+    .bind(&mover.wallet_address)
+    .bind(role.as_str())
+    .bind(&mover.name)
+    .bind(&mover.logo)
+    .fetch_optional(&mut **tx)
+    .await
+}
+
+/// Apply `update` to the mover of `wallet_address`, `None` if it is not registered.
+pub async fn update_market_mover(
+    tx: &mut Transaction<'_, Postgres>,
+    wallet_address: &str,
+    role: Option<MoverRole>,
+    update: &MarketMoverUpdate,
+) -> Result<Option<MarketMover>, sqlx::Error> {
+    sqlx::query_as(
+        "UPDATE market_mover
+        SET role = COALESCE($2, role), name = COALESCE($3, name), logo = COALESCE($4, logo)
+        WHERE wallet_address = $1
+        RETURNING wallet_address, role, name, logo, created_at",
+    )
+    .bind(wallet_address)
+    .bind(role.map(MoverRole::as_str))
+    .bind(&update.name)
+    .bind(&update.logo)
+    .fetch_optional(&mut **tx)
+    .await
+}
+
+/// Delete the mover of `wallet_address`, its moves are kept. `false` if it is not registered.
+pub async fn delete_market_mover(
+    tx: &mut Transaction<'_, Postgres>,
+    wallet_address: &str,
+) -> Result<bool, sqlx::Error> {
+    let deleted = sqlx::query("DELETE FROM market_mover WHERE wallet_address = $1")
+        .bind(wallet_address)
+        .execute(&mut **tx)
+        .await?
+        .rows_affected();
+    Ok(deleted > 0)
+}
+
+/// Insert `movers`, replacing the role, name and logo of those already registered.
+pub async fn import_market_movers(
+    tx: &mut Transaction<'_, Postgres>,
+    movers: &[(NewMarketMover, MoverRole)],
+) -> Result<ImportReport, sqlx::Error> {
+    let created: Vec<bool> = sqlx::query_scalar(
+        "INSERT INTO market_mover AS m (wallet_address, role, name, logo)
+        SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[])
+        ON CONFLICT (wallet_address) DO UPDATE
+        SET role = EXCLUDED.role, name = EXCLUDED.name, logo = EXCLUDED.logo
+        RETURNING m.xmax = 0",
+    )
+    .bind(
+        movers
+            .iter()
+            .map(|(m, _)| &m.wallet_address)
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        movers
+            .iter()
+            .map(|(_, role)| role.as_str())
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        movers
+            .iter()
+            .map(|(m, _)| m.name.as_deref())
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        movers
+            .iter()
+            .map(|(m, _)| m.logo.as_deref())
+            .collect::<Vec<_>>(),
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    let inserted = created.iter().filter(|created| **created).count() as u64;
+    Ok(ImportReport {
+        created: inserted,
+        updated: created.len() as u64 - inserted,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const WALLET: &str = "5DxD5ViWjvRZEkxQEaJHZw2sBsso6xoXx3wGFNKgXUzE";
+
+    #[test]
+    fn should_parse_roles_case_insensitively() {
+        assert_eq!("kol".parse::<MoverRole>().unwrap(), MoverRole::Kol);
+        assert_eq!(
+            " smart_trader".parse::<MoverRole>().unwrap(),
+            MoverRole::SmartTrader
+        );
+        let unknown = "shrimp".parse::<MoverRole>().unwrap_err();
+        assert_eq!(
+            unknown.to_string(),
+            "unknown role shrimp, \
+            expected one of whale, KOL, smart_trader, fund, insider, developer"
+        );
+    }
+
+    #[test]
+    fn should_read_csv_import_and_name_invalid_rows() {
+        let csv = format!(
+            "wallet_address,role,name,logo\n\
+            {WALLET}, KOL ,Ansem,\n\
+            {WALLET},whale,,\n\
+            not-a-wallet,whale,,\n"
+        );
+        let movers = parse_csv(&csv).unwrap();
+        assert_eq!(movers.len(), 3);
+        assert_eq!(movers[0].name.as_deref(), Some("Ansem"));
+        assert_eq!(movers[0].logo, None);
+
+        let error = check_import(movers).unwrap_err();
+        assert_eq!(error.to_string(), format!("row 2: {WALLET} is repeated"));
+
+        let error = parse_csv("wallet_address,name\nabc,def\n").unwrap_err();
+        assert!(error.to_string().starts_with("row 1: "), "{error}");
+    }
+}
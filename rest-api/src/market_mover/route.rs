@@ -0,0 +1,135 @@
+use axum::extract::{Path, Query, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use chrono::Utc;
+use serde::Deserialize;
+use tracing::info;
+use validator::Validate;
+
+use crate::app::AppState;
+use crate::error::ApiError;
+use crate::market_mover::{self, ImportReport, MarketMover, MarketMoverUpdate, NewMarketMover};
+use crate::response::{HttpPaginationResponse, HttpResponse};
+
+#[derive(Deserialize, Validate)]
+pub struct MarketMoverQuery {
+    #[validate(range(min = 1, max = 100))]
+    pub limit: i64,
+    #[validate(range(min = 0))]
+    pub offset: i64,
+    pub role: Option<String>,
+}
+
+pub async fn list_market_movers(
+    State(app): State<AppState>,
+    Query(query): Query<MarketMoverQuery>,
+) -> Result<Json<HttpPaginationResponse<Vec<MarketMover>>>, ApiError> {
+    query.validate()?;
+    let role = query.role.as_deref().map(str::parse).transpose()?;
+
+    let (movers, total) =
+        market_mover::list_market_movers(&app.pool, role, query.limit, query.offset).await?;
+    Ok(Json(HttpPaginationResponse {
+        code: 200,
+        response: movers,
+        last_updated: Utc::now().timestamp(),
+        total,
+    }))
+}
+
+pub async fn create_market_mover(
+    State(app): State<AppState>,
+    Json(mover): Json<NewMarketMover>,
+) -> Result<(StatusCode, Json<HttpResponse<MarketMover>>), ApiError> {
+    let role = mover.check()?;
+
+    let mut tx = market_mover::begin_change(&app.pool).await?;
+    let created = market_mover::insert_market_mover(&mut tx, &mover, role)
+        .await?
+        .ok_or_else(|| {
+            ApiError::Conflict(format!(
+                "{} is already a market mover",
+                mover.wallet_address
+            ))
+        })?;
+    market_mover::commit_synced(tx, app.webhook_sdk.as_ref()).await?;
+    info!("Market mover {} created", created.wallet_address);
+    Ok((
+        StatusCode::CREATED,
+        Json(HttpResponse {
+            code: 201,
+            response: created,
+            last_updated: Utc::now().timestamp(),
+        }),
+    ))
+}
+
+pub async fn update_market_mover(
+    State(app): State<AppState>,
+    Path(wallet_address): Path<String>,
+    Json(update): Json<MarketMoverUpdate>,
+) -> Result<Json<HttpResponse<MarketMover>>, ApiError> {
+    update.validate()?;
+    let role = update.role.as_deref().map(str::parse).transpose()?;
+
+    let mut tx = market_mover::begin_change(&app.pool).await?;
+    let updated = market_mover::update_market_mover(&mut tx, &wallet_address, role, &update)
+        .await?
+        .ok_or_else(|| ApiError::NotFound(format!("{wallet_address} is not a market mover")))?;
+    market_mover::commit_synced(tx, app.webhook_sdk.as_ref()).await?;
+    info!("Market mover {wallet_address} updated");
+    Ok(Json(HttpResponse {
+        code: 200,
+        response: updated,
+        last_updated: Utc::now().timestamp(),
+    }))
+}
+
+pub async fn delete_market_mover(
+    State(app): State<AppState>,
+    Path(wallet_address): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let mut tx = market_mover::begin_change(&app.pool).await?;
+    if !market_mover::delete_market_mover(&mut tx, &wallet_address).await? {
+        return Err(ApiError::NotFound(format!(
+            "{wallet_address} is not a market mover"
+        )));
+    }
+    market_mover::commit_synced(tx, app.webhook_sdk.as_ref()).await?;
+    info!("Market mover {wallet_address} deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Create or update the market movers of a `text/csv` body or of a JSON array, all of them or
+/// none.
+pub async fn import_market_movers(
+    State(app): State<AppState>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<HttpResponse<ImportReport>>, ApiError> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let movers = if content_type.starts_with("text/csv") {
+        market_mover::parse_csv(&body)?
+    } else if content_type.starts_with("application/json") {
+        serde_json::from_str(&body).map_err(|e| ApiError::BadRequest(e.to_string()))?
+    } else {
+        return Err(ApiError::BadRequest(
+            "expected a text/csv or application/json body".into(),
+        ));
+    };
+    let movers = market_mover::check_import(movers)?;
+
+    let mut tx = market_mover::begin_change(&app.pool).await?;
+    let report = market_mover::import_market_movers(&mut tx, &movers).await?;
+    market_mover::commit_synced(tx, app.webhook_sdk.as_ref()).await?;
+    info!("Market movers imported {report:?}");
+    Ok(Json(HttpResponse {
+        code: 200,
+        response: report,
+        last_updated: Utc::now().timestamp(),
+    }))
+}
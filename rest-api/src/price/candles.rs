@@ -0,0 +1,227 @@
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{PgPool, QueryBuilder};
+use tracing::{error, warn};
+
+use super::{PriceSdk, TimeFilters};
+
+/// OHLCV candle starting at `unix_time`.
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Candle {
+    pub unix_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// Candles of `token_address` starting between `from` and `to`, oldest first.
+///
+/// Closed candles are cached in `token_candles` and only the part of the range outside of the
+/// cached span is asked from the provider, the open candle is always fetched again. Cached
+/// candles are still served when the provider fails, and the provider alone when the database
+/// is unreachable.
+pub async fn fetch_candles<T: PriceSdk + ?Sized>(
+    pool: &PgPool,
+    client: &T,
+    token_address: &str,
+    interval: TimeFilters,
+    from: i64,
+    to: i64,
+) -> anyhow::Result<Vec<Candle>> {
+    let (from, to) = (interval.align(from), interval.align(to));
+    let last_closed = interval.align(Utc::now().timestamp()) - interval.as_secs();
+    let covered = match query_candle_coverage(pool, token_address, interval).await {
+        Ok(covered) => covered,
+        Err(e) => {
+            error!("query_candle_coverage {token_address} {e}");
+            return client.get_ohlcv(token_address, interval, from, to).await;
+        }
+    };
+
+    if let Some((fetch_from, fetch_to)) = missing_span(covered, from, to, interval.as_secs()) {
+        match client
+            .get_ohlcv(token_address, interval, fetch_from, fetch_to)
+            .await
+        {
+            Ok(candles) => {
+                upsert_candles(pool, token_address, interval, &candles).await?;
+                let fetched = (fetch_from, fetch_to.min(last_closed));
+                if fetched.0 <= fetched.1 {
+                    let covered = merge_span(covered, fetched, interval.as_secs());
+                    upsert_candle_coverage(pool, token_address, interval, covered).await?;
+                }
+            }
+            Err(e) => {
+                let cached = query_candles(pool, token_address, interval, from, to).await?;
+                if cached.is_empty() {
+                    return Err(e);
+                }
+                warn!("serving cached candles of {token_address}: {e}");
+                return Ok(cached);
+            }
+        }
+    }
+    Ok(query_candles(pool, token_address, interval, from, to).await?)
+}
+
+/// Part of `[from, to]` outside of the `covered` span that has to be fetched, kept adjacent to
+/// the covered span so that both can be merged.
+fn missing_span(covered: Option<(i64, i64)>, from: i64, to: i64, step: i64) -> Option<(i64, i64)> {
+    let Some((covered_from, covered_to)) = covered else {
+        return Some((from, to));
+    };
+    match (from < covered_from, to > covered_to) {
+        (false, false) => None,
+        (false, true) if from <= covered_to + step => Some((from.max(covered_to + step), to)),
+        (true, false) if to >= covered_from - step => Some((from, to.min(covered_from - step))),
+        _ => Some((from, to)),
+    }
+}
+
+/// Covered span after fetching `fetched`, which replaces `covered` when they don't touch.
+fn merge_span(covered: Option<(i64, i64)>, fetched: (i64, i64), step: i64) -> (i64, i64) {
+    match covered {
+        Some((from, to)) if fetched.0 <= to + step && fetched.1 >= from - step => {
+            (from.min(fetched.0), to.max(fetched.1))
+        }
+        _ => fetched,
+    }
+}
+
+async fn query_candle_coverage(
+    pool: &PgPool,
+    token_address: &str,
+    interval: TimeFilters,
+) -> Result<Option<(i64, i64)>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT covered_from, covered_to FROM token_candle_coverage
+        WHERE token_address = $1 AND resolution = $2",
+    )
+    .bind(token_address)
+    .bind(interval.as_query_param())
+    .fetch_optional(pool)
+    .await
+}
+
+async fn upsert_candle_coverage(
+    pool: &PgPool,
+    token_address: &str,
+    interval: TimeFilters,
+    (covered_from, covered_to): (i64, i64),
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO token_candle_coverage (token_address, resolution, covered_from, covered_to)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (token_address, resolution) DO UPDATE SET
+            covered_from = EXCLUDED.covered_from,
+            covered_to = EXCLUDED.covered_to,
+            updated_at = NOW()",
+    )
+    .bind(token_address)
+    .bind(interval.as_query_param())
+    .bind(covered_from)
+    .bind(covered_to)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn upsert_candles(
+    pool: &PgPool,
+    token_address: &str,
+    interval: TimeFilters,
+    candles: &[Candle],
+) -> Result<(), sqlx::Error> {
+    if candles.is_empty() {
+        return Ok(());
+    }
+    let mut qb = QueryBuilder::new(
+        "INSERT INTO token_candles
+        (token_address, resolution, unix_time, open, high, low, close, volume) ",
+    );
+    qb.push_values(candles, |mut b, candle| {
+        b.push_bind(token_address)
+            .push_bind(interval.as_query_param())
+            .push_bind(candle.unix_time)
+            .push_bind(candle.open)
+            .push_bind(candle.high)
+            .push_bind(candle.low)
+            .push_bind(candle.close)
+            .push_bind(candle.volume);
+    });
+    qb.push(
+        " ON CONFLICT (token_address, resolution, unix_time) DO UPDATE SET
+            open = EXCLUDED.open,
+            high = EXCLUDED.high,
+            low = EXCLUDED.low,
+            close = EXCLUDED.close,
+            volume = EXCLUDED.volume,
+            updated_at = NOW()",
+    );
+    qb.build().execute(pool).await?;
+    Ok(())
+}
+
+pub async fn query_candles(
+    pool: &PgPool,
+    token_address: &str,
+    interval: TimeFilters,
+    from: i64,
+    to: i64,
+) -> Result<Vec<Candle>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT unix_time, open, high, low, close, volume FROM token_candles
+        WHERE token_address = $1 AND resolution = $2 AND unix_time BETWEEN $3 AND $4
+        ORDER BY unix_time",
+    )
+    .bind(token_address)
+    .bind(interval.as_query_param())
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_fetch_only_what_is_not_covered() {
+        let step = 60;
+        assert_eq!(missing_span(None, 0, 600, step), Some((0, 600)));
+        assert_eq!(missing_span(Some((0, 600)), 60, 540, step), None);
+        // newer candles right after the covered span
+        assert_eq!(
+            missing_span(Some((0, 600)), 300, 900, step),
+            Some((660, 900))
+        );
+        // older candles right before it
+        assert_eq!(missing_span(Some((600, 900)), 0, 700, step), Some((0, 540)));
+        // a range around or away from the covered span is fetched whole
+        assert_eq!(missing_span(Some((300, 600)), 0, 900, step), Some((0, 900)));
+        assert_eq!(
+            missing_span(Some((0, 60)), 600, 900, step),
+            Some((600, 900))
+        );
+    }
+
+    #[test]
+    fn should_merge_adjacent_spans_only() {
+        let step = 60;
+        assert_eq!(merge_span(None, (0, 600), step), (0, 600));
+        assert_eq!(merge_span(Some((0, 600)), (660, 900), step), (0, 900));
+        assert_eq!(merge_span(Some((600, 900)), (0, 540), step), (0, 900));
+        assert_eq!(merge_span(Some((0, 60)), (600, 900), step), (600, 900));
+    }
+
+    #[test]
+    fn should_align_to_interval() {
+        assert_eq!(TimeFilters::FifteenMinutes.align(1743466501), 1743466500);
+        assert_eq!(TimeFilters::OneDay.align(1743466501), 1743465600);
+        assert_eq!(TimeFilters::OneHour.align(1743465600), 1743465600);
+    }
+}
@@ -1,6 +1,7 @@
-use bigdecimal::{BigDecimal, FromPrimitive};
+use crate::price::{fetch_candles, PriceSdk, PriceSource, TimeFilters};
 use crate::thirdparty::birdeye;
 use crate::thirdparty::PriceHistory;
+use bigdecimal::{BigDecimal, FromPrimitive};
 use chrono::DateTime;
 use chrono::Timelike;
 use chrono::Utc;
@@ -43,26 +44,86 @@ pub async fn insert_token_prices(
     Ok(())
 }
 
-pub async fn get_token_prices_between(
+/// Closing prices of the daily rollups of `address` from `start` to `end`, oldest first.
+pub async fn get_daily_prices_between(
     pool: &PgPool,
     address: &str,
     start: DateTime<Utc>,
     end: DateTime<Utc>,
-) -> Result<Vec<TokenPrice>, sqlx::Error> {
-    let prices = sqlx::query_as::<_, TokenPrice>(
+) -> Result<Vec<BigDecimal>, sqlx::Error> {
+    sqlx::query_scalar(
         r#"
-        SELECT address, price, unixtime, recorded_at
-        FROM token_prices
-        WHERE address = $1 AND recorded_at BETWEEN $2 AND $3
+        SELECT close
+        FROM token_prices_daily
+        WHERE address = $1 AND bucket BETWEEN $2 AND $3
+        ORDER BY bucket
         "#,
     )
     .bind(address)
-    .bind(start)
-    .bind(end)
+    .bind(start.timestamp())
+    .bind(end.timestamp())
     .fetch_all(pool)
-    .await?;
+    .await
+}
+
+/// Stored price of `address` nearest to `unix_time`, from the price histories and the metrics,
+/// if one is at most `max_distance_secs` away.
+pub async fn get_price_near(
+    pool: &PgPool,
+    address: &str,
+    unix_time: i64,
+    max_distance_secs: i64,
+) -> Result<Option<f64>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT price FROM (
+            (SELECT price::FLOAT8 AS price, ABS(unixtime - $2) AS distance
+             FROM token_prices
+             WHERE address = $1 AND unixtime BETWEEN $2 - $3 AND $2 + $3
+             ORDER BY distance LIMIT 1)
+            UNION ALL
+            (SELECT price::FLOAT8, ABS(update_unix_time - $2)
+             FROM token_metrics
+             WHERE token_address = $1 AND price IS NOT NULL
+               AND update_unix_time BETWEEN $2 - $3 AND $2 + $3
+             ORDER BY 2 LIMIT 1)
+        ) nearest
+        ORDER BY distance
+        LIMIT 1
+        "#,
+    )
+    .bind(address)
+    .bind(unix_time)
+    .bind(max_distance_secs)
+    .fetch_optional(pool)
+    .await
+}
 
-    Ok(prices)
+/// Price of `address` at `unix_time`, the nearest stored price at most `max_distance_secs` away
+/// or else the close of the provider 1m candle of that time.
+pub async fn price_at<T: PriceSdk + ?Sized>(
+    pool: &PgPool,
+    client: &T,
+    address: &str,
+    unix_time: i64,
+    max_distance_secs: i64,
+) -> anyhow::Result<Option<(f64, PriceSource)>> {
+    if let Some(price) = get_price_near(pool, address, unix_time, max_distance_secs).await? {
+        return Ok(Some((price, PriceSource::Database)));
+    }
+    let interval = TimeFilters::OneMinute;
+    let candles = fetch_candles(
+        pool,
+        client,
+        address,
+        interval,
+        unix_time - 5 * interval.as_secs(),
+        unix_time,
+    )
+    .await?;
+    Ok(candles
+        .last()
+        .map(|candle| (candle.close, PriceSource::Provider)))
 }
 
 // Database model for token_prices
@@ -85,3 +146,79 @@ impl TokenPrice {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{test_pool, unique_name};
+    use crate::thirdparty::mock::{MockClient, UNKNOWN_TOKEN};
+    use crate::thirdparty::Items;
+    use bigdecimal::ToPrimitive;
+    use chrono::Duration;
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_roll_up_daily_prices() {
+        let pool = test_pool().await;
+        let address = unique_name("rollup");
+        let day = DateTime::from_timestamp(1743465600, 0).unwrap();
+        let items = [(0, 10.0), (3600, 12.5), (7200, 9.0), (86400, 11.0)]
+            .into_iter()
+            .map(|(offset, value)| Items {
+                value,
+                unix_time: day.timestamp() + offset,
+            })
+            .collect();
+        insert_token_prices(&pool, PriceHistory { items }, &address)
+            .await
+            .unwrap();
+
+        let rollups = get_daily_prices_between(&pool, &address, day, day + Duration::days(1))
+            .await
+            .unwrap();
+
+        let closes: Vec<_> = rollups.iter().map(|close| close.to_f64()).collect();
+        assert_eq!(closes, [Some(9.0), Some(11.0)]);
+        let first_day: (f64, f64, f64) = sqlx::query_as(
+            "SELECT open::FLOAT8, high::FLOAT8, low::FLOAT8 FROM token_prices_daily
+            WHERE address = $1 AND bucket = $2",
+        )
+        .bind(&address)
+        .bind(day.timestamp())
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(first_day, (10.0, 12.5, 9.0));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_price_at_nearest_stored_price_or_provider_candle() {
+        let pool = test_pool().await;
+        let address = unique_name("price_at");
+        let at = 1743465600;
+        let items = [(-600, 1.0), (120, 2.0)]
+            .into_iter()
+            .map(|(offset, value)| Items {
+                value,
+                unix_time: at + offset,
+            })
+            .collect();
+        insert_token_prices(&pool, PriceHistory { items }, &address)
+            .await
+            .unwrap();
+
+        let stored = price_at(&pool, &MockClient, &address, at, 3600)
+            .await
+            .unwrap();
+        assert_eq!(stored, Some((2.0, PriceSource::Database)));
+
+        let (_, source) = price_at(&pool, &MockClient, &address, at + 86400, 3600)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(source, PriceSource::Provider);
+        let unknown = price_at(&pool, &MockClient, UNKNOWN_TOKEN, at, 3600).await;
+        assert!(unknown.unwrap().is_none());
+    }
+}
@@ -1,54 +1,198 @@
+pub mod candles;
 pub mod history;
 pub mod route;
 
+pub use candles::*;
 pub use history::*;
 
+use async_trait::async_trait;
 use bigdecimal::ToPrimitive;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
 use sqlx::types::BigDecimal;
 use sqlx::PgPool;
 use sqlx::Row;
+use std::collections::HashMap;
+use tracing::{error, warn};
 
 use crate::thirdparty::{PriceHistory, TokenData};
 
+/// Resolution of a price history or of OHLCV candles, named as in the Birdeye API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TimeFilters {
+    #[serde(rename = "1m")]
+    OneMinute,
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1H")]
+    OneHour,
+    #[serde(rename = "4H")]
+    FourHours,
+    #[serde(rename = "1D")]
     OneDay,
+    #[serde(rename = "1W")]
+    OneWeek,
 }
 
 impl TimeFilters {
-    pub fn as_query_param(&self) -> &str {
+    pub fn as_query_param(&self) -> &'static str {
         match self {
+            Self::OneMinute => "1m",
+            Self::FiveMinutes => "5m",
+            Self::FifteenMinutes => "15m",
+            Self::OneHour => "1H",
+            Self::FourHours => "4H",
             Self::OneDay => "1D",
+            Self::OneWeek => "1W",
         }
     }
+
+    pub fn as_secs(&self) -> i64 {
+        match self {
+            Self::OneMinute => 60,
+            Self::FiveMinutes => 5 * 60,
+            Self::FifteenMinutes => 15 * 60,
+            Self::OneHour => 60 * 60,
+            Self::FourHours => 4 * 60 * 60,
+            Self::OneDay => 24 * 60 * 60,
+            Self::OneWeek => 7 * 24 * 60 * 60,
+        }
+    }
+
+    /// Start of the candle containing `unix_time`.
+    pub fn align(&self, unix_time: i64) -> i64 {
+        unix_time - unix_time.rem_euclid(self.as_secs())
+    }
 }
 
-pub trait PriceSdk {
+#[async_trait]
+pub trait PriceSdk: Send + Sync {
     async fn get_price(&self, token: &str) -> Result<TokenData, anyhow::Error>;
+    /// Prices of `tokens` keyed by address, tokens the provider doesn't know are left out.
+    async fn get_prices(
+        &self,
+        tokens: &[String],
+    ) -> Result<HashMap<String, TokenData>, anyhow::Error>;
     async fn get_price_by_time_filter(
         &self,
         token: &str,
         filter: TimeFilters,
     ) -> Result<PriceHistory, anyhow::Error>;
+    /// Candles of `token` starting between `time_from` and `time_to`, oldest first.
+    async fn get_ohlcv(
+        &self,
+        token: &str,
+        interval: TimeFilters,
+        time_from: i64,
+        time_to: i64,
+    ) -> Result<Vec<Candle>, anyhow::Error>;
 }
 
-#[allow(dead_code)]
-pub async fn get_price<T: PriceSdk>(
+/// Where a served price comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceSource {
+    Database,
+    Provider,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CachedPrice {
+    #[serde(flatten)]
+    pub data: TokenData,
+    pub source: PriceSource,
+    /// Seconds since the provider observed the price.
+    pub age_secs: i64,
+    /// Older than the freshness limit, only served because the provider is unavailable.
+    pub stale: bool,
+}
+
+/// Prices of `token_addresses` keyed by address, served from `token_metrics` while they are at
+/// most `max_age_secs` old.
+///
+/// The other addresses are fetched from the provider in one batch and stored. When the provider
+/// fails the stored prices are served as stale, it is an error only if there is nothing to serve.
+pub async fn fetch_prices<T: PriceSdk + ?Sized>(
     pool: &PgPool,
     client: &T,
-    token_address: &str,
-) -> Result<TokenData, Box<dyn std::error::Error>> {
-    // ✅ 1. Try to get metric from the database
-    if let Some(metric) = get_metric_from_db(pool, token_address).await? {
-        return Ok(metric);
-    }
+    token_addresses: &[String],
+    max_age_secs: i64,
+) -> anyhow::Result<HashMap<String, CachedPrice>> {
+    let now = Utc::now().timestamp();
+    let stored = get_metrics_from_db(pool, token_addresses)
+        .await
+        .unwrap_or_else(|e| {
+            error!("get_metrics_from_db {e}");
+            HashMap::new()
+        });
+    let mut prices: HashMap<String, CachedPrice> = stored
+        .into_iter()
+        .map(|(address, data)| {
+            let age_secs = (now - data.update_unix_time).max(0);
+            let price = CachedPrice {
+                data,
+                source: PriceSource::Database,
+                age_secs,
+                stale: age_secs > max_age_secs,
+            };
+            (address, price)
+        })
+        .collect();
 
-    // ✅ 2. If not found, call 3rd-party API
-    let metric = client.get_price(token_address).await?;
-
-    // ✅ 3. Store the metric in the database
-    store_metric_in_db(pool, &metric, token_address).await?;
+    let outdated: Vec<String> = token_addresses
+        .iter()
+        .filter(|address| prices.get(*address).is_none_or(|price| price.stale))
+        .cloned()
+        .collect();
+    if outdated.is_empty() {
+        return Ok(prices);
+    }
+    match client.get_prices(&outdated).await {
+        Ok(fetched) => {
+            for (address, data) in fetched {
+                if let Err(e) = store_metric_in_db(pool, &data, &address).await {
+                    error!("store_metric_in_db {address} {e}");
+                }
+                let price = CachedPrice {
+                    age_secs: (now - data.update_unix_time).max(0),
+                    data,
+                    source: PriceSource::Provider,
+                    stale: false,
+                };
+                prices.insert(address, price);
+            }
+        }
+        Err(e) if prices.is_empty() => return Err(e),
+        Err(e) => warn!("serving stored prices of {} tokens: {e}", outdated.len()),
+    }
+    Ok(prices)
+}
 
-    Ok(metric)
+fn metric_from_row(row: &PgRow) -> TokenData {
+    TokenData {
+        update_unix_time: row.get("update_unix_time"),
+        update_human_time: row.get("update_human_time"),
+        volume_usd: row
+            .get::<BigDecimal, _>("volume_usd")
+            .to_f64()
+            .unwrap_or_default(),
+        volume_change_percent: row
+            .get::<BigDecimal, _>("volume_change_percent")
+            .to_f64()
+            .unwrap_or(0.0),
+        price_change_percent: row
+            .get::<BigDecimal, _>("price_change_percent")
+            .to_f64()
+            .unwrap_or(0.0),
+        price: row
+            .get::<BigDecimal, _>("price")
+            .to_f64()
+            .unwrap_or_default(),
+    }
 }
 
 /// ✅ Query the database for an existing metric
@@ -64,30 +208,26 @@ pub async fn get_metric_from_db(
     .fetch_optional(pool)
     .await?;
 
-    if let Some(row) = row {
-        Ok(Some(TokenData {
-            update_unix_time: row.get("update_unix_time"),
-            update_human_time: row.get("update_human_time"),
-            volume_usd: row
-                .get::<BigDecimal, _>("volume_usd")
-                .to_f64()
-                .unwrap_or_default(),
-            volume_change_percent: row
-                .get::<BigDecimal, _>("volume_change_percent")
-                .to_f64()
-                .unwrap_or(0.0),
-            price_change_percent: row
-                .get::<BigDecimal, _>("price_change_percent")
-                .to_f64()
-                .unwrap_or(0.0),
-            price: row
-                .get::<BigDecimal, _>("price")
-                .to_f64()
-                .unwrap_or_default(),
-        }))
-    } else {
-        Ok(None)
-    }
+    Ok(row.as_ref().map(metric_from_row))
+}
+
+/// Latest stored metric of each of `token_addresses` that has one.
+pub async fn get_metrics_from_db(
+    pool: &PgPool,
+    token_addresses: &[String],
+) -> Result<HashMap<String, TokenData>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT DISTINCT ON (token_address) token_address, update_unix_time, update_human_time, volume_usd, volume_change_percent, price_change_percent, price
+        FROM token_metrics WHERE token_address = ANY($1) ORDER BY token_address, update_unix_time DESC"
+    )
+    .bind(token_addresses)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("token_address"), metric_from_row(row)))
+        .collect())
 }
 
 /// ✅ Store the fetched metric in the database
@@ -112,3 +252,31 @@ pub async fn store_metric_in_db(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{test_pool, unique_name};
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_store_memecoin_prices_and_large_changes() {
+        let pool = test_pool().await;
+        let address = unique_name("memecoin");
+        let metric = TokenData {
+            price: 1e-9,
+            update_unix_time: Utc::now().timestamp(),
+            update_human_time: Utc::now().to_rfc3339(),
+            volume_usd: 1250.5,
+            volume_change_percent: 123456.75,
+            price_change_percent: -99.999999999,
+        };
+
+        store_metric_in_db(&pool, &metric, &address).await.unwrap();
+        let stored = get_metric_from_db(&pool, &address).await.unwrap().unwrap();
+
+        assert_eq!(stored.price, 1e-9);
+        assert_eq!(stored.volume_change_percent, 123456.75);
+        assert_eq!(stored.price_change_percent, -99.999999999);
+    }
+}
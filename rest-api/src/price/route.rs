@@ -1,26 +1,147 @@
+use std::collections::HashMap;
+
 use crate::app::AppState;
-use crate::price::PriceSdk;
+use crate::error::ApiError;
+use crate::price::{fetch_candles, fetch_prices, CachedPrice, Candle, TimeFilters};
+use crate::response::HttpResponse;
 use crate::thirdparty::TokenData;
-use axum::extract::State;
-use axum::http::StatusCode;
+use crate::token::validate_mint;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Path, Query, State};
 use axum::Json;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use tracing::error;
 
+/// Most candles served by one request.
+const MAX_CANDLES: i64 = 1000;
+/// Candles served when the request has no `from`.
+const DEFAULT_CANDLES: i64 = 300;
+
+#[derive(Debug, Deserialize)]
+pub struct PricesQuery {
+    /// Comma separated token addresses.
+    pub addresses: String,
+}
+
+async fn prices(
+    app: &AppState,
+    addresses: &[String],
+) -> Result<HashMap<String, CachedPrice>, ApiError> {
+    fetch_prices(
+        &app.pool,
+        app.price_sdk.as_ref(),
+        addresses,
+        app.price_config.max_age_secs,
+    )
+    .await
+    .map_err(|e| {
+        error!("fetch_prices {e}");
+        ApiError::UpstreamUnavailable("BirdEye price lookup failed".into())
+    })
+}
+
 pub async fn get_price(
     State(app): State<AppState>,
-) -> Result<Json<TokenData>, (StatusCode, String)> {
-    let birdeye_client = &app.bird_eye_client;
-    match birdeye_client
-        .get_price("So11111111111111111111111111111111111111112")
-        .await
-    {
-        Ok(result) => Ok(Json(result)),
-        Err(e) => {
-            error!("get_price {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "BirdEye get_price failed".to_string(),
-            ))
-        }
+    Path(address): Path<String>,
+) -> Result<Json<TokenData>, ApiError> {
+    validate_mint(&address)?;
+    prices(&app, std::slice::from_ref(&address))
+        .await?
+        .remove(&address)
+        .map(|price| Json(price.data))
+        .ok_or_else(|| ApiError::NotFound(format!("no price for token {address}")))
+}
+
+/// Prices of several tokens keyed by address, tokens without a known price are left out.
+pub async fn get_prices(
+    State(app): State<AppState>,
+    Query(query): Query<PricesQuery>,
+) -> Result<Json<HttpResponse<HashMap<String, CachedPrice>>>, ApiError> {
+    let mut addresses: Vec<String> = query
+        .addresses
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(String::from)
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    let max_addresses = app.price_config.max_addresses;
+    if addresses.is_empty() || addresses.len() > max_addresses {
+        return Err(ApiError::BadRequest(format!(
+            "addresses must list 1 to {max_addresses} tokens"
+        )));
+    }
+    for address in &addresses {
+        validate_mint(address)?;
+    }
+
+    Ok(Json(HttpResponse {
+        code: 200,
+        response: prices(&app, &addresses).await?,
+        last_updated: Utc::now().timestamp(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CandlesQuery {
+    pub interval: TimeFilters,
+    /// Unix time of the first candle, defaults to 300 candles before `to`.
+    pub from: Option<i64>,
+    /// Unix time of the last candle, defaults to now.
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandlesResponse {
+    pub address: String,
+    pub interval: TimeFilters,
+    pub candles: Vec<Candle>,
+}
+
+/// OHLCV candles of a token, oldest first.
+pub async fn get_candles(
+    State(app): State<AppState>,
+    Path(address): Path<String>,
+    query: Result<Query<CandlesQuery>, QueryRejection>,
+) -> Result<Json<HttpResponse<CandlesResponse>>, ApiError> {
+    let Query(query) = query?;
+    validate_mint(&address)?;
+    let step = query.interval.as_secs();
+    let to = query.to.unwrap_or_else(|| Utc::now().timestamp());
+    let from = query.from.unwrap_or(to - (DEFAULT_CANDLES - 1) * step);
+    if from > to {
+        return Err(ApiError::BadRequest("from must not be after to".into()));
+    }
+    if (to - from) / step >= MAX_CANDLES {
+        return Err(ApiError::BadRequest(format!(
+            "at most {MAX_CANDLES} candles can be requested at once"
+        )));
     }
+
+    let candles = fetch_candles(
+        &app.pool,
+        app.price_sdk.as_ref(),
+        &address,
+        query.interval,
+        from,
+        to,
+    )
+    .await
+    .map_err(|e| {
+        error!("fetch_candles {address} {e}");
+        ApiError::UpstreamUnavailable("BirdEye candles lookup failed".into())
+    })?;
+
+    Ok(Json(HttpResponse {
+        code: 200,
+        response: CandlesResponse {
+            address,
+            interval: query.interval,
+            candles,
+        },
+        last_updated: Utc::now().timestamp(),
+    }))
 }
@@ -0,0 +1,77 @@
+use anyhow::Context;
+use redis::aio::{ConnectionManager, PubSub};
+use redis::Client;
+use tracing::info;
+
+use crate::config::RedisConfig;
+
+/// Redis shared by the instances of a deployment, every key and channel is namespaced by the
+/// configured prefix.
+#[derive(Clone)]
+pub struct RedisStore {
+    client: Client,
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisStore {
+    /// Connect to the configured Redis, `None` when no `REDIS_URL` is set.
+    pub async fn connect(config: &RedisConfig) -> anyhow::Result<Option<Self>> {
+        let Some(url) = config.url.as_deref() else {
+            return Ok(None);
+        };
+        let client = Client::open(url).context("parse REDIS_URL")?;
+        let connection = client
+            .get_connection_manager()
+            .await
+            .context("connect to redis")?;
+        info!("sharing cache, budget and events through redis");
+        Ok(Some(Self {
+            client,
+            connection,
+            prefix: config.key_prefix.clone(),
+        }))
+    }
+
+    /// `name` namespaced by the prefix.
+    pub fn key(&self, name: &str) -> String {
+        format!("{}:{name}", self.prefix)
+    }
+
+    /// Multiplexed connection, reconnecting on failure, cheap to clone.
+    pub fn connection(&self) -> ConnectionManager {
+        self.connection.clone()
+    }
+
+    /// A dedicated connection subscribed to `channel`, namespaced by the prefix.
+    pub async fn subscribe(&self, channel: &str) -> redis::RedisResult<PubSub> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(self.key(channel)).await?;
+        Ok(pubsub)
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use chrono::Utc;
+
+    /// A store on `TEST_REDIS_URL` under a prefix of its own so that tests do not share keys.
+    pub async fn test_store() -> RedisStore {
+        let url = std::env::var("TEST_REDIS_URL").expect("TEST_REDIS_URL is not set");
+        let config = RedisConfig {
+            url: Some(url),
+            key_prefix: format!("munki_test_{}", Utc::now().timestamp_nanos_opt().unwrap()),
+        };
+        RedisStore::connect(&config).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_stay_local_without_url() {
+        let config = RedisConfig {
+            url: None,
+            key_prefix: "munki".into(),
+        };
+        assert!(RedisStore::connect(&config).await.unwrap().is_none());
+    }
+}
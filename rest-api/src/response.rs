@@ -12,3 +12,16 @@ pub struct HttpPaginationResponse<T: serde::Serialize> {
     pub last_updated: i64,
     pub total: i64,
 }
+
+#[derive(serde::Serialize)]
+pub struct HttpErrorResponse {
+    pub code: i32,
+    pub error: ErrorBody,
+    pub last_updated: i64,
+}
+
+#[derive(serde::Serialize)]
+pub struct ErrorBody {
+    pub kind: &'static str,
+    pub message: String,
+}
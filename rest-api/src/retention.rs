@@ -0,0 +1,328 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::info;
+
+use crate::config::RetentionConfig;
+use crate::price::TimeFilters;
+use crate::time_util;
+
+/// Candle resolutions pruned after [`RetentionConfig::intraday_candle_days`].
+const INTRADAY_RESOLUTIONS: [TimeFilters; 3] = [
+    TimeFilters::OneMinute,
+    TimeFilters::FiveMinutes,
+    TimeFilters::FifteenMinutes,
+];
+
+/// Rows removed by a retention run, or that would be removed by a dry run.
+#[derive(Debug, Default, PartialEq)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    /// Raw `token_metrics` rows folded into the summaries.
+    pub metrics_downsampled: u64,
+    pub hourly_summaries_removed: u64,
+    pub candles_removed: u64,
+    pub token_watch_removed: u64,
+}
+
+/// Cut-offs of a run at `now`, metrics and candles are cut at the start of a day so that no
+/// hourly or daily bucket is split.
+struct Cutoffs {
+    raw_metrics: i64,
+    hourly_summaries: i64,
+    intraday_candles: i64,
+    token_watch: DateTime<Utc>,
+}
+
+impl Cutoffs {
+    fn new(config: &RetentionConfig, now: DateTime<Utc>) -> Self {
+        let day_start = |days| time_util::get_start_of_day(now - Duration::days(days)).timestamp();
+        Self {
+            raw_metrics: day_start(config.raw_metrics_days),
+            hourly_summaries: day_start(config.hourly_summary_days),
+            intraday_candles: day_start(config.intraday_candle_days),
+            token_watch: now - Duration::days(config.token_watch_inactive_days),
+        }
+    }
+}
+
+/// Downsample and prune the time series and the inactive `token_watch` rows.
+///
+/// With TimescaleDB `token_metrics` and its rollups are aged by the policies of the extension
+/// and are left alone here.
+pub async fn run_retention(
+    pool: &PgPool,
+    config: &RetentionConfig,
+    now: DateTime<Utc>,
+) -> anyhow::Result<RetentionReport> {
+    let cutoffs = Cutoffs::new(config, now);
+    let resolutions: Vec<&str> = INTRADAY_RESOLUTIONS
+        .iter()
+        .map(TimeFilters::as_query_param)
+        .collect();
+    let timescale = has_timescaledb(pool).await?;
+    let mut report = RetentionReport {
+        dry_run: config.dry_run,
+        ..Default::default()
+    };
+
+    if config.dry_run {
+        if !timescale {
+            report.metrics_downsampled = count(
+                pool,
+                "SELECT COUNT(*) FROM token_metrics WHERE update_unix_time < $1",
+                cutoffs.raw_metrics,
+            )
+            .await?;
+            report.hourly_summaries_removed = count(
+                pool,
+                "SELECT COUNT(*) FROM token_metrics_hourly_summary WHERE bucket < $1",
+                cutoffs.hourly_summaries,
+            )
+            .await?;
+        }
+        report.candles_removed = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM token_candles WHERE resolution = ANY($1) AND unix_time < $2",
+        )
+        .bind(&resolutions)
+        .bind(cutoffs.intraday_candles)
+        .fetch_one(pool)
+        .await? as u64;
+        report.token_watch_removed = count(
+            pool,
+            "SELECT COUNT(*) FROM token_watch
+            WHERE COALESCE(last_active, updated_at AT TIME ZONE 'UTC') < $1",
+            cutoffs.token_watch,
+        )
+        .await?;
+        info!("retention dry run {report:?}");
+        return Ok(report);
+    }
+
+    if !timescale {
+        let mut tx = pool.begin().await?;
+        for (table, width) in [
+            ("token_metrics_hourly_summary", 3600),
+            ("token_metrics_daily_summary", 86400),
+        ] {
+            downsample_metrics(&mut tx, table, width, cutoffs.raw_metrics).await?;
+        }
+        report.metrics_downsampled =
+            sqlx::query("DELETE FROM token_metrics WHERE update_unix_time < $1")
+                .bind(cutoffs.raw_metrics)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        tx.commit().await?;
+
+        report.hourly_summaries_removed =
+            sqlx::query("DELETE FROM token_metrics_hourly_summary WHERE bucket < $1")
+                .bind(cutoffs.hourly_summaries)
+                .execute(pool)
+                .await?
+                .rows_affected();
+    }
+
+    report.candles_removed =
+        prune_intraday_candles(pool, &resolutions, cutoffs.intraday_candles).await?;
+    // rows never marked active, some predate `last_active`, count from their last refresh
+    report.token_watch_removed = sqlx::query(
+        "DELETE FROM token_watch
+        WHERE COALESCE(last_active, updated_at AT TIME ZONE 'UTC') < $1",
+    )
+    .bind(cutoffs.token_watch)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    info!("retention {report:?}");
+    Ok(report)
+}
+
+async fn has_timescaledb(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'timescaledb')")
+        .fetch_one(pool)
+        .await
+}
+
+async fn count<'a, T>(pool: &PgPool, query: &'a str, cutoff: T) -> Result<u64, sqlx::Error>
+where
+    T: sqlx::Encode<'a, Postgres> + sqlx::Type<Postgres> + Send + 'a,
+{
+    let count: i64 = sqlx::query_scalar(query)
+        .bind(cutoff)
+        .fetch_one(pool)
+        .await?;
+    Ok(count as u64)
+}
+
+/// Fold the `token_metrics` rows older than `cutoff` into `table` by buckets of `width` seconds,
+/// merging into the buckets of an earlier run.
+async fn downsample_metrics(
+    tx: &mut Transaction<'_, Postgres>,
+    table: &str,
+    width: i64,
+    cutoff: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "INSERT INTO {table} AS s
+            (token_address, bucket, open, high, low, close, volume_usd, samples)
+        SELECT token_address,
+               update_unix_time - update_unix_time % $1 AS bucket,
+               (ARRAY_AGG(price ORDER BY update_unix_time))[1],
+               MAX(price),
+               MIN(price),
+               (ARRAY_AGG(price ORDER BY update_unix_time DESC))[1],
+               (ARRAY_AGG(volume_usd ORDER BY update_unix_time DESC))[1],
+               COUNT(*)
+        FROM token_metrics
+        WHERE update_unix_time < $2
+        GROUP BY token_address, bucket
+        ON CONFLICT (token_address, bucket) DO UPDATE SET
+            high = GREATEST(s.high, EXCLUDED.high),
+            low = LEAST(s.low, EXCLUDED.low),
+            close = EXCLUDED.close,
+            volume_usd = EXCLUDED.volume_usd,
+            samples = s.samples + EXCLUDED.samples"
+    ))
+    .bind(width)
+    .bind(cutoff)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Delete the cached `resolutions` candles older than `cutoff` and shrink their coverage so that
+/// they are fetched again when requested.
+async fn prune_intraday_candles(
+    pool: &PgPool,
+    resolutions: &[&str],
+    cutoff: i64,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "DELETE FROM token_candle_coverage
+        WHERE resolution = ANY($1) AND covered_to < $2",
+    )
+    .bind(resolutions)
+    .bind(cutoff)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "UPDATE token_candle_coverage SET covered_from = $2, updated_at = NOW()
+        WHERE resolution = ANY($1) AND covered_from < $2",
+    )
+    .bind(resolutions)
+    .bind(cutoff)
+    .execute(&mut *tx)
+    .await?;
+    let removed =
+        sqlx::query("DELETE FROM token_candles WHERE resolution = ANY($1) AND unix_time < $2")
+            .bind(resolutions)
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    tx.commit().await?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{test_pool, unique_name};
+    use envconfig::Envconfig;
+    use std::collections::HashMap;
+
+    async fn seed(pool: &PgPool, address: &str, update_unix_time: i64, price: f64) {
+        sqlx::query(
+            "INSERT INTO token_metrics
+            (token_address, update_unix_time, update_human_time, volume_usd, price)
+            VALUES ($1, $2, '', $3, $4)",
+        )
+        .bind(address)
+        .bind(update_unix_time)
+        .bind(price * 1000.0)
+        .bind(price)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_downsample_expired_metrics_and_prune_inactive_tokens() {
+        let pool = test_pool().await;
+        let address = unique_name("retention");
+        let now = Utc::now();
+        let old_day = time_util::get_start_of_day(now - Duration::days(30)).timestamp();
+        // memecoin prices, far below the last digit of a NUMERIC(10,6)
+        for (offset, price) in [(60, 2e-9), (120, 3e-9), (3660, 1e-9)] {
+            seed(&pool, &address, old_day + offset, price).await;
+        }
+        seed(&pool, &address, now.timestamp(), 4e-9).await;
+        sqlx::query("INSERT INTO token_watch (token_address, last_active) VALUES ($1, $2)")
+            .bind(&address)
+            .bind(now - Duration::days(200))
+            .execute(&pool)
+            .await
+            .unwrap();
+        let never_active = format!("{address}_never");
+        sqlx::query("INSERT INTO token_watch (token_address, updated_at) VALUES ($1, $2)")
+            .bind(&never_active)
+            .bind((now - Duration::days(200)).naive_utc())
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut config = RetentionConfig::init_from_hashmap(&HashMap::new()).unwrap();
+        config.dry_run = true;
+        let report = run_retention(&pool, &config, now).await.unwrap();
+        assert!(report.metrics_downsampled >= 3);
+        assert!(report.token_watch_removed >= 2);
+        let rows: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM token_metrics WHERE token_address = $1")
+                .bind(&address)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(rows, 4);
+
+        config.dry_run = false;
+        run_retention(&pool, &config, now).await.unwrap();
+
+        let rows: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM token_metrics WHERE token_address = $1")
+                .bind(&address)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(rows, 1);
+        let hourly: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT bucket, samples FROM token_metrics_hourly
+            WHERE token_address = $1 ORDER BY bucket",
+        )
+        .bind(&address)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(hourly.len(), 3);
+        assert_eq!(hourly[0], (old_day, 2));
+        assert_eq!(hourly[1], (old_day + 3600, 1));
+        let (samples, close): (i64, f64) = sqlx::query_as(
+            "SELECT samples, close::FLOAT8 FROM token_metrics_daily
+            WHERE token_address = $1 AND bucket = $2",
+        )
+        .bind(&address)
+        .bind(old_day)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!((samples, close), (3, 1e-9));
+        let watched: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM token_watch WHERE token_address = ANY($1)")
+                .bind([&address, &never_active])
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(watched, 0);
+    }
+}
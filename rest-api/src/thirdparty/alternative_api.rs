@@ -1,23 +1,28 @@
 use crate::fearandgreed::{FearAndGreedApiResponse, FearAndGreedSdk};
-use reqwest::Client;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+use super::http::ProviderHttp;
+
 #[allow(dead_code)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct AlternativeClient {
     base_url: String,
     limit: i8,
+    http: ProviderHttp,
 }
 
 impl AlternativeClient {
-    pub fn new(base_url: String, limit: i8) -> Self {
+    pub fn new(base_url: String, limit: i8, http: ProviderHttp) -> Self {
         Self {
             base_url: base_url.to_string(),
             limit,
+            http,
         }
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 pub struct AlternativeResponse {
     pub name: String,
@@ -25,6 +30,7 @@ pub struct AlternativeResponse {
     pub metadata: Metadata,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, serde::Deserialize)]
 pub struct AlternativeResponseData {
     pub value: String,
@@ -33,33 +39,45 @@ pub struct AlternativeResponseData {
     pub time_until_update: Option<String>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, serde::Deserialize)]
 pub struct Metadata {
     pub error: Option<String>,
 }
 
+impl From<AlternativeResponseData> for FearAndGreedApiResponse {
+    fn from(data: AlternativeResponseData) -> Self {
+        Self {
+            value: data.value,
+            status: data.value_classification,
+            timestamp: data.timestamp,
+            chain: "BTC".to_string(),
+        }
+    }
+}
+
 #[derive(Serialize, Debug)]
 struct QueryParams {
     limit: i8,
 }
 
 // Make sure this is inside the impl FearAndGreedSdk for AlternativeClient block
+#[async_trait]
 impl FearAndGreedSdk for AlternativeClient {
     async fn get_fear_and_greed(
         &self,
         limit: i8,
     ) -> Result<Vec<FearAndGreedApiResponse>, anyhow::Error> {
-        let client = Client::new();
         let url = &self.base_url;
 
         let query_params = QueryParams { limit };
 
-        let resp = client
+        let request = self
+            .http
             .get(url)
             .query(&query_params)
-            .header("accept", "application/json")
-            .send()
-            .await?;
+            .header("accept", "application/json");
+        let resp = self.http.send(request).await?;
 
         if !resp.status().is_success() {
             return Err(anyhow::anyhow!(
@@ -83,12 +101,7 @@ impl FearAndGreedSdk for AlternativeClient {
         let fear_and_greed_list: Vec<FearAndGreedApiResponse> = parsed_resp
             .data
             .into_iter()
-            .map(|data| FearAndGreedApiResponse {
-                value: data.value,
-                status: data.value_classification,
-                timestamp: data.timestamp.to_string(),
-                chain: "BTC".to_string(),
-            })
+            .map(FearAndGreedApiResponse::from)
             .collect();
 
         if fear_and_greed_list.is_empty() {
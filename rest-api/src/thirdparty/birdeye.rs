@@ -1,34 +1,98 @@
-use crate::price::{PriceSdk, TimeFilters};
+use crate::price::{Candle, PriceSdk, TimeFilters};
 use crate::token::market::TradeData;
 use crate::token::trade::MarketData;
-use crate::token::{TokenHolder, TokenMetadata, TokenOverview, TokenSdk, Trending};
+use crate::token::{TokenHolder, TokenMetadata, TokenOverview, TokenSdk, Trending, UnknownToken};
 use anyhow::Error;
+use async_trait::async_trait;
 use chrono::{Duration, Timelike, Utc};
-use reqwest::Client;
+use reqwest::{RequestBuilder, Response};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tracing::info;
 
+use super::http::ProviderHttp;
 use super::token_search::TokenSearchResult;
 
+/// Most addresses `/defi/price_volume/multi` accepts in one call.
+const PRICE_VOLUME_MULTI_MAX_ADDRESSES: usize = 50;
+
 #[allow(dead_code)]
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct BirdEyeClient {
     base_url: String,
     api_key: String,
-    client: Client,
+    http: ProviderHttp,
 }
 
 impl BirdEyeClient {
-    pub fn new(base_url: &str, api_key: &str) -> Self {
+    /// `http` should carry the Birdeye limiter, see [`ProviderHttp::with_limiter`].
+    pub fn new(base_url: &str, api_key: &str, http: ProviderHttp) -> Self {
         Self {
-            client: Client::new(),
+            http,
             base_url: base_url.to_string(),
             api_key: api_key.to_string(),
         }
     }
+
+    /// Send `request` to `endpoint`, every attempt is charged the compute units of `endpoint`.
+    async fn send(&self, endpoint: Endpoint, request: RequestBuilder) -> Result<Response, Error> {
+        self.http
+            .send_to(endpoint.name(), endpoint.compute_units(), request)
+            .await
+    }
 }
 
+/// Birdeye endpoints with the compute units a call is charged, used to stay in the CU budget.
+#[derive(Debug, Clone, Copy)]
+enum Endpoint {
+    TokenTrending,
+    TokenMetaMultiple,
+    TokenOverview,
+    TokenHolder,
+    Search,
+    TradeData,
+    MarketData,
+    PriceVolume,
+    PriceVolumeMulti,
+    HistoryPrice,
+    Ohlcv,
+}
+
+impl Endpoint {
+    fn name(self) -> &'static str {
+        match self {
+            Endpoint::TokenTrending => "token_trending",
+            Endpoint::TokenMetaMultiple => "token_meta_multiple",
+            Endpoint::TokenOverview => "token_overview",
+            Endpoint::TokenHolder => "token_holder",
+            Endpoint::Search => "search",
+            Endpoint::TradeData => "trade_data",
+            Endpoint::MarketData => "market_data",
+            Endpoint::PriceVolume => "price_volume",
+            Endpoint::PriceVolumeMulti => "price_volume_multi",
+            Endpoint::HistoryPrice => "history_price",
+            Endpoint::Ohlcv => "ohlcv",
+        }
+    }
+
+    fn compute_units(self) -> u32 {
+        match self {
+            Endpoint::TokenTrending => 50,
+            Endpoint::TokenMetaMultiple => 25,
+            Endpoint::TokenOverview => 30,
+            Endpoint::TokenHolder => 50,
+            Endpoint::Search => 50,
+            Endpoint::TradeData => 15,
+            Endpoint::MarketData => 15,
+            Endpoint::PriceVolume => 15,
+            Endpoint::PriceVolumeMulti => 50,
+            Endpoint::HistoryPrice => 60,
+            Endpoint::Ohlcv => 40,
+        }
+    }
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BirdEyeResponse<T> {
@@ -49,6 +113,31 @@ pub struct PriceHistory {
     pub items: Vec<Items>,
 }
 
+/// Candle as returned by `/defi/ohlcv`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OhlcvItem {
+    pub unix_time: i64,
+    pub o: f64,
+    pub h: f64,
+    pub l: f64,
+    pub c: f64,
+    pub v: f64,
+}
+
+impl From<OhlcvItem> for Candle {
+    fn from(item: OhlcvItem) -> Self {
+        Candle {
+            unix_time: item.unix_time,
+            open: item.o,
+            high: item.h,
+            low: item.l,
+            close: item.c,
+            volume: item.v,
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ItemsResponse<T> {
@@ -74,22 +163,38 @@ pub struct TrendingResponse {
     pub tokens: Vec<Trending>,
 }
 
+/// Birdeye answers an unknown address with an empty `data`, or no `data` at all.
+fn overview_from(
+    address: &str,
+    resp: BirdEyeResponse<Option<serde_json::Value>>,
+) -> Result<TokenOverview, Error> {
+    match resp.data {
+        Some(data) if data.get("address").is_some_and(|a| !a.is_null()) => {
+            Ok(serde_json::from_value(data)?)
+        }
+        _ => Err(UnknownToken(address.to_string()).into()),
+    }
+}
+
+#[async_trait]
 impl TokenSdk for BirdEyeClient {
     async fn get_trending(&self, offset: i32, limit: i32) -> Result<Vec<Trending>, anyhow::Error> {
         let url = format!("{}/defi/token_trending", self.base_url);
         let resp = self
-            .client
-            .get(url)
-            .query(&[
-                ("sort_by", "volume24hUSD"),
-                ("sort_type", "desc"),
-                ("offset", offset.to_string().as_str()),
-                ("limit", limit.to_string().as_str()),
-            ])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::TokenTrending,
+                self.http
+                    .get(url)
+                    .query(&[
+                        ("sort_by", "volume24hUSD"),
+                        ("sort_type", "desc"),
+                        ("offset", offset.to_string().as_str()),
+                        ("limit", limit.to_string().as_str()),
+                    ])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -113,13 +218,15 @@ impl TokenSdk for BirdEyeClient {
     ) -> Result<Vec<TokenMetadata>, Error> {
         let url = format!("{}/defi/v3/token/meta-data/multiple", self.base_url);
         let resp = self
-            .client
-            .get(url)
-            .query(&[("list_address", addresses.join(",").as_str())])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::TokenMetaMultiple,
+                self.http
+                    .get(url)
+                    .query(&[("list_address", addresses.join(",").as_str())])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -134,19 +241,21 @@ impl TokenSdk for BirdEyeClient {
             .await?
             .data;
 
-        Ok(resp.into_iter().map(|(_, a)| a).collect())
+        Ok(resp.into_values().collect())
     }
 
     async fn overview(&self, address: &str) -> Result<TokenOverview, anyhow::Error> {
         let url = format!("{}/defi/token_overview", self.base_url);
         let resp = self
-            .client
-            .get(url)
-            .query(&[("address", address)])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::TokenOverview,
+                self.http
+                    .get(url)
+                    .query(&[("address", address)])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -156,19 +265,30 @@ impl TokenSdk for BirdEyeClient {
             ));
         }
 
-        Ok(resp.json::<BirdEyeResponse<TokenOverview>>().await?.data)
+        overview_from(address, resp.json().await?)
     }
 
-    async fn holders(&self, address: &str) -> Result<Vec<TokenHolder>, Error> {
+    async fn holders(
+        &self,
+        address: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<TokenHolder>, Error> {
         let url = format!("{}/defi/v3/token/holder", self.base_url);
         let resp = self
-            .client
-            .get(url)
-            .query(&[("address", address), ("limit", "100"), ("offset", "0")])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::TokenHolder,
+                self.http
+                    .get(url)
+                    .query(&[
+                        ("address", address),
+                        ("limit", limit.to_string().as_str()),
+                        ("offset", offset.to_string().as_str()),
+                    ])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -187,23 +307,25 @@ impl TokenSdk for BirdEyeClient {
     async fn search(&self, address: &str) -> Result<Vec<TokenOverview>, anyhow::Error> {
         let url = format!("{}/defi/v3/search", self.base_url);
         let resp = self
-            .client
-            .get(url)
-            .query(&[
-                ("chain", "solana"),
-                ("target", "all"),
-                ("search_mode", "fuzzy"),
-                ("search_by", "address"),
-                ("sort_by", "volume_24h_usd"),
-                ("sort_type", "desc"),
-                ("offset", "0"),
-                ("limit", "20"),
-                ("keyword", address),
-            ])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::Search,
+                self.http
+                    .get(url)
+                    .query(&[
+                        ("chain", "solana"),
+                        ("target", "all"),
+                        ("search_mode", "fuzzy"),
+                        ("search_by", "address"),
+                        ("sort_by", "volume_24h_usd"),
+                        ("sort_type", "desc"),
+                        ("offset", "0"),
+                        ("limit", "20"),
+                        ("keyword", address),
+                    ])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -231,13 +353,15 @@ impl TokenSdk for BirdEyeClient {
     async fn trade_data(&self, address: &str) -> Result<TradeData, anyhow::Error> {
         let url = format!("{}/defi/v3/token/trade-data/single", self.base_url);
         let resp = self
-            .client
-            .get(&url)
-            .query(&[("address", address)])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::TradeData,
+                self.http
+                    .get(&url)
+                    .query(&[("address", address)])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -254,13 +378,15 @@ impl TokenSdk for BirdEyeClient {
     async fn market_data(&self, address: &str) -> Result<MarketData, anyhow::Error> {
         let url = format!("{}/defi/v3/token/market-data", self.base_url);
         let resp = self
-            .client
-            .get(&url)
-            .query(&[("address", address)])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::MarketData,
+                self.http
+                    .get(&url)
+                    .query(&[("address", address)])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -275,18 +401,21 @@ impl TokenSdk for BirdEyeClient {
     }
 }
 
+#[async_trait]
 impl PriceSdk for BirdEyeClient {
     async fn get_price(&self, token: &str) -> Result<TokenData, anyhow::Error> {
         let url = format!("{}/defi/price_volume/single", self.base_url);
         info!("price endpoint: {url}");
         let resp = self
-            .client
-            .get(url)
-            .query(&[("address", token)])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::PriceVolume,
+                self.http
+                    .get(url)
+                    .query(&[("address", token)])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -299,6 +428,44 @@ impl PriceSdk for BirdEyeClient {
         Ok(resp)
     }
 
+    async fn get_prices(
+        &self,
+        tokens: &[String],
+    ) -> Result<HashMap<String, TokenData>, anyhow::Error> {
+        let url = format!("{}/defi/price_volume/multi", self.base_url);
+        let mut prices = HashMap::with_capacity(tokens.len());
+        for chunk in tokens.chunks(PRICE_VOLUME_MULTI_MAX_ADDRESSES) {
+            let body = serde_json::json!({ "list_address": chunk.join(","), "type": "24h" });
+            let resp = self
+                .send(
+                    Endpoint::PriceVolumeMulti,
+                    self.http
+                        .post(&url)
+                        .json(&body)
+                        .header("X-API-KEY", &self.api_key)
+                        .header("accept", "application/json")
+                        .header("x-chain", "solana"),
+                )
+                .await?;
+
+            if !resp.status().is_success() {
+                return Err(anyhow::anyhow!(
+                    "Request failed with status: {}",
+                    resp.status()
+                ));
+            }
+            let resp = resp
+                .json::<BirdEyeResponse<HashMap<String, Option<TokenData>>>>()
+                .await?;
+            prices.extend(
+                resp.data
+                    .into_iter()
+                    .filter_map(|(address, price)| Some((address, price?))),
+            );
+        }
+        Ok(prices)
+    }
+
     async fn get_price_by_time_filter(
         &self,
         token: &str,
@@ -320,19 +487,21 @@ impl PriceSdk for BirdEyeClient {
         let last_week_unix = last_week.timestamp().to_string();
         let url = format!("{}/defi/history_price", self.base_url);
         let resp = self
-            .client
-            .get(url)
-            .query(&[
-                ("address", token),
-                ("address_type", "token"),
-                ("type", filter.as_query_param()),
-                ("time_from", &last_week_unix),
-                ("time_to", &today_unix),
-            ])
-            .header("X-API-KEY", &self.api_key)
-            .header("accept", "application/json")
-            .header("x-chain", "solana")
-            .send()
+            .send(
+                Endpoint::HistoryPrice,
+                self.http
+                    .get(url)
+                    .query(&[
+                        ("address", token),
+                        ("address_type", "token"),
+                        ("type", filter.as_query_param()),
+                        ("time_from", &last_week_unix),
+                        ("time_to", &today_unix),
+                    ])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -346,31 +515,99 @@ impl PriceSdk for BirdEyeClient {
         info!("fetch price history: {resp:?}");
         Ok(resp)
     }
+
+    async fn get_ohlcv(
+        &self,
+        token: &str,
+        interval: TimeFilters,
+        time_from: i64,
+        time_to: i64,
+    ) -> Result<Vec<Candle>, anyhow::Error> {
+        let url = format!("{}/defi/ohlcv", self.base_url);
+        let resp = self
+            .send(
+                Endpoint::Ohlcv,
+                self.http
+                    .get(url)
+                    .query(&[
+                        ("address", token),
+                        ("type", interval.as_query_param()),
+                        ("time_from", &time_from.to_string()),
+                        ("time_to", &time_to.to_string()),
+                    ])
+                    .header("X-API-KEY", &self.api_key)
+                    .header("accept", "application/json")
+                    .header("x-chain", "solana"),
+            )
+            .await?;
+
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!(
+                "Request failed with status: {}",
+                resp.status()
+            ));
+        }
+
+        let resp = resp
+            .json::<BirdEyeResponse<ItemsResponse<OhlcvItem>>>()
+            .await?;
+        Ok(resp.data.items.into_iter().map(Candle::from).collect())
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::price::PriceSdk;
-    use crate::thirdparty::{BirdEyeResponse, TokenData, TrendingResponse};
-
-    #[tokio::test]
-    async fn test_get_price_should_return_token_price() {
-        let client = super::BirdEyeClient::new("https://api.birdeye.com", "api_key");
-        let token_address = "0x1234567890abcdef";
-        let token_price = client.get_price(token_address).await.unwrap();
-        assert_eq!(token_price.price, 0.0);
+    use super::overview_from;
+    use crate::price::Candle;
+    use crate::thirdparty::{
+        BirdEyeResponse, ItemsResponse, OhlcvItem, PriceHistory, TokenData, TrendingResponse,
+    };
+    use crate::token::UnknownToken;
+
+    #[test]
+    fn should_tell_unknown_tokens_apart_from_partial_overviews() {
+        let fixture = include_str!("../../fixtures/birdeye/token_overview.json");
+        let overview = overview_from("known", serde_json::from_str(fixture).unwrap()).unwrap();
+        assert!(!overview.address.is_empty());
+
+        let partial = r#"{"success": true, "data": {"address": "partial", "decimals": 6, "symbol": "P", "name": "Partial"}}"#;
+        let overview = overview_from("partial", serde_json::from_str(partial).unwrap()).unwrap();
+        assert!(overview.price.is_none());
+        assert!(overview.logo_uri.is_none());
+
+        for unknown in [
+            r#"{"success": true, "data": {}}"#,
+            r#"{"success": false, "message": "Not found"}"#,
+        ] {
+            let error =
+                overview_from("unknown", serde_json::from_str(unknown).unwrap()).unwrap_err();
+            assert!(error.is::<UnknownToken>(), "{unknown}");
+        }
     }
 
-    #[tokio::test]
-    async fn test_get_price_history() {
-        use std::collections::HashMap;
-        let resp = reqwest::get("https://httpbin.org/ip")
-            .await
-            .unwrap()
-            .json::<HashMap<String, String>>()
-            .await
-            .unwrap();
-        println!("{resp:#?}");
+    #[test]
+    fn test_get_price_should_return_token_price() {
+        let fixture = include_str!("../../fixtures/birdeye/price_volume_single.json");
+        let resp: BirdEyeResponse<TokenData> = serde_json::from_str(fixture).unwrap();
+        assert!(resp.data.price > 0.0);
+    }
+
+    #[test]
+    fn should_read_ohlcv_candles() {
+        let fixture = include_str!("../../fixtures/birdeye/ohlcv.json");
+        let resp: BirdEyeResponse<ItemsResponse<OhlcvItem>> =
+            serde_json::from_str(fixture).unwrap();
+        let candles: Vec<Candle> = resp.data.items.into_iter().map(Candle::from).collect();
+        assert!(!candles.is_empty());
+        assert!(candles.windows(2).all(|w| w[0].unix_time < w[1].unix_time));
+        assert!(candles.iter().all(|c| c.low <= c.open && c.open <= c.high));
+    }
+
+    #[test]
+    fn test_get_price_history() {
+        let fixture = include_str!("../../fixtures/birdeye/history_price.json");
+        let resp: BirdEyeResponse<PriceHistory> = serde_json::from_str(fixture).unwrap();
+        assert!(!resp.data.items.is_empty());
     }
     #[test]
     fn deserialize_trending() {
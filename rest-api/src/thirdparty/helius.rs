@@ -0,0 +1,160 @@
+use std::sync::Mutex;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::http::ProviderHttp;
+
+/// Webhook delivering the transactions of a list of addresses to `POST /webhook`.
+#[async_trait]
+pub trait WebhookSdk: Send + Sync {
+    /// Replace the addresses whose transactions are delivered.
+    async fn set_addresses(&self, addresses: &[String]) -> anyhow::Result<()>;
+}
+
+/// Helius webhook `webhook_id`, edited through the webhook API.
+pub struct HeliusClient {
+    http: ProviderHttp,
+    base_url: String,
+    api_key: String,
+    webhook_id: String,
+}
+
+impl HeliusClient {
+    pub fn new(http: ProviderHttp, base_url: &str, api_key: &str, webhook_id: &str) -> Self {
+        Self {
+            http,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key: api_key.to_string(),
+            webhook_id: webhook_id.to_string(),
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/v0/webhooks/{}", self.base_url, self.webhook_id)
+    }
+}
+
+#[async_trait]
+impl WebhookSdk for HeliusClient {
+    async fn set_addresses(&self, addresses: &[String]) -> anyhow::Result<()> {
+        // an edit replaces the whole webhook, the other settings are sent back as they are
+        let request = self
+            .http
+            .get(self.url())
+            .query(&[("api-key", &self.api_key)]);
+        let resp = self.http.send(request).await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("Failed to get helius webhook: {}", resp.status());
+        }
+        let mut webhook: Value = resp.json().await?;
+        let fields = webhook
+            .as_object_mut()
+            .context("helius webhook is not an object")?;
+        fields.remove("webhookID");
+        fields.remove("wallet");
+        fields.insert("accountAddresses".into(), addresses.into());
+
+        let request = self
+            .http
+            .put(self.url())
+            .query(&[("api-key", &self.api_key)])
+            .json(&webhook);
+        let resp = self.http.send(request).await?;
+        if !resp.status().is_success() {
+            anyhow::bail!("Failed to edit helius webhook: {}", resp.status());
+        }
+        Ok(())
+    }
+}
+
+/// Keeps the addresses in memory, stands in for Helius when it is not configured and in tests.
+#[derive(Default)]
+pub struct LocalWebhook {
+    addresses: Mutex<Vec<String>>,
+}
+
+impl LocalWebhook {
+    /// The addresses last set.
+    #[cfg(test)]
+    pub fn addresses(&self) -> Vec<String> {
+        self.addresses.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl WebhookSdk for LocalWebhook {
+    async fn set_addresses(&self, addresses: &[String]) -> anyhow::Result<()> {
+        *self.addresses.lock().unwrap() = addresses.to_vec();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::ProviderHttpConfig;
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use envconfig::Envconfig;
+    use reqwest::Client;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn should_replace_addresses_and_keep_other_settings() {
+        let edits = Arc::new(Mutex::new(vec![]));
+        let recorded = edits.clone();
+        let router = Router::new().route(
+            "/v0/webhooks/hook",
+            get(|| async {
+                Json(serde_json::json!({
+                    "webhookID": "hook",
+                    "wallet": "owner",
+                    "webhookURL": "https://munki.example/api/v1/webhook",
+                    "transactionTypes": ["ANY"],
+                    "accountAddresses": ["old"],
+                    "webhookType": "enhanced",
+                    "authHeader": "current",
+                }))
+            })
+            .put(
+                move |Query(query): Query<HashMap<String, String>>, Json(body): Json<Value>| {
+                    recorded
+                        .lock()
+                        .unwrap()
+                        .push((query["api-key"].clone(), body));
+                    async { Json(serde_json::json!({})) }
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let config = ProviderHttpConfig::init_from_hashmap(&HashMap::new()).unwrap();
+        let http = ProviderHttp::new("helius", Client::new(), 1, &config);
+        let client = HeliusClient::new(http, &url, "key", "hook");
+
+        client
+            .set_addresses(&["whale".into(), "kol".into()])
+            .await
+            .unwrap();
+
+        let edits = edits.lock().unwrap();
+        assert_eq!(edits.len(), 1);
+        let (api_key, body) = &edits[0];
+        assert_eq!(api_key, "key");
+        assert_eq!(
+            body,
+            &serde_json::json!({
+                "webhookURL": "https://munki.example/api/v1/webhook",
+                "transactionTypes": ["ANY"],
+                "accountAddresses": ["whale", "kol"],
+                "webhookType": "enhanced",
+                "authHeader": "current",
+            })
+        );
+    }
+}
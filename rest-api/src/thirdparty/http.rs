@@ -0,0 +1,357 @@
+use crate::config::ProviderHttpConfig;
+use chrono::{DateTime, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, IntoUrl, RequestBuilder, Response, StatusCode};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::warn;
+
+use super::limiter::Limiter;
+
+#[derive(Debug, Error)]
+pub enum ProviderError {
+    #[error("{provider} is unavailable, retry in {}s", retry_in.as_secs())]
+    CircuitOpen {
+        provider: &'static str,
+        retry_in: Duration,
+    },
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    /// End of the running trial call, a trial that never reports back stops blocking then.
+    trial_until: Option<Instant>,
+}
+
+/// Opens after `failure_threshold` consecutive failures and rejects calls for `cooldown`. Then it
+/// is half open: one trial call at a time goes through, its success closes the breaker and its
+/// failure opens it again, the other calls are rejected meanwhile.
+#[derive(Debug)]
+struct CircuitBreaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    fn check(&self) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match (state.open_until, state.trial_until) {
+            (None, _) => Ok(()),
+            (Some(until), _) if until > now => Err(until - now),
+            (Some(_), Some(until)) if until > now => Err(until - now),
+            (Some(_), _) => {
+                state.trial_until = Some(now + self.cooldown);
+                Ok(())
+            }
+        }
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        if success {
+            *state = BreakerState::default();
+        } else {
+            state.consecutive_failures += 1;
+            if state.consecutive_failures >= self.failure_threshold {
+                state.open_until = Some(Instant::now() + self.cooldown);
+                state.trial_until = None;
+            }
+        }
+    }
+}
+
+/// HTTP client of one provider: a shared connection pool, a request timeout, bounded retries of
+/// 5xx and 429 answers honoring `Retry-After`, and a circuit breaker failing fast while the
+/// provider is down. Clones share the breaker and the limiter.
+#[derive(Debug, Clone)]
+pub struct ProviderHttp {
+    name: &'static str,
+    client: Client,
+    timeout: Duration,
+    max_retries: u32,
+    retry_base: Duration,
+    retry_max: Duration,
+    breaker: Arc<CircuitBreaker>,
+    limiter: Option<Arc<Limiter>>,
+}
+
+impl ProviderHttp {
+    pub fn new(
+        name: &'static str,
+        client: Client,
+        timeout_secs: u64,
+        config: &ProviderHttpConfig,
+    ) -> Self {
+        Self {
+            name,
+            client,
+            timeout: Duration::from_secs(timeout_secs),
+            max_retries: config.max_retries,
+            retry_base: Duration::from_millis(config.retry_base_ms),
+            retry_max: Duration::from_secs(config.retry_max_secs),
+            breaker: Arc::new(CircuitBreaker {
+                failure_threshold: config.breaker_failures.max(1),
+                cooldown: Duration::from_secs(config.breaker_cooldown_secs),
+                state: Mutex::new(BreakerState::default()),
+            }),
+            limiter: None,
+        }
+    }
+
+    /// Make every attempt of a call wait for `limiter`, clones share it.
+    pub fn with_limiter(mut self, limiter: Arc<Limiter>) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.get(url).timeout(self.timeout)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.post(url).timeout(self.timeout)
+    }
+
+    pub fn put<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.put(url).timeout(self.timeout)
+    }
+
+    /// Send `request`, retrying it while the provider answers 5xx or 429 or cannot be reached.
+    /// The last answer is returned as is, callers still check its status.
+    pub async fn send(&self, request: RequestBuilder) -> anyhow::Result<Response> {
+        self.send_to(self.name, 0, request).await
+    }
+
+    /// [`ProviderHttp::send`] to `endpoint`, each attempt waits for the limiter and is counted
+    /// in its usage as a call costing `compute_units`.
+    pub async fn send_to(
+        &self,
+        endpoint: &'static str,
+        compute_units: u32,
+        request: RequestBuilder,
+    ) -> anyhow::Result<Response> {
+        if let Err(retry_in) = self.breaker.check() {
+            return Err(ProviderError::CircuitOpen {
+                provider: self.name,
+                retry_in,
+            }
+            .into());
+        }
+
+        let mut attempt = 0;
+        loop {
+            let Some(this_attempt) = request.try_clone() else {
+                let result = self.attempt(endpoint, compute_units, request).await;
+                self.breaker
+                    .record(result.as_ref().is_ok_and(|r| !is_retryable(r.status())));
+                return Ok(result?);
+            };
+            let result = self.attempt(endpoint, compute_units, this_attempt).await;
+            let retry = match &result {
+                Ok(resp) if is_retryable(resp.status()) => Some(retry_after(resp)),
+                Ok(_) => None,
+                Err(_) => Some(None),
+            };
+            let Some(retry_after) = retry else {
+                self.breaker.record(true);
+                return Ok(result?);
+            };
+
+            let delay = retry_after.unwrap_or(self.retry_base * 2u32.pow(attempt));
+            if attempt >= self.max_retries || delay > self.retry_max {
+                self.breaker.record(false);
+                return Ok(result?);
+            }
+            match &result {
+                Ok(resp) => warn!(
+                    "{} answered {}, retry in {delay:?}",
+                    self.name,
+                    resp.status()
+                ),
+                Err(e) => warn!("{} request failed: {e}, retry in {delay:?}", self.name),
+            }
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// One attempt of a call, through the limiter when the provider has one.
+    async fn attempt(
+        &self,
+        endpoint: &'static str,
+        compute_units: u32,
+        request: RequestBuilder,
+    ) -> reqwest::Result<Response> {
+        let Some(limiter) = &self.limiter else {
+            return request.send().await;
+        };
+        limiter.acquire(endpoint, compute_units).await;
+        let result = request.send().await;
+        match &result {
+            Ok(resp) if resp.status().is_success() => {}
+            Ok(resp) => {
+                limiter.record_failure(endpoint, resp.status() == StatusCode::TOO_MANY_REQUESTS)
+            }
+            Err(_) => limiter.record_failure(endpoint, false),
+        }
+        result
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// The `Retry-After` header, either in seconds or as an HTTP date.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(secs) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value)
+        .ok()?
+        .with_timezone(&Utc);
+    Some((at - Utc::now()).to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::http::HeaderMap;
+    use axum::routing::get;
+    use axum::Router;
+    use envconfig::Envconfig;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn http(overrides: &[(&str, &str)]) -> ProviderHttp {
+        let mut config = HashMap::from([("PROVIDER_RETRY_BASE_MS".to_string(), "10".to_string())]);
+        for (key, value) in overrides {
+            config.insert(key.to_string(), value.to_string());
+        }
+        let config = ProviderHttpConfig::init_from_hashmap(&config).unwrap();
+        ProviderHttp::new("test", Client::new(), 1, &config)
+    }
+
+    /// Serve `answers` in turn, the last one is repeated.
+    async fn serve(answers: Vec<(StatusCode, HeaderMap)>) -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let router = Router::new().route(
+            "/",
+            get(move || {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                let answer = answers[call.min(answers.len() - 1)].clone();
+                async move { answer }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        (url, calls)
+    }
+
+    #[tokio::test]
+    async fn should_retry_server_errors_honoring_retry_after() {
+        let mut retry_after = HeaderMap::new();
+        retry_after.insert(RETRY_AFTER, "1".parse().unwrap());
+        let (url, calls) = serve(vec![
+            (StatusCode::TOO_MANY_REQUESTS, retry_after),
+            (StatusCode::BAD_GATEWAY, HeaderMap::new()),
+            (StatusCode::OK, HeaderMap::new()),
+        ])
+        .await;
+        let http = http(&[]);
+
+        let started = Instant::now();
+        let resp = http.send(http.get(&url)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn should_pass_every_attempt_through_the_limiter() {
+        let (url, calls) = serve(vec![
+            (StatusCode::TOO_MANY_REQUESTS, HeaderMap::new()),
+            (StatusCode::BAD_GATEWAY, HeaderMap::new()),
+            (StatusCode::OK, HeaderMap::new()),
+        ])
+        .await;
+        let limiter = Arc::new(Limiter::new(100, 0));
+        let http = http(&[]).with_limiter(limiter.clone());
+
+        let resp = http.send_to("overview", 30, http.get(&url)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        let usage = &limiter.usage()["overview"];
+        assert_eq!(usage.calls, 3);
+        assert_eq!(usage.compute_units, 90);
+        assert_eq!(usage.failures, 2);
+        assert_eq!(usage.rate_limited, 1);
+    }
+
+    #[tokio::test]
+    async fn should_not_retry_client_errors() {
+        let (url, calls) = serve(vec![(StatusCode::NOT_FOUND, HeaderMap::new())]).await;
+        let http = http(&[]);
+
+        let resp = http.send(http.get(&url)).await.unwrap();
+
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn should_fail_fast_while_circuit_is_open() {
+        let (url, calls) = serve(vec![(StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new())]).await;
+        let http = http(&[
+            ("PROVIDER_MAX_RETRIES", "1"),
+            ("PROVIDER_BREAKER_FAILURES", "2"),
+        ]);
+
+        for _ in 0..2 {
+            let resp = http.send(http.get(&url)).await.unwrap();
+            assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let error = http.send(http.get(&url)).await.unwrap_err();
+        assert!(error.is::<ProviderError>());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn should_let_one_trial_call_through_after_cooldown() {
+        let (url, calls) = serve(vec![
+            (StatusCode::SERVICE_UNAVAILABLE, HeaderMap::new()),
+            (StatusCode::OK, HeaderMap::new()),
+        ])
+        .await;
+        let http = http(&[
+            ("PROVIDER_MAX_RETRIES", "0"),
+            ("PROVIDER_BREAKER_FAILURES", "1"),
+            ("PROVIDER_BREAKER_COOLDOWN_SECS", "1"),
+        ]);
+        http.send(http.get(&url)).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        let (trial, other) = tokio::join!(http.send(http.get(&url)), async {
+            tokio::task::yield_now().await;
+            http.send(http.get(&url)).await
+        });
+
+        assert_eq!(trial.unwrap().status(), StatusCode::OK);
+        assert!(other.unwrap_err().is::<ProviderError>());
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        let closed = http.send(http.get(&url)).await.unwrap();
+        assert_eq!(closed.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,373 @@
+use redis::Script;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+use crate::redis_store::RedisStore;
+
+/// Who a provider call is made for, interactive requests are served before background ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Interactive,
+    Background,
+}
+
+tokio::task_local! {
+    static LANE: Lane;
+}
+
+impl Lane {
+    /// The lane of the running task, calls made outside of [`Lane::scope`] are interactive.
+    pub fn current() -> Self {
+        LANE.try_with(|lane| *lane).unwrap_or(Lane::Interactive)
+    }
+
+    /// Run `future` with every provider call it makes in this lane.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        LANE.scope(self, future).await
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    per_sec: f64,
+    tokens: f64,
+}
+
+impl Bucket {
+    fn new(capacity: f64, per_sec: f64) -> Self {
+        Self {
+            capacity,
+            per_sec,
+            tokens: capacity,
+        }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.per_sec).min(self.capacity);
+    }
+
+    /// Time until `amount` tokens are available.
+    fn wait_for(&self, amount: f64) -> Duration {
+        let missing = amount.min(self.capacity) - self.tokens;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.per_sec)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    updated_at: Instant,
+    requests: Bucket,
+    compute_units: Option<Bucket>,
+    usage: BTreeMap<&'static str, EndpointUsage>,
+}
+
+/// Calls made to one endpoint since start.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointUsage {
+    pub calls: u64,
+    pub interactive_calls: u64,
+    pub background_calls: u64,
+    pub compute_units: u64,
+    pub throttled: u64,
+    pub rate_limited: u64,
+    pub failures: u64,
+}
+
+/// Counts a call in the fixed windows of the deployment, answers 0 when it fits, 1 when the
+/// request window is full and 2 when the compute unit window is.
+const RESERVE_SCRIPT: &str = r#"
+local requests = redis.call('INCR', KEYS[1])
+if requests == 1 then redis.call('EXPIRE', KEYS[1], 2) end
+if requests > tonumber(ARGV[1]) then
+    redis.call('DECR', KEYS[1])
+    return 1
+end
+local cost = tonumber(ARGV[2])
+local budget = tonumber(ARGV[3])
+if budget > 0 then
+    local spent = redis.call('INCRBY', KEYS[2], cost)
+    if spent == cost then redis.call('EXPIRE', KEYS[2], 120) end
+    if spent > budget then
+        redis.call('DECRBY', KEYS[2], cost)
+        redis.call('DECR', KEYS[1])
+        return 2
+    end
+end
+return 0
+"#;
+
+/// Budget of a provider shared by every instance through Redis, the calls are counted per
+/// second and per minute.
+struct SharedBudget {
+    redis: RedisStore,
+    name: String,
+    requests_per_sec: u32,
+    compute_units_per_minute: u32,
+    script: Script,
+}
+
+impl SharedBudget {
+    /// Wait until the call fits in the windows of the deployment, a Redis failure lets it through
+    /// with only the local limit applied.
+    async fn reserve(&self, compute_units: u32) {
+        let cost = match self.compute_units_per_minute {
+            0 => compute_units,
+            budget => compute_units.min(budget),
+        };
+        loop {
+            let now = chrono::Utc::now().timestamp_millis();
+            let (second, minute) = (now / 1000, now / 60_000);
+            let result: redis::RedisResult<u8> = self
+                .script
+                .key(
+                    self.redis
+                        .key(&format!("budget:{}:req:{second}", self.name)),
+                )
+                .key(self.redis.key(&format!("budget:{}:cu:{minute}", self.name)))
+                .arg(self.requests_per_sec)
+                .arg(cost)
+                .arg(self.compute_units_per_minute)
+                .invoke_async(&mut self.redis.connection())
+                .await;
+            let window_end = match result {
+                Ok(0) => return,
+                Ok(1) => (second + 1) * 1000,
+                Ok(_) => (minute + 1) * 60_000,
+                Err(err) => {
+                    warn!("shared {} budget unavailable: {err}", self.name);
+                    return;
+                }
+            };
+            tokio::time::sleep(Duration::from_millis((window_end - now).max(10) as u64)).await;
+        }
+    }
+}
+
+impl std::fmt::Debug for SharedBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedBudget")
+            .field("name", &self.name)
+            .field("requests_per_sec", &self.requests_per_sec)
+            .field("compute_units_per_minute", &self.compute_units_per_minute)
+            .finish()
+    }
+}
+
+/// Token bucket limiter of a provider, bounding both the request rate and the compute units
+/// spent per minute, with interactive calls going ahead of background ones.
+///
+/// The buckets bound the calls of this instance, [`Limiter::shared`] also bounds those of the
+/// whole deployment.
+#[derive(Debug)]
+pub struct Limiter {
+    state: Mutex<LimiterState>,
+    interactive_waiting: AtomicUsize,
+    requests_per_sec: u32,
+    compute_units_per_minute: u32,
+    shared: Option<SharedBudget>,
+}
+
+impl Limiter {
+    /// A `compute_units_per_minute` of 0 disables the compute unit budget.
+    pub fn new(requests_per_sec: u32, compute_units_per_minute: u32) -> Self {
+        let requests = requests_per_sec.max(1) as f64;
+        let compute_units = (compute_units_per_minute > 0).then(|| {
+            let per_minute = compute_units_per_minute as f64;
+            Bucket::new(per_minute, per_minute / 60.0)
+        });
+        Self {
+            state: Mutex::new(LimiterState {
+                updated_at: Instant::now(),
+                requests: Bucket::new(requests, requests),
+                compute_units,
+                usage: BTreeMap::new(),
+            }),
+            interactive_waiting: AtomicUsize::new(0),
+            requests_per_sec: requests_per_sec.max(1),
+            compute_units_per_minute,
+            shared: None,
+        }
+    }
+
+    /// Also count the calls in Redis under `name` so that the instances sharing `redis` stay
+    /// within the same budget together.
+    pub fn shared(mut self, redis: RedisStore, name: &str) -> Self {
+        self.shared = Some(SharedBudget {
+            redis,
+            name: name.into(),
+            requests_per_sec: self.requests_per_sec,
+            compute_units_per_minute: self.compute_units_per_minute,
+            script: Script::new(RESERVE_SCRIPT),
+        });
+        self
+    }
+
+    /// Wait until a call to `endpoint` costing `compute_units` fits in the budget and count it.
+    pub async fn acquire(&self, endpoint: &'static str, compute_units: u32) {
+        let lane = Lane::current();
+        let _waiting = (lane == Lane::Interactive).then(|| WaitingGuard::new(self));
+        let cost = compute_units as f64;
+        let mut throttled = false;
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.updated_at);
+                state.updated_at = now;
+                state.requests.refill(elapsed);
+                if let Some(bucket) = state.compute_units.as_mut() {
+                    bucket.refill(elapsed);
+                }
+
+                let wait = state.requests.wait_for(1.0).max(
+                    state
+                        .compute_units
+                        .as_ref()
+                        .map_or(Duration::ZERO, |bucket| bucket.wait_for(cost)),
+                );
+                let yield_to_interactive = lane == Lane::Background
+                    && self.interactive_waiting.load(Ordering::Acquire) > 0;
+                if wait.is_zero() && !yield_to_interactive {
+                    state.requests.tokens -= 1.0;
+                    if let Some(bucket) = state.compute_units.as_mut() {
+                        bucket.tokens -= cost.min(bucket.capacity);
+                    }
+                    let usage = state.usage.entry(endpoint).or_default();
+                    usage.calls += 1;
+                    usage.compute_units += compute_units as u64;
+                    usage.throttled += throttled as u64;
+                    match lane {
+                        Lane::Interactive => usage.interactive_calls += 1,
+                        Lane::Background => usage.background_calls += 1,
+                    }
+                    None
+                } else {
+                    Some(wait.max(Duration::from_millis(10)))
+                }
+            };
+            match wait {
+                Some(wait) => {
+                    throttled = true;
+                    tokio::time::sleep(wait).await;
+                }
+                None => break,
+            }
+        }
+        if let Some(shared) = &self.shared {
+            shared.reserve(compute_units).await;
+        }
+    }
+
+    /// Count a call to `endpoint` answered with a non success status.
+    pub fn record_failure(&self, endpoint: &'static str, rate_limited: bool) {
+        let mut state = self.state.lock().unwrap();
+        let usage = state.usage.entry(endpoint).or_default();
+        usage.failures += 1;
+        usage.rate_limited += rate_limited as u64;
+    }
+
+    pub fn usage(&self) -> BTreeMap<&'static str, EndpointUsage> {
+        self.state.lock().unwrap().usage.clone()
+    }
+}
+
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaitingGuard<'a> {
+    fn new(limiter: &'a Limiter) -> Self {
+        limiter.interactive_waiting.fetch_add(1, Ordering::AcqRel);
+        Self(&limiter.interactive_waiting)
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn should_limit_requests_and_compute_units() {
+        let limiter = Limiter::new(100, 600);
+        let started = Instant::now();
+        // 600 CU per minute is 10 CU per second, the burst covers the first 600 CU
+        for _ in 0..6 {
+            limiter.acquire("token_overview", 100).await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(100));
+        limiter.acquire("token_overview", 5).await;
+        assert!(started.elapsed() >= Duration::from_millis(450));
+
+        let usage = &limiter.usage()["token_overview"];
+        assert_eq!(usage.calls, 7);
+        assert_eq!(usage.compute_units, 605);
+        assert_eq!(usage.throttled, 1);
+    }
+
+    #[tokio::test]
+    async fn should_serve_interactive_calls_first() {
+        let limiter = Arc::new(Limiter::new(5, 0));
+        for _ in 0..5 {
+            limiter.acquire("drain", 1).await;
+        }
+
+        let order = Arc::new(Mutex::new(vec![]));
+        let background = {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            tokio::spawn(Lane::Background.scope(async move {
+                limiter.acquire("holder", 1).await;
+                order.lock().unwrap().push(Lane::current());
+            }))
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let interactive = {
+            let (limiter, order) = (limiter.clone(), order.clone());
+            tokio::spawn(async move {
+                limiter.acquire("search", 1).await;
+                order.lock().unwrap().push(Lane::current());
+            })
+        };
+        background.await.unwrap();
+        interactive.await.unwrap();
+
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![Lane::Interactive, Lane::Background]
+        );
+        assert_eq!(limiter.usage()["holder"].background_calls, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_REDIS_URL"]
+    async fn should_share_compute_units_through_redis() {
+        let redis = crate::redis_store::test::test_store().await;
+        let first = Limiter::new(100, 100).shared(redis.clone(), "birdeye");
+        let second = Limiter::new(100, 100).shared(redis, "birdeye");
+
+        first.acquire("token_overview", 60).await;
+        // the local bucket of `second` is full, the minute window of the deployment is not
+        let blocked = tokio::time::timeout(
+            Duration::from_millis(200),
+            second.acquire("token_overview", 60),
+        )
+        .await;
+        assert!(blocked.is_err());
+        second.acquire("token_overview", 30).await;
+    }
+}
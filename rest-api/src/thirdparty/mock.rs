@@ -0,0 +1,278 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+use crate::fearandgreed::{FearAndGreedApiResponse, FearAndGreedSdk};
+use crate::price::{Candle, PriceSdk, TimeFilters};
+use crate::thirdparty::alternative_api::AlternativeResponse;
+use crate::thirdparty::defi::{DefiLlamaVolumeResponse, VolumeSdk};
+use crate::thirdparty::token_search::TokenSearchResult;
+use crate::thirdparty::{
+    BirdEyeResponse, ItemsResponse, MunScoreData, MunScoreSdk, OhlcvItem, PriceHistory, SafeScore,
+    SafetySdk, TokenData, TrendingResponse,
+};
+use crate::time_util;
+use crate::token::market::TradeData;
+use crate::token::trade::MarketData;
+use crate::token::{TokenHolder, TokenMetadata, TokenOverview, TokenSdk, Trending, UnknownToken};
+
+const TOKEN_TRENDING: &str = include_str!("../../fixtures/birdeye/token_trending.json");
+const META_DATA_MULTIPLE: &str = include_str!("../../fixtures/birdeye/meta_data_multiple.json");
+const TOKEN_OVERVIEW: &str = include_str!("../../fixtures/birdeye/token_overview.json");
+const TOKEN_HOLDER: &str = include_str!("../../fixtures/birdeye/token_holder.json");
+const SEARCH: &str = include_str!("../../fixtures/birdeye/search.json");
+const TRADE_DATA_SINGLE: &str = include_str!("../../fixtures/birdeye/trade_data_single.json");
+const MARKET_DATA: &str = include_str!("../../fixtures/birdeye/market_data.json");
+const PRICE_VOLUME_SINGLE: &str = include_str!("../../fixtures/birdeye/price_volume_single.json");
+const HISTORY_PRICE: &str = include_str!("../../fixtures/birdeye/history_price.json");
+const OHLCV: &str = include_str!("../../fixtures/birdeye/ohlcv.json");
+const FEAR_AND_GREED: &str = include_str!("../../fixtures/alternative/fng.json");
+const TWITTER_INFO_FULL: &str = include_str!("../../fixtures/moni/twitter_info_full.json");
+const ORCHESTRATION: &str = include_str!("../../fixtures/safety/orchestration.json");
+const DEXS_SOLANA: &str = include_str!("../../fixtures/defillama/dexs_solana.json");
+
+/// In-memory provider replaying the responses recorded under `fixtures/`.
+///
+/// Token specific responses are recorded for a single token and re-addressed to the requested
+/// one, so every address resolves but [`UNKNOWN_TOKEN`]. Used by tests and by the
+/// `MOCK_PROVIDERS` mode.
+#[derive(Debug, Default, Clone)]
+pub struct MockClient;
+
+/// The system program, a valid address that is not a mint.
+pub const UNKNOWN_TOKEN: &str = "11111111111111111111111111111111";
+
+fn birdeye<T: DeserializeOwned>(fixture: &str) -> Result<T, Error> {
+    Ok(serde_json::from_str::<BirdEyeResponse<T>>(fixture)?.data)
+}
+
+#[async_trait]
+impl TokenSdk for MockClient {
+    async fn get_trending(&self, offset: i32, limit: i32) -> Result<Vec<Trending>, Error> {
+        let tokens = birdeye::<TrendingResponse>(TOKEN_TRENDING)?.tokens;
+        Ok(tokens
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn token_meta_multiple(
+        &self,
+        addresses: Vec<String>,
+    ) -> Result<Vec<TokenMetadata>, Error> {
+        let metadata = birdeye::<HashMap<String, TokenMetadata>>(META_DATA_MULTIPLE)?;
+        Ok(metadata
+            .into_iter()
+            .filter(|(address, _)| addresses.contains(address))
+            .map(|(_, meta)| meta)
+            .collect())
+    }
+
+    async fn overview(&self, address: &str) -> Result<TokenOverview, Error> {
+        if address == UNKNOWN_TOKEN {
+            return Err(UnknownToken(address.to_string()).into());
+        }
+        let mut overview = birdeye::<TokenOverview>(TOKEN_OVERVIEW)?;
+        overview.address = address.to_string();
+        Ok(overview)
+    }
+
+    async fn holders(
+        &self,
+        address: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<TokenHolder>, Error> {
+        let holders = birdeye::<ItemsResponse<TokenHolder>>(TOKEN_HOLDER)?.items;
+        Ok(holders
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|holder| TokenHolder {
+                mint: address.to_string(),
+                ..holder
+            })
+            .collect())
+    }
+
+    async fn search(&self, _address: &str) -> Result<Vec<TokenOverview>, Error> {
+        let results = birdeye::<ItemsResponse<TokenSearchResult>>(SEARCH)?.items;
+        Ok(results
+            .into_iter()
+            .flat_map(Vec::<TokenOverview>::from)
+            .collect())
+    }
+
+    async fn trade_data(&self, address: &str) -> Result<TradeData, Error> {
+        let mut trade_data = birdeye::<TradeData>(TRADE_DATA_SINGLE)?;
+        trade_data.address = address.to_string();
+        Ok(trade_data)
+    }
+
+    async fn market_data(&self, address: &str) -> Result<MarketData, Error> {
+        let mut market_data = birdeye::<MarketData>(MARKET_DATA)?;
+        market_data.address = address.to_string();
+        Ok(market_data)
+    }
+}
+
+#[async_trait]
+impl PriceSdk for MockClient {
+    async fn get_price(&self, _token: &str) -> Result<TokenData, Error> {
+        birdeye(PRICE_VOLUME_SINGLE)
+    }
+
+    async fn get_prices(&self, tokens: &[String]) -> Result<HashMap<String, TokenData>, Error> {
+        tokens
+            .iter()
+            .filter(|token| token.as_str() != UNKNOWN_TOKEN)
+            .map(|token| Ok((token.clone(), birdeye(PRICE_VOLUME_SINGLE)?)))
+            .collect()
+    }
+
+    async fn get_price_by_time_filter(
+        &self,
+        _token: &str,
+        _filter: TimeFilters,
+    ) -> Result<PriceHistory, Error> {
+        birdeye(HISTORY_PRICE)
+    }
+
+    async fn get_ohlcv(
+        &self,
+        token: &str,
+        interval: TimeFilters,
+        time_from: i64,
+        time_to: i64,
+    ) -> Result<Vec<Candle>, Error> {
+        if token == UNKNOWN_TOKEN {
+            return Ok(Vec::new());
+        }
+        // Replay the recorded candles in turn, one per interval of the requested range.
+        let recorded: Vec<Candle> = birdeye::<ItemsResponse<OhlcvItem>>(OHLCV)?
+            .items
+            .into_iter()
+            .map(Candle::from)
+            .collect();
+        let start = interval.align(time_from);
+        let start = if start < time_from {
+            start + interval.as_secs()
+        } else {
+            start
+        };
+        Ok((start..=time_to)
+            .step_by(interval.as_secs() as usize)
+            .take(1000)
+            .zip(recorded.into_iter().cycle())
+            .map(|(unix_time, candle)| Candle {
+                unix_time,
+                ..candle
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl FearAndGreedSdk for MockClient {
+    async fn get_fear_and_greed(&self, limit: i8) -> Result<Vec<FearAndGreedApiResponse>, Error> {
+        // Re-date the daily entries so the newest one is today, like the live index.
+        let today = time_util::get_start_of_day(Utc::now());
+        let resp = serde_json::from_str::<AlternativeResponse>(FEAR_AND_GREED)?;
+        Ok(resp
+            .data
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .enumerate()
+            .map(|(days_ago, data)| FearAndGreedApiResponse {
+                timestamp: (today - Duration::days(days_ago as i64))
+                    .timestamp()
+                    .to_string(),
+                ..FearAndGreedApiResponse::from(data)
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl MunScoreSdk for MockClient {
+    async fn get_mun_score(&self, _username: &str) -> Result<MunScoreData, Error> {
+        Ok(serde_json::from_str(TWITTER_INFO_FULL)?)
+    }
+}
+
+#[async_trait]
+impl SafetySdk for MockClient {
+    async fn get_safe_score(&self, _token: &str) -> Result<f64, Error> {
+        Ok(serde_json::from_str::<SafeScore>(ORCHESTRATION)?
+            .orchestration
+            .safety_score)
+    }
+}
+
+#[async_trait]
+impl VolumeSdk for MockClient {
+    async fn get_blockchain_volum(&self, _chain: &str) -> Result<DefiLlamaVolumeResponse, Error> {
+        Ok(serde_json::from_str(DEXS_SOLANA)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MockClient;
+    use crate::fearandgreed::FearAndGreedSdk;
+    use crate::price::{PriceSdk, TimeFilters};
+    use crate::thirdparty::defi::VolumeSdk;
+    use crate::thirdparty::{MunScoreSdk, SafetySdk};
+    use crate::token::TokenSdk;
+
+    const ADDRESS: &str = "34HDZNbUkTyTrgYKy2ox43yp2f8PJ5hoM7xsrfNApump";
+
+    #[tokio::test]
+    async fn should_replay_every_token_fixture() {
+        let client = MockClient;
+
+        assert_eq!(client.get_trending(0, 5).await.unwrap().len(), 5);
+        assert_eq!(client.overview(ADDRESS).await.unwrap().address, ADDRESS);
+        assert_eq!(client.trade_data(ADDRESS).await.unwrap().address, ADDRESS);
+        assert_eq!(client.market_data(ADDRESS).await.unwrap().address, ADDRESS);
+        assert_eq!(client.search("arc").await.unwrap().len(), 1);
+        let metadata = client
+            .token_meta_multiple(vec!["So11111111111111111111111111111111111111112".into()])
+            .await
+            .unwrap();
+        assert_eq!(metadata[0].symbol, "SOL");
+    }
+
+    #[tokio::test]
+    async fn should_page_holders() {
+        let client = MockClient;
+
+        let first = client.holders(ADDRESS, 0, 10).await.unwrap();
+        let rest = client.holders(ADDRESS, 10, 10).await.unwrap();
+
+        assert_eq!(first.len(), 10);
+        assert_eq!(rest.len(), 2);
+        assert!(first.iter().all(|h| h.mint == ADDRESS));
+    }
+
+    #[tokio::test]
+    async fn should_replay_price_sentiment_and_score_fixtures() {
+        let client = MockClient;
+
+        assert!(client.get_price(ADDRESS).await.unwrap().price > 0.0);
+        let history = client
+            .get_price_by_time_filter(ADDRESS, TimeFilters::OneDay)
+            .await
+            .unwrap();
+        assert_eq!(history.items.len(), 8);
+        assert_eq!(client.get_fear_and_greed(31).await.unwrap().len(), 31);
+        let score = client.get_mun_score("arcdotfun").await.unwrap();
+        assert_eq!(score.smart_engagement.followers_score, 812);
+        assert_eq!(client.get_safe_score(ADDRESS).await.unwrap(), 62.2);
+        let volume = client.get_blockchain_volum("solana").await.unwrap();
+        assert_eq!(volume.total24h, 1446817296);
+    }
+}
@@ -1,7 +1,12 @@
 pub mod alternative_api;
 pub mod birdeye;
 pub mod defi;
+pub mod helius;
+pub mod http;
+pub mod limiter;
+pub mod mock;
 pub mod moni;
+pub mod route;
 mod safety;
 pub mod token_search;
 
@@ -1,15 +1,17 @@
 use anyhow::Error;
-use reqwest::Client;
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
+use super::http::ProviderHttp;
+
 pub struct MoniClient {
-    pub client: Client,
+    pub http: ProviderHttp,
     pub moni_key: String,
 }
 
 impl MoniClient {
-    pub fn new(moni_key: String, client: Client) -> Self {
-        Self { moni_key, client }
+    pub fn new(moni_key: String, http: ProviderHttp) -> Self {
+        Self { moni_key, http }
     }
 }
 
@@ -20,19 +22,20 @@ const BASE_URL: &str = "https://api.discover.getmoni.io/api/v2";
 // --header 'Api-Key: aaaaaaa' \
 // --header 'accept: application/json'
 
-pub trait MunScoreSdk {
+#[async_trait]
+pub trait MunScoreSdk: Send + Sync {
     async fn get_mun_score(&self, username: &str) -> Result<MunScoreData, Error>;
 }
 
+#[async_trait]
 impl MunScoreSdk for MoniClient {
     async fn get_mun_score(&self, username: &str) -> Result<MunScoreData, Error> {
         let url = format!("{BASE_URL}/twitters/{username}/info/full");
-        let resp = self
-            .client
+        let request = self
+            .http
             .get(&url)
-            .header("Api-Key", self.moni_key.as_str())
-            .send()
-            .await?;
+            .header("Api-Key", self.moni_key.as_str());
+        let resp = self.http.send(request).await?;
 
         let status = resp.status();
         if !status.is_success() {
@@ -54,6 +57,7 @@ pub struct MunScoreData {
     //pub smart_profile: SmartProfile,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Meta {
@@ -69,6 +73,7 @@ pub struct Meta {
     pub links: Vec<Link>,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Link {
@@ -87,6 +92,7 @@ pub struct SmartEngagement {
     pub smart_mentions_count: u64,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SmartProfile {
@@ -98,6 +104,7 @@ pub struct SmartProfile {
     pub bio_changes_count: u64,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SmartTier {
@@ -105,6 +112,7 @@ pub struct SmartTier {
     pub logo_url: String,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SmartTag {
@@ -113,6 +121,7 @@ pub struct SmartTag {
     pub total_count: u64,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SmartTagCategory {
@@ -120,6 +129,7 @@ pub struct SmartTagCategory {
     pub name: String,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectTag {
@@ -127,6 +137,7 @@ pub struct ProjectTag {
     pub name: String,
 }
 
+#[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Chain {
@@ -136,14 +147,19 @@ pub struct Chain {
 
 #[cfg(test)]
 mod test {
+    use crate::config::ProviderHttpConfig;
+    use crate::thirdparty::http::ProviderHttp;
     use crate::thirdparty::{MoniClient, MunScoreSdk};
+    use envconfig::Envconfig;
 
     #[tokio::test]
+    #[ignore = "calls the live Moni API"]
     async fn test_get_mun_score() {
         let key = "5e1738d2-79a1-40d4-9977-6a8425f2a721";
         let username = "elonmusk";
-        let client = reqwest::Client::new();
-        let moni_client = MoniClient::new(key.into(), client);
+        let config = ProviderHttpConfig::init_from_hashmap(&Default::default()).unwrap();
+        let http = ProviderHttp::new("moni", reqwest::Client::new(), 10, &config);
+        let moni_client = MoniClient::new(key.into(), http);
         let data = moni_client.get_mun_score(username).await.unwrap();
         println!("{:?}", data);
     }
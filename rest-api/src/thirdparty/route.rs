@@ -0,0 +1,29 @@
+use std::collections::BTreeMap;
+
+use axum::extract::State;
+use axum::Json;
+use chrono::Utc;
+
+use crate::app::AppState;
+use crate::error::ApiError;
+use crate::response::HttpResponse;
+use crate::thirdparty::limiter::EndpointUsage;
+
+/// Usage of each endpoint, by provider.
+pub type ProviderUsage = BTreeMap<&'static str, BTreeMap<&'static str, EndpointUsage>>;
+
+/// Calls made to each provider endpoint since start.
+pub async fn provider_usage(
+    State(app): State<AppState>,
+) -> Result<Json<HttpResponse<ProviderUsage>>, ApiError> {
+    let usage = app
+        .limiters
+        .iter()
+        .map(|(provider, limiter)| (*provider, limiter.usage()))
+        .collect();
+    Ok(Json(HttpResponse {
+        code: 200,
+        response: usage,
+        last_updated: Utc::now().timestamp(),
+    }))
+}
@@ -1,6 +1,8 @@
-use reqwest::Client;
+use async_trait::async_trait;
 use serde::Deserialize;
 
+use super::http::ProviderHttp;
+
 const SAFE_API_URL: &str =
     "https://l7db1lpgkb.execute-api.us-east-2.amazonaws.com/prod/orchestration";
 
@@ -14,13 +16,35 @@ pub struct Orchestration {
     pub safety_score: f64,
 }
 
-pub async fn get_safe_score(client: &Client, token: &str) -> Result<f64, anyhow::Error> {
-    let response = client
+#[async_trait]
+pub trait SafetySdk: Send + Sync {
+    async fn get_safe_score(&self, token: &str) -> Result<f64, anyhow::Error>;
+}
+
+#[derive(Clone)]
+pub struct SafetyClient {
+    http: ProviderHttp,
+}
+
+impl SafetyClient {
+    pub fn new(http: ProviderHttp) -> Self {
+        Self { http }
+    }
+}
+
+#[async_trait]
+impl SafetySdk for SafetyClient {
+    async fn get_safe_score(&self, token: &str) -> Result<f64, anyhow::Error> {
+        get_safe_score(&self.http, token).await
+    }
+}
+
+pub async fn get_safe_score(http: &ProviderHttp, token: &str) -> Result<f64, anyhow::Error> {
+    let request = http
         .get(SAFE_API_URL)
         .query(&[("ca", token)])
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await?;
+        .header("Authorization", format!("Bearer {}", token));
+    let response = http.send(request).await?;
 
     if !response.status().is_success() {
         return Err(anyhow::anyhow!(
@@ -38,11 +62,15 @@ mod test {
     use crate::thirdparty::safety::get_safe_score;
 
     #[tokio::test]
+    #[ignore = "calls the live safety API"]
     async fn test_get_safe_score() {
-        use reqwest::Client;
-        let client = Client::new();
+        use crate::config::ProviderHttpConfig;
+        use crate::thirdparty::http::ProviderHttp;
+        use envconfig::Envconfig;
+        let config = ProviderHttpConfig::init_from_hashmap(&Default::default()).unwrap();
+        let http = ProviderHttp::new("safety", reqwest::Client::new(), 20, &config);
         let token = "34HDZNbUkTyTrgYKy2ox43yp2f8PJ5hoM7xsrfNApump";
-        let score = get_safe_score(&client, token).await.unwrap();
+        let score = get_safe_score(&http, token).await.unwrap();
         println!("Safe score: {score}");
     }
 
@@ -1,4 +1,16 @@
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres};
+
+use crate::time_util;
+
+/// Number of daily snapshots shown in the analytics historical series.
+const HISTORY_DAYS: i64 = 7;
+/// Minimum mun score for a token to be ranked `ALPHA`.
+const ALPHA_LEVEL_SCORE: u32 = 700;
+/// Minimum mun score for a token to be ranked `BETA`.
+const BETA_LEVEL_SCORE: u32 = 300;
 
 // Main struct for the entire data structure
 #[derive(Debug, Serialize, Deserialize)]
@@ -71,207 +83,260 @@ pub struct MentionMetrics {
     mention_numbers_historical: Vec<HistoricalValue>,
 }
 
-pub fn create_dummy_token_analysis() -> TokenAnalytics {
-    TokenAnalytics {
-        market_cap: 1_234_567.89,
-        market_cap_change_7d: -5.43,
-        market_cap_7d_historical_values: vec![
-            HistoricalValue {
-                value: 1_230_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-            HistoricalValue {
-                value: 1_234_567.89,
-                time: Some(1711586400),
-                label: None,
-            },
-            HistoricalValue {
-                value: 1_434_567.89,
-                time: Some(1711586400),
-                label: None,
-            },
-            HistoricalValue {
-                value: 1_134_567.89,
-                time: Some(1711586400),
-                label: None,
-            },
-            HistoricalValue {
-                value: 1_734_567.89,
-                time: Some(1711586400),
-                label: None,
-            },
-        ],
-        volume_24h: 98_765.43,
-        volume_24h_change_7d: 2.1,
-        volume_historical: vec![
-            HistoricalValue {
-                value: 95_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-            HistoricalValue {
-                value: 95_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-            HistoricalValue {
-                value: 98_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-            HistoricalValue {
-                value: 100_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-            HistoricalValue {
-                value: 195_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-            HistoricalValue {
-                value: 185_000.0,
-                time: Some(1711500000),
-                label: Some("Day 1".to_string()),
-            },
-        ],
-        liquidity: 45_678.9,
-        liquidity_change: 1.5,
-        liquidity_historical: vec![
-            HistoricalValue {
-                value: 45_000.0,
-                time: Some(1711500000),
-                label: None,
-            },
-            HistoricalValue {
-                value: 45_000.0,
-                time: Some(1711500000),
-                label: None,
-            },
-            HistoricalValue {
-                value: 45_000.0,
-                time: Some(1711500000),
-                label: None,
-            },
-            HistoricalValue {
-                value: 45_000.0,
-                time: Some(1711500000),
-                label: None,
-            },
-            HistoricalValue {
-                value: 45_000.0,
-                time: Some(1711500000),
-                label: None,
-            },
-        ],
-        holders: 5000,
-        holders_change_7d: -200,
-        holders_historical: vec![HistoricalValue {
-            value: 5200.0,
-            time: Some(1711500000),
-            label: Some("Day 1".to_string()),
-        }],
-        moon_score: 960,
-        level: Level::Alpha,
-        risk_score: 10.96,
-        top_followers: vec![
-            FollowerProfile {
-                profile_url: "https://x.com/user1".to_string(),
-                tag: "@user1".to_string(),
-                name: "User One".to_string(),
-                followers: 10_000,
-            },
-            FollowerProfile {
-                profile_url: "https://x.com/user2".to_string(),
-                tag: "@user2".to_string(),
-                name: "User Two".to_string(),
-                followers: 5_000,
-            },
-            FollowerProfile {
-                profile_url: "https://x.com/user2".to_string(),
-                tag: "@user2".to_string(),
-                name: "User Two".to_string(),
-                followers: 5_000,
-            },
-            FollowerProfile {
-                profile_url: "https://x.com/user2".to_string(),
-                tag: "@user2".to_string(),
-                name: "User Two".to_string(),
-                followers: 5_000,
+#[derive(Debug, sqlx::FromRow)]
+pub struct TokenSnapshot {
+    pub record_date: i64,
+    pub marketcap: Option<BigDecimal>,
+    pub volume_24h: Option<BigDecimal>,
+    pub liquidity: Option<BigDecimal>,
+    pub holders: Option<i32>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct TokenAnalyticsSummary {
+    pub marketcap: Option<BigDecimal>,
+    pub volume_24h: Option<BigDecimal>,
+    pub liquidity: Option<BigDecimal>,
+    pub holders: Option<i32>,
+    pub mun_score: BigDecimal,
+    pub risk_score: BigDecimal,
+    pub smart_followers: i64,
+}
+
+impl TokenAnalytics {
+    /// Build the analytics payload from the current `tokens` row and its daily snapshots,
+    /// ordered by `record_date` ascending. Deltas compare the current value with the oldest
+    /// snapshot in the window.
+    pub fn from_snapshots(summary: &TokenAnalyticsSummary, snapshots: &[TokenSnapshot]) -> Self {
+        let market_cap = to_f64(&summary.marketcap);
+        let volume_24h = to_f64(&summary.volume_24h);
+        let liquidity = to_f64(&summary.liquidity);
+        let holders = summary.holders.unwrap_or_default().max(0) as u64;
+        let oldest = snapshots.first();
+        let moon_score = summary.mun_score.to_f64().unwrap_or_default().max(0.0) as u32;
+
+        Self {
+            market_cap,
+            market_cap_change_7d: percent_change(oldest.map(|s| to_f64(&s.marketcap)), market_cap),
+            market_cap_7d_historical_values: historical(snapshots, |s| to_f64(&s.marketcap)),
+            volume_24h,
+            volume_24h_change_7d: percent_change(oldest.map(|s| to_f64(&s.volume_24h)), volume_24h),
+            volume_historical: historical(snapshots, |s| to_f64(&s.volume_24h)),
+            liquidity,
+            liquidity_change: percent_change(oldest.map(|s| to_f64(&s.liquidity)), liquidity),
+            liquidity_historical: historical(snapshots, |s| to_f64(&s.liquidity)),
+            holders,
+            holders_change_7d: oldest
+                .map(|s| holders as i64 - s.holders.unwrap_or_default() as i64)
+                .unwrap_or_default(),
+            holders_historical: historical(snapshots, |s| s.holders.unwrap_or_default() as f64),
+            moon_score,
+            level: Level::from_score(moon_score),
+            risk_score: summary.risk_score.to_f64().unwrap_or_default().max(0.0),
+            top_followers: vec![],
+            followers: FollowerMetrics {
+                follower_number: 0,
+                follower_number_change_7d: 0,
+                smarts: summary.smart_followers.max(0) as u64,
+                smarts_change: 0,
+                follower_numbers_historical: vec![],
             },
-            FollowerProfile {
-                profile_url: "https://x.com/user2".to_string(),
-                tag: "@user2".to_string(),
-                name: "User Two".to_string(),
-                followers: 5_000,
+            mentions: MentionMetrics {
+                mention_number: 0,
+                mention_number_change_7d: 0,
+                smarts: 0,
+                smarts_change: 0,
+                mention_numbers_historical: vec![],
             },
-        ],
-        followers: FollowerMetrics {
-            follower_number: 25_000,
-            follower_number_change_7d: 500,
-            smarts: 3_000,
-            smarts_change: -50,
-            follower_numbers_historical: vec![
-                HistoricalValue {
-                    value: 24_500.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 24_500.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 24_500.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 24_500.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 24_500.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-            ],
-        },
-        mentions: MentionMetrics {
-            mention_number: 1_200,
-            mention_number_change_7d: 100,
-            smarts: 150,
-            smarts_change: 10,
-            mention_numbers_historical: vec![
-                HistoricalValue {
-                    value: 1_100.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 1_100.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 1_100.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 1_100.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-                HistoricalValue {
-                    value: 1_100.0,
-                    time: Some(1711500000),
-                    label: Some("Day 1".to_string()),
-                },
-            ],
-        },
+        }
+    }
+}
+
+impl Level {
+    fn from_score(score: u32) -> Self {
+        match score {
+            s if s >= ALPHA_LEVEL_SCORE => Level::Alpha,
+            s if s >= BETA_LEVEL_SCORE => Level::Beta,
+            _ => Level::Gamma,
+        }
+    }
+}
+
+fn to_f64(value: &Option<BigDecimal>) -> f64 {
+    value.as_ref().and_then(|v| v.to_f64()).unwrap_or_default()
+}
+
+fn percent_change(from: Option<f64>, to: f64) -> f64 {
+    match from {
+        Some(from) if from != 0.0 => (to - from) / from * 100.0,
+        _ => 0.0,
+    }
+}
+
+fn historical(
+    snapshots: &[TokenSnapshot],
+    value: impl Fn(&TokenSnapshot) -> f64,
+) -> Vec<HistoricalValue> {
+    snapshots
+        .iter()
+        .map(|s| HistoricalValue {
+            value: value(s),
+            time: Some(s.record_date),
+            label: DateTime::<Utc>::from_timestamp(s.record_date, 0)
+                .map(|d| d.format("%Y-%m-%d").to_string()),
+        })
+        .collect()
+}
+
+/// Copy the current `tokens` row into today's snapshot so the analytics series can be built.
+pub async fn upsert_daily_snapshot(
+    pool: &Pool<Postgres>,
+    token_address: &str,
+    record_date: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO token_daily_snapshot (token_address, record_date, marketcap, volume_24h, liquidity, holders, price)
+        SELECT token_address, $2, marketcap, volume_24h, liquidity, holders, current_price
+        FROM tokens
+        WHERE token_address = $1
+        ON CONFLICT (token_address, record_date) DO UPDATE SET
+            marketcap = EXCLUDED.marketcap,
+            volume_24h = EXCLUDED.volume_24h,
+            liquidity = EXCLUDED.liquidity,
+            holders = EXCLUDED.holders,
+            price = EXCLUDED.price,
+            recorded_at = NOW()
+        "#,
+    )
+    .bind(token_address)
+    .bind(record_date)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn query_daily_snapshots(
+    pool: &Pool<Postgres>,
+    token_address: &str,
+    from: i64,
+) -> Result<Vec<TokenSnapshot>, sqlx::Error> {
+    sqlx::query_as::<_, TokenSnapshot>(
+        r#"
+        SELECT record_date, marketcap, volume_24h, liquidity, holders
+        FROM token_daily_snapshot
+        WHERE token_address = $1 AND record_date >= $2
+        ORDER BY record_date
+        "#,
+    )
+    .bind(token_address)
+    .bind(from)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn query_analytics_summary(
+    pool: &Pool<Postgres>,
+    token_address: &str,
+) -> Result<Option<TokenAnalyticsSummary>, sqlx::Error> {
+    sqlx::query_as::<_, TokenAnalyticsSummary>(
+        r#"
+        SELECT
+            t.marketcap,
+            t.volume_24h,
+            t.liquidity,
+            t.holders,
+            COALESCE(a.mun_score, 0) as mun_score,
+            COALESCE(a.risk_score, 0) as risk_score,
+            COALESCE(a.smart_followers, 0) as smart_followers
+        FROM tokens t
+        LEFT JOIN alpha_move_token_metric a ON a.token_address = t.token_address
+        WHERE t.token_address = $1
+        "#,
+    )
+    .bind(token_address)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Build the analytics of a token from stored data, `None` if the token is unknown.
+pub async fn token_analytics(
+    pool: &Pool<Postgres>,
+    token_address: &str,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Option<TokenAnalytics>> {
+    let Some(summary) = query_analytics_summary(pool, token_address).await? else {
+        return Ok(None);
+    };
+    let from = time_util::get_start_of_day(now) - Duration::days(HISTORY_DAYS - 1);
+    let snapshots = query_daily_snapshots(pool, token_address, from.timestamp()).await?;
+    Ok(Some(TokenAnalytics::from_snapshots(&summary, &snapshots)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bigdecimal::FromPrimitive;
+
+    fn snapshot(record_date: i64, marketcap: f64, holders: i32) -> TokenSnapshot {
+        TokenSnapshot {
+            record_date,
+            marketcap: BigDecimal::from_f64(marketcap),
+            volume_24h: BigDecimal::from_f64(marketcap / 10.0),
+            liquidity: None,
+            holders: Some(holders),
+        }
+    }
+
+    #[test]
+    fn should_compute_deltas_against_oldest_snapshot() {
+        let summary = TokenAnalyticsSummary {
+            marketcap: BigDecimal::from_f64(1_500.0),
+            volume_24h: BigDecimal::from_f64(300.0),
+            liquidity: None,
+            holders: Some(120),
+            mun_score: BigDecimal::from(750),
+            risk_score: BigDecimal::from(-1),
+            smart_followers: 12,
+        };
+        let snapshots = vec![
+            snapshot(1743465600, 1_000.0, 100),
+            snapshot(1743552000, 1_200.0, 110),
+        ];
+
+        let analytics = TokenAnalytics::from_snapshots(&summary, &snapshots);
+
+        assert_eq!(analytics.market_cap_change_7d, 50.0);
+        assert_eq!(analytics.volume_24h_change_7d, 200.0);
+        assert_eq!(analytics.liquidity_change, 0.0);
+        assert_eq!(analytics.holders_change_7d, 20);
+        assert_eq!(analytics.market_cap_7d_historical_values.len(), 2);
+        assert_eq!(
+            analytics.holders_historical[1].label.as_deref(),
+            Some("2025-04-02")
+        );
+        assert!(matches!(analytics.level, Level::Alpha));
+        assert_eq!(analytics.risk_score, 0.0);
+        assert_eq!(analytics.followers.smarts, 12);
+    }
+
+    #[test]
+    fn should_not_report_change_without_history() {
+        let summary = TokenAnalyticsSummary {
+            marketcap: BigDecimal::from_f64(1_500.0),
+            volume_24h: None,
+            liquidity: None,
+            holders: None,
+            mun_score: BigDecimal::from(0),
+            risk_score: BigDecimal::from(0),
+            smart_followers: 0,
+        };
+
+        let analytics = TokenAnalytics::from_snapshots(&summary, &[]);
+
+        assert_eq!(analytics.market_cap_change_7d, 0.0);
+        assert_eq!(analytics.holders_change_7d, 0);
+        assert!(analytics.volume_historical.is_empty());
+        assert!(matches!(analytics.level, Level::Gamma));
     }
 }
@@ -1,14 +1,22 @@
 use crate::app::AppState;
-use crate::thirdparty::{self, MunScoreSdk};
-use crate::token::{TokenOverview, TokenOverviewResponse, TokenSdk};
-use anyhow::Result;
+use crate::config::TokenWatchConfig;
+use crate::thirdparty::MunScoreData;
+use crate::time_util;
+use crate::token::{
+    query_token_distribution, refresh_token_distribution, reprioritize_token_watch,
+    upsert_daily_snapshot, TokenOverview,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt};
 use log::{error, info};
+use serde::Serialize;
 use sqlx::types::Json;
 use sqlx::{PgPool, Pool, Postgres};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::time::sleep;
+use std::time::Instant;
 
 use super::market::TradeData;
 use super::trade::MarketData;
@@ -16,144 +24,220 @@ use super::trade::MarketData;
 #[derive(Debug, sqlx::FromRow)]
 struct TokenRow {}
 
-pub async fn start_token_fetcher(app_state: Arc<AppState>) {
-    tokio::spawn(async move {
-        loop {
-            if let Err(e) = process_token_watch(&app_state).await {
-                error!("Error processing token fetch queue: {}", e);
-            }
-            // Sleep for 60 seconds between runs.
-            sleep(Duration::from_secs(60)).await;
-        }
-    });
-}
+/// Refresh the tokens due in the watch queue, run by the `token_watch` job.
+///
+/// The claimed batch is spread over `TOKEN_WATCH_CONCURRENCY` workers and the provider calls
+/// of a token are made in parallel, the limiter of each provider keeps the batch within its
+/// limits and serves the interactive calls first.
+pub async fn process_token_watch(app: &Arc<AppState>) -> Result<()> {
+    let config = &app.token_watch_config;
+    let started_at = Instant::now();
+    let changed = reprioritize_token_watch(&app.pool).await?;
+    if changed > 0 {
+        info!("{changed} tokens changed priority in token watch");
+    }
+    let token_addresses = claim_token_watch_due(&app.pool, config).await?;
+    if token_addresses.is_empty() {
+        return Ok(());
+    }
 
-async fn process_token_watch(app: &Arc<AppState>) -> Result<()> {
-    let pool = &app.pool;
-    let batch_size = 50; // adjust as needed
-    let token_addresses = get_token_watch_due(pool, batch_size).await?;
-    for token_address in token_addresses {
-        if let Ok(token_data) = fetch_token_details(app, &token_address).await.map_err(|e| {
-            error!("Error fetching token details for {}: {}", token_address, e);
-            e
-        }) {
-            info!("Token details: {:?}", token_data);
-            insert_token(pool, &token_data).await?;
-
-            match fetch_trade_data(app, &token_address).await {
-                Ok(market_data) => {
-                    info!("trade data: {:?}", market_data);
-                    if let Err(e) = insert_trade_data(pool, &market_data).await {
-                        error!("Error inserting trade data for {token_address}: {e}");
+    let claimed = token_addresses.len();
+    let failed = AtomicUsize::new(0);
+    stream::iter(token_addresses)
+        .for_each_concurrent(config.concurrency.max(1), |token_address| {
+            let failed = &failed;
+            async move {
+                match refresh_watched_token(app, &token_address).await {
+                    Ok(_) => {
+                        if let Err(e) = renew_token_in_watch(&app.pool, &token_address).await {
+                            error!("Error renewing {token_address} in token watch: {e}");
+                        }
+                        info!("Token address {} is refreshed", token_address);
+                    }
+                    Err(e) => {
+                        error!("Error refreshing token {token_address}: {e:#}");
+                        failed.fetch_add(1, Ordering::Relaxed);
+                        let error = format!("{e:#}");
+                        match record_token_watch_failure(&app.pool, &token_address, &error, config)
+                            .await
+                        {
+                            Ok(failure) if failure.dead_lettered => error!(
+                                "Token {token_address} is dead-lettered after {} failures",
+                                failure.failure_count
+                            ),
+                            Ok(failure) => info!(
+                                "Token {token_address} is retried at {:?}",
+                                failure.next_attempt_at
+                            ),
+                            Err(e) => {
+                                error!("Error recording failure of {token_address} in token watch: {e}")
+                            }
+                        }
                     }
-                }
-                Err(e) => {
-                    error!("Error fetching trade data for {token_address}: {e}");
                 }
             }
+        })
+        .await;
 
-            match fetch_market_data(app, &token_address).await {
-                Ok(market_data) => {
-                    info!("market data: {:?}", market_data);
-                    if let Err(e) = insert_market_data(pool, &market_data).await {
-                        error!("Error inserting market data for {token_address}: {e}");
-                    }
-                }
-                Err(e) => {
-                    error!("Error fetching market data for {token_address}: {e}");
-                }
-            };
+    let elapsed = started_at.elapsed().as_secs_f64();
+    let failed = failed.into_inner();
+    info!(
+        "token_watch refreshed {} of {} tokens in {:.1}s ({:.2} tokens/s, {} failed, {} workers)",
+        claimed - failed,
+        claimed,
+        elapsed,
+        claimed as f64 / elapsed.max(f64::EPSILON),
+        failed,
+        config.concurrency,
+    );
+    Ok(())
+}
 
-            if let Ok(safety_score) = thirdparty::get_safe_score(&app.client, &token_address)
-                .await
-                .map_err(|e| {
-                    error!("Error fetching safe score for {}: {}", token_address, e);
-                    e
-                })
-            {
-                let _ = upsert_safe_score(pool, &token_address, safety_score)
-                    .await
-                    .map_err(|e| {
-                        error!("Error fetching safe score for {}: {}", token_address, e);
-                        e
-                    });
+/// Fetch everything the watch keeps about a token in parallel and store it.
+///
+/// Only a missing overview fails the token, the other sources are logged and skipped.
+async fn refresh_watched_token(app: &AppState, token_address: &str) -> Result<()> {
+    let pool = &app.pool;
+    let overview_and_mun_score = async {
+        let overview = app.token_sdk.overview(token_address).await?;
+        let mun_score = fetch_missing_mun_score(app, token_address, &overview).await?;
+        anyhow::Ok((overview, mun_score))
+    };
+    let (overview_and_mun_score, trade_data, market_data, safety_score, holders) = tokio::join!(
+        overview_and_mun_score,
+        app.token_sdk.trade_data(token_address),
+        app.token_sdk.market_data(token_address),
+        app.safety_sdk.get_safe_score(token_address),
+        app.token_sdk.holders(token_address, 0, 100),
+    );
+    let (token_data, mun_score) = overview_and_mun_score
+        .with_context(|| format!("Error fetching token details for {token_address}"))?;
+    info!("Token details: {:?}", token_data);
+    insert_token(pool, &token_data).await?;
+
+    match trade_data {
+        Ok(trade_data) => {
+            info!("trade data: {:?}", trade_data);
+            if let Err(e) = insert_trade_data(pool, &trade_data).await {
+                error!("Error inserting trade data for {token_address}: {e}");
             }
+        }
+        Err(e) => error!("Error fetching trade data for {token_address}: {e}"),
+    }
 
-            if let Ok(holders) = app
-                .bird_eye_client
-                .holders(&token_address)
-                .await
-                .map_err(|e| {
-                    error!("Error fetching holding for {}: {}", token_address, e);
-                    e
-                })
-            {
-                let count =
-                    query_in_mover(&app.pool, holders.iter().map(|a| a.owner.clone()).collect())
-                        .await?;
-                match upsert_alpha_metric(&app.pool, &token_address, count).await {
-                    Err(err) => error!(
-                        "Error upserting alpha metric for {}: {}",
-                        token_address, err
-                    ),
-                    _ => info!("Found {} holders in market_mover", count),
-                };
+    match market_data {
+        Ok(market_data) => {
+            info!("market data: {:?}", market_data);
+            if let Err(e) = insert_market_data(pool, &market_data).await {
+                error!("Error inserting market data for {token_address}: {e}");
+            }
+        }
+        Err(e) => error!("Error fetching market data for {token_address}: {e}"),
+    }
+
+    let record_date = time_util::get_start_of_day(Utc::now()).timestamp();
+    if let Err(e) = upsert_daily_snapshot(pool, token_address, record_date).await {
+        error!("Error upserting daily snapshot for {token_address}: {e}");
+    }
+
+    match safety_score {
+        Ok(safety_score) => {
+            if let Err(e) = upsert_safe_score(pool, token_address, safety_score).await {
+                error!("Error upserting safe score for {token_address}: {e}");
             }
+        }
+        Err(e) => error!("Error fetching safe score for {token_address}: {e}"),
+    }
+
+    match holders {
+        Ok(holders) => {
+            let count =
+                query_in_mover(pool, holders.iter().map(|a| a.owner.clone()).collect()).await?;
+            match upsert_alpha_metric(pool, token_address, count).await {
+                Err(err) => error!("Error upserting alpha metric for {token_address}: {err}"),
+                _ => info!("Found {} holders in market_mover", count),
+            };
+        }
+        Err(e) => error!("Error fetching holding for {token_address}: {e}"),
+    }
 
-            if check_if_exists_munscore(pool, &token_address)
+    if let Some((username, mun_score)) = mun_score {
+        match mun_score {
+            Ok(mun_score) => {
+                info!("Mun score: {mun_score:?}");
+                match upsert_alpha_metric_munscore(
+                    pool,
+                    token_address,
+                    mun_score.smart_engagement.followers_score as f64,
+                    mun_score.smart_engagement.smart_followers_count,
+                )
                 .await
-                .map_err(|e| {
-                    error!(
-                        "Error checking if mun score exists for {}: {}",
-                        token_address, e
-                    );
-                    e
-                })?
-                .is_none()
-            {
-                if let Some(username) = extract_twitter_username(token_data.extensions) {
-                    info!("Fetching mun score for {}", username);
-                    if let Ok(mun_score) =
-                        app.moni_client.get_mun_score(&username).await.map_err(|e| {
-                            error!("Error fetching mun score for {}: {}", token_address, e);
-                            e
-                        })
-                    {
-                        info!("Mun score: {mun_score:?}");
-                        match upsert_alpha_metric_munscore(
-                            pool,
-                            &token_address,
-                            mun_score.smart_engagement.followers_score as f64,
-                            mun_score.smart_engagement.smart_followers_count,
-                        )
-                        .await
-                        {
-                            Ok(_) => info!("Mun score {username} of {token_address} is updated"),
-                            Err(err) => error!(
-                            "Error upserting mun score for {username} of {token_address}: {err}"
-                        ),
-                        };
-                    } else {
-                        mark_failed_munscore(pool, &token_address)
-                            .await
-                            .map_err(|e| {
-                                error!(
-                                    "Error marking mun score as failed for {}: {}",
-                                    token_address, e
-                                );
-                                e
-                            })?;
+                {
+                    Ok(_) => info!("Mun score {username} of {token_address} is updated"),
+                    Err(err) => {
+                        error!("Error upserting mun score for {username} of {token_address}: {err}")
                     }
-                }
+                };
             }
-        };
-        renew_token_in_watch(pool, &token_address).await?;
-        info!("Token address {} is refreshed", token_address);
-        sleep(Duration::from_millis(5000)).await;
+            Err(e) => {
+                error!("Error fetching mun score for {token_address}: {e}");
+                mark_failed_munscore(pool, token_address).await?;
+            }
+        }
+    }
+
+    // the distribution is valued from the price and supply stored above
+    if distribution_is_stale(app, token_address).await {
+        match refresh_token_distribution(
+            pool,
+            app.token_sdk.as_ref(),
+            &app.distribution_config,
+            token_address,
+        )
+        .await
+        {
+            Ok(_) => info!("Holder distribution of {token_address} is refreshed"),
+            Err(e) => error!("Error refreshing holder distribution for {token_address}: {e}"),
+        }
     }
     Ok(())
 }
 
+/// Look up the mun score of the token's twitter account, unless it is known already.
+async fn fetch_missing_mun_score(
+    app: &AppState,
+    token_address: &str,
+    overview: &TokenOverview,
+) -> Result<Option<(String, Result<MunScoreData>)>> {
+    if check_if_exists_munscore(&app.pool, token_address)
+        .await
+        .with_context(|| format!("Error checking if mun score exists for {token_address}"))?
+        .is_some()
+    {
+        return Ok(None);
+    }
+    let Some(username) = extract_twitter_username(overview.extensions.clone()) else {
+        return Ok(None);
+    };
+    info!("Fetching mun score for {}", username);
+    let mun_score = app.mun_score_sdk.get_mun_score(&username).await;
+    Ok(Some((username, mun_score)))
+}
+
+async fn distribution_is_stale(app: &AppState, token_address: &str) -> bool {
+    match query_token_distribution(&app.pool, token_address).await {
+        Ok(Some((_, updated_at))) => {
+            Utc::now() - updated_at
+                >= chrono::Duration::seconds(app.distribution_config.refresh_interval_secs)
+        }
+        Ok(None) => true,
+        Err(e) => {
+            error!("Error loading holder distribution for {token_address}: {e}");
+            false
+        }
+    }
+}
+
 async fn insert_market_data(
     pool: &Pool<Postgres>,
     market_data: &MarketData,
@@ -219,6 +303,7 @@ fn extract_twitter_username(extensions: Option<HashMap<String, Option<String>>>)
         })
 }
 
+#[allow(dead_code)]
 async fn upsert_smart_holder_metric(
     pool: &Pool<Postgres>,
     address: &str,
@@ -264,7 +349,7 @@ async fn upsert_alpha_metric_munscore(
 
 async fn upsert_alpha_metric(
     pool: &Pool<Postgres>,
-    address: &String,
+    address: &str,
     top_holders: i64,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
@@ -294,21 +379,51 @@ async fn query_in_mover(
     Ok(count)
 }
 
-/// Fetch tokens from the token_watch table whose updated_at timestamp is older than 60 seconds.
-pub async fn get_token_watch_due(pool: &Pool<Postgres>, batch_size: i64) -> Result<Vec<String>> {
+/// Claim up to `batch_size` tokens of the token_watch table that are due, hot tokens first and
+/// then the ones overdue the longest. A token is due once the refresh interval of its priority
+/// has passed since its last refresh. Rows locked or claimed by another instance are skipped, a
+/// claim expires after `claim_lease_secs` so tokens of a crashed instance are picked up again.
+/// Failed tokens wait for their `next_attempt_at` and dead-lettered tokens are never claimed.
+/// Long tail tokens are only claimed while active within `long_tail_active_days`.
+pub async fn claim_token_watch_due(
+    pool: &Pool<Postgres>,
+    config: &TokenWatchConfig,
+) -> Result<Vec<String>> {
     let addresses = sqlx::query_scalar(
-        "SELECT token_address FROM token_watch WHERE updated_at <= NOW() - INTERVAL '3600 seconds' and last_active >= NOW() - INTERVAL '1 hour' LIMIT $1",)
-    .bind(batch_size)
+        "UPDATE token_watch
+         SET claimed_until = NOW() + make_interval(secs => $2)
+         WHERE token_address IN (
+             SELECT token_address FROM token_watch
+             WHERE updated_at <= NOW() - make_interval(secs => CASE priority
+                     WHEN 0 THEN $3 WHEN 1 THEN $4 ELSE $5 END)
+               AND (claimed_until IS NULL OR claimed_until < NOW())
+               AND (next_attempt_at IS NULL OR next_attempt_at <= NOW())
+               AND dead_lettered_at IS NULL
+               AND (priority < 2 OR last_active >= NOW() - make_interval(days => $6))
+             ORDER BY priority,
+                      updated_at + make_interval(secs => CASE priority
+                          WHEN 0 THEN $3 WHEN 1 THEN $4 ELSE $5 END)
+             LIMIT $1
+             FOR UPDATE SKIP LOCKED)
+         RETURNING token_address",
+    )
+    .bind(config.batch_size)
+    .bind(config.claim_lease_secs)
+    .bind(config.hot_interval_secs)
+    .bind(config.active_interval_secs)
+    .bind(config.long_tail_interval_secs)
+    .bind(config.long_tail_active_days)
     .fetch_all(pool)
     .await?;
     Ok(addresses)
 }
 
-// Remove a token from the queue and return the token address.
+// Mark a token as refreshed, release its claim and clear its failures.
 pub async fn renew_token_in_watch(pool: &Pool<Postgres>, token_address: &str) -> Result<()> {
     let _: Option<TokenRow> = sqlx::query_as::<_, TokenRow>(
         "UPDATE token_watch
-         SET updated_at = NOW()
+         SET updated_at = NOW(), claimed_until = NULL,
+             failure_count = 0, last_error = NULL, next_attempt_at = NULL
          WHERE token_address = $1
          RETURNING token_address",
     )
@@ -318,22 +433,100 @@ pub async fn renew_token_in_watch(pool: &Pool<Postgres>, token_address: &str) ->
     Ok(())
 }
 
-// Simulate fetching token details; replace with an actual implementation.
-pub async fn fetch_token_details(app: &AppState, token_address: &str) -> Result<TokenOverview> {
-    //fetch overview -> fetch market data -> fectch trade data
-    app.bird_eye_client.overview(token_address).await
+#[derive(Debug, sqlx::FromRow)]
+pub struct TokenWatchFailure {
+    pub failure_count: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub dead_lettered: bool,
+}
+
+/// Release the claim of a token that failed to refresh and schedule its next attempt with an
+/// exponential backoff. The token is dead-lettered once it failed `max_failures` times in a row.
+pub async fn record_token_watch_failure(
+    pool: &Pool<Postgres>,
+    token_address: &str,
+    error: &str,
+    config: &TokenWatchConfig,
+) -> Result<TokenWatchFailure> {
+    let failure = sqlx::query_as::<_, TokenWatchFailure>(
+        "UPDATE token_watch
+         SET claimed_until = NULL,
+             failure_count = failure_count + 1,
+             last_error = $2,
+             next_attempt_at = NOW() + make_interval(secs => LEAST($3 * power(2, failure_count), $4)),
+             dead_lettered_at = CASE WHEN failure_count + 1 >= $5 THEN NOW() END
+         WHERE token_address = $1
+         RETURNING failure_count, next_attempt_at, dead_lettered_at IS NOT NULL AS dead_lettered",
+    )
+    .bind(token_address)
+    .bind(error)
+    .bind(config.retry_base_secs)
+    .bind(config.retry_max_secs)
+    .bind(config.max_failures)
+    .fetch_one(pool)
+    .await?;
+    Ok(failure)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct StuckToken {
+    pub token_address: String,
+    pub failure_count: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub dead_lettered_at: Option<DateTime<Utc>>,
+    pub last_refreshed_at: DateTime<Utc>,
 }
 
-pub async fn fetch_trade_data(app: &AppState, token_address: &str) -> Result<TradeData> {
-    app.bird_eye_client.trade_data(token_address).await
+/// Tokens of the watch whose last refresh failed, dead-lettered ones first.
+pub async fn query_stuck_token_watch(
+    pool: &Pool<Postgres>,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<StuckToken>, i64), sqlx::Error> {
+    let tokens = sqlx::query_as::<_, StuckToken>(
+        "SELECT token_address, failure_count, last_error, next_attempt_at, dead_lettered_at,
+                updated_at AT TIME ZONE 'UTC' AS last_refreshed_at
+         FROM token_watch
+         WHERE failure_count > 0
+         ORDER BY dead_lettered_at IS NULL, failure_count DESC, token_address
+         LIMIT $1 OFFSET $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await?;
+    let total = sqlx::query_scalar("SELECT count(*) FROM token_watch WHERE failure_count > 0")
+        .fetch_one(pool)
+        .await?;
+    Ok((tokens, total))
 }
 
-pub async fn fetch_market_data(app: &AppState, token_address: &str) -> Result<MarketData> {
+/// Put a failed or dead-lettered token back in the queue, returns false if it is not watched.
+pub async fn retry_token_watch(
+    pool: &Pool<Postgres>,
+    token_address: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE token_watch
+         SET failure_count = 0, next_attempt_at = NULL, dead_lettered_at = NULL
+         WHERE token_address = $1",
+    )
+    .bind(token_address)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Simulate fetching token details; replace with an actual implementation.
+pub async fn fetch_token_details(app: &AppState, token_address: &str) -> Result<TokenOverview> {
     //fetch overview -> fetch market data -> fectch trade data
-    app.bird_eye_client.market_data(token_address).await
+    app.token_sdk.overview(token_address).await
 }
 
 // Insert token data into the tokens table.
+#[allow(clippy::too_many_arguments)]
 pub async fn insert_token_with_params(
     pool: &Pool<Postgres>,
     address: &str,
@@ -441,3 +634,221 @@ async fn upsert_safe_score(
     .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{test_pool, unique_name};
+    use envconfig::Envconfig;
+
+    fn watch_config(overrides: &[(&str, &str)]) -> TokenWatchConfig {
+        let mut config = HashMap::from([
+            ("TOKEN_WATCH_BATCH_SIZE".to_string(), "1000".to_string()),
+            ("TOKEN_WATCH_CLAIM_LEASE_SECS".to_string(), "60".to_string()),
+        ]);
+        for (key, value) in overrides {
+            config.insert(key.to_string(), value.to_string());
+        }
+        TokenWatchConfig::init_from_hashmap(&config).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_not_claim_a_token_twice() {
+        let pool = test_pool().await;
+        let config = watch_config(&[]);
+        let suffix = Utc::now().timestamp_nanos_opt().unwrap();
+        let addresses = vec![format!("claim_a_{suffix}"), format!("claim_b_{suffix}")];
+        sqlx::query(
+            "INSERT INTO token_watch (token_address, updated_at, last_active, priority)
+            SELECT unnest($1::varchar[]), NOW() - INTERVAL '2 hours', NOW(), 1",
+        )
+        .bind(&addresses)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let (first, second) = tokio::join!(
+            claim_token_watch_due(&pool, &config),
+            claim_token_watch_due(&pool, &config)
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        for address in &addresses {
+            assert!(first.contains(address) ^ second.contains(address));
+        }
+        assert!(claim_token_watch_due(&pool, &config)
+            .await
+            .unwrap()
+            .iter()
+            .all(|a| !addresses.contains(a)));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_refresh_and_release_claimed_tokens() {
+        use crate::app::Providers;
+        use crate::config::{
+            AdminConfig, CacheConfig, DistributionConfig, PriceConfig, WebhookConfig,
+        };
+
+        let pool = test_pool().await;
+        let app = Arc::new(AppState::with_providers(
+            pool.clone(),
+            Providers::mock(),
+            DistributionConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            watch_config(&[]),
+            PriceConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            CacheConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            WebhookConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+            AdminConfig::init_from_hashmap(&HashMap::new()).unwrap(),
+        ));
+        let address = unique_name("watch");
+        sqlx::query(
+            "INSERT INTO token_watch (token_address, updated_at, last_active, priority)
+            VALUES ($1, NOW() - INTERVAL '2 hours', NOW(), 1)",
+        )
+        .bind(&address)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        process_token_watch(&app).await.unwrap();
+
+        let (refreshed, claimed): (bool, bool) = sqlx::query_as(
+            "SELECT updated_at > NOW() - INTERVAL '1 minute', claimed_until IS NOT NULL
+            FROM token_watch WHERE token_address = $1",
+        )
+        .bind(&address)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(refreshed);
+        assert!(!claimed);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_back_off_and_dead_letter_failing_tokens() {
+        let pool = test_pool().await;
+        let config = watch_config(&[
+            ("TOKEN_WATCH_RETRY_BASE_SECS", "60"),
+            ("TOKEN_WATCH_RETRY_MAX_SECS", "100"),
+            ("TOKEN_WATCH_MAX_FAILURES", "3"),
+        ]);
+        let address = unique_name("failing");
+        sqlx::query(
+            "INSERT INTO token_watch (token_address, updated_at, last_active, priority)
+            VALUES ($1, NOW() - INTERVAL '2 hours', NOW(), 1)",
+        )
+        .bind(&address)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // 60s, 120s capped to 100s, then dead-lettered
+        let mut delays = vec![];
+        for _ in 0..3 {
+            let now = Utc::now();
+            let failure = record_token_watch_failure(&pool, &address, "birdeye down", &config)
+                .await
+                .unwrap();
+            delays.push((failure.next_attempt_at.unwrap() - now).num_seconds());
+            assert_eq!(failure.dead_lettered, failure.failure_count == 3);
+        }
+        assert_eq!(delays[..2], [60, 100]);
+
+        sqlx::query("UPDATE token_watch SET next_attempt_at = NOW() WHERE token_address = $1")
+            .bind(&address)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let claimed = claim_token_watch_due(&pool, &config).await.unwrap();
+        assert!(!claimed.contains(&address));
+        let (stuck, total) = query_stuck_token_watch(&pool, 100, 0).await.unwrap();
+        assert!(total >= 1);
+        let token = stuck.iter().find(|t| t.token_address == address).unwrap();
+        assert_eq!(token.failure_count, 3);
+        assert_eq!(token.last_error.as_deref(), Some("birdeye down"));
+        assert!(token.dead_lettered_at.is_some());
+
+        assert!(retry_token_watch(&pool, &address).await.unwrap());
+        let claimed = claim_token_watch_due(&pool, &config).await.unwrap();
+        assert!(claimed.contains(&address));
+        renew_token_in_watch(&pool, &address).await.unwrap();
+        let (stuck, _) = query_stuck_token_watch(&pool, 100, 0).await.unwrap();
+        assert!(stuck.iter().all(|t| t.token_address != address));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_claim_hot_tokens_first_and_long_tail_rarely() {
+        use crate::token::{promote_token_watch, WatchPriority};
+
+        let pool = test_pool().await;
+        let suffix = Utc::now().timestamp_nanos_opt().unwrap();
+        let (hot, active, long_tail, cold) = (
+            format!("hot_{suffix}"),
+            format!("active_{suffix}"),
+            format!("long_tail_{suffix}"),
+            format!("cold_{suffix}"),
+        );
+        let (stale, never_active) = (format!("stale_{suffix}"), format!("never_{suffix}"));
+        // refreshed 10 minutes ago: due when hot, not due when active
+        sqlx::query(
+            "INSERT INTO token_watch (token_address, updated_at, last_active)
+            SELECT unnest($1::varchar[]), NOW() - INTERVAL '10 minutes', NOW()",
+        )
+        .bind([hot.clone(), active.clone()])
+        .execute(&pool)
+        .await
+        .unwrap();
+        // inactive for days: due only once a day, and never once inactive for months
+        sqlx::query(
+            "INSERT INTO token_watch (token_address, updated_at, last_active)
+            VALUES ($1, NOW() - INTERVAL '2 days', NOW() - INTERVAL '3 days'),
+                   ($2, NOW() - INTERVAL '2 hours', NOW() - INTERVAL '3 days'),
+                   ($3, NOW() - INTERVAL '2 days', NOW() - INTERVAL '60 days'),
+                   ($4, NOW() - INTERVAL '2 days', NULL)",
+        )
+        .bind(&long_tail)
+        .bind(&cold)
+        .bind(&stale)
+        .bind(&never_active)
+        .execute(&pool)
+        .await
+        .unwrap();
+        promote_token_watch(
+            &pool,
+            std::slice::from_ref(&hot),
+            WatchPriority::Hot,
+            3600.0,
+        )
+        .await
+        .unwrap();
+        reprioritize_token_watch(&pool).await.unwrap();
+
+        let priorities: HashMap<String, WatchPriority> = sqlx::query_as(
+            "SELECT token_address, priority FROM token_watch WHERE token_address = ANY($1)",
+        )
+        .bind([&hot, &active, &long_tail, &cold])
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .collect();
+        assert_eq!(priorities[&hot], WatchPriority::Hot);
+        assert_eq!(priorities[&active], WatchPriority::Active);
+        assert_eq!(priorities[&long_tail], WatchPriority::LongTail);
+        assert_eq!(priorities[&cold], WatchPriority::LongTail);
+
+        let config = watch_config(&[]);
+        let claimed = claim_token_watch_due(&pool, &config).await.unwrap();
+        assert!(claimed.contains(&hot));
+        assert!(claimed.contains(&long_tail));
+        assert!(!claimed.contains(&active));
+        assert!(!claimed.contains(&cold));
+        assert!(!claimed.contains(&stale));
+        assert!(!claimed.contains(&never_active));
+    }
+}
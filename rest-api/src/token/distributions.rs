@@ -1,14 +1,27 @@
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, QueryBuilder};
+
+use crate::config::DistributionConfig;
+use crate::token::{TokenHolder, TokenSdk};
+
+/// Page size of the Birdeye holder endpoint.
+const HOLDER_PAGE_SIZE: u32 = 100;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct TokenDistributions {
-    category: Category,
-    percentage: f64,
-    amount: u64,
+    pub category: Category,
+    pub percentage: f64,
+    pub amount: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, sqlx::Type)]
 #[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
 pub enum Category {
     Shrimp,
     Fish,
@@ -17,32 +30,282 @@ pub enum Category {
     Whale,
 }
 
-pub fn create_dummy_token_distribution() -> Vec<TokenDistributions> {
-    vec![
-        TokenDistributions {
-            category: Category::Shrimp,
-            percentage: 0.18,
-            amount: 4212,
-        },
-        TokenDistributions {
-            category: Category::Fish,
-            percentage: 5.0,
-            amount: 800,
-        },
-        TokenDistributions {
-            category: Category::Dolphin,
-            percentage: 12.75,
-            amount: 300,
-        },
-        TokenDistributions {
-            category: Category::Shark,
-            percentage: 20.0,
-            amount: 50,
-        },
-        TokenDistributions {
-            category: Category::Whale,
-            percentage: 25.5,
-            amount: 15,
-        },
-    ]
+impl Category {
+    const ALL: [Category; 5] = [
+        Category::Shrimp,
+        Category::Fish,
+        Category::Dolphin,
+        Category::Shark,
+        Category::Whale,
+    ];
+}
+
+/// How a holder balance is measured against the cutoffs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistributionMode {
+    /// Balance value in USD at the current token price.
+    Usd,
+    /// Balance as percent of the total supply.
+    Supply,
+}
+
+impl FromStr for DistributionMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "usd" => Ok(Self::Usd),
+            "supply" => Ok(Self::Supply),
+            other => Err(anyhow!("unknown distribution mode {other}")),
+        }
+    }
+}
+
+/// Lower bounds of the fish, dolphin, shark and whale buckets, anything below is a shrimp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cutoffs(pub [f64; 4]);
+
+impl FromStr for Cutoffs {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let cutoffs: [f64; 4] = values
+            .try_into()
+            .map_err(|_| anyhow!("expected 4 cutoffs, got {s}"))?;
+        if cutoffs.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(anyhow!("cutoffs must be strictly increasing: {s}"));
+        }
+        Ok(Self(cutoffs))
+    }
+}
+
+impl Cutoffs {
+    fn category(&self, value: f64) -> Category {
+        let index = self.0.iter().take_while(|cutoff| value >= **cutoff).count();
+        Category::ALL[index]
+    }
+}
+
+/// Bucket holders by balance. `percentage` is the share of the supply held by the bucket and
+/// `amount` the number of holders in it.
+pub fn classify_holders(
+    holders: &[TokenHolder],
+    config: &DistributionConfig,
+    price: f64,
+    total_supply: f64,
+) -> Vec<TokenDistributions> {
+    let supply = if total_supply > 0.0 {
+        total_supply
+    } else {
+        holders.iter().map(|h| h.ui_amount).sum()
+    };
+    let mut buckets = Category::ALL.map(|category| TokenDistributions {
+        category,
+        percentage: 0.0,
+        amount: 0,
+    });
+    for holder in holders {
+        let category = match config.mode {
+            DistributionMode::Usd => config.usd_cutoffs.category(holder.ui_amount * price),
+            DistributionMode::Supply if supply > 0.0 => config
+                .supply_cutoffs
+                .category(holder.ui_amount / supply * 100.0),
+            DistributionMode::Supply => Category::Shrimp,
+        };
+        let bucket = &mut buckets[category as usize];
+        bucket.amount += 1;
+        if supply > 0.0 {
+            bucket.percentage += holder.ui_amount / supply * 100.0;
+        }
+    }
+    buckets.into()
+}
+
+/// Page through the holder list of a token, up to `max_holders` entries.
+pub async fn fetch_all_holders<T: TokenSdk + ?Sized>(
+    client: &T,
+    address: &str,
+    max_holders: u32,
+) -> anyhow::Result<Vec<TokenHolder>> {
+    let mut holders = vec![];
+    let mut offset = 0;
+    while offset < max_holders {
+        let limit = HOLDER_PAGE_SIZE.min(max_holders - offset);
+        let page = client.holders(address, offset, limit).await?;
+        let last_page = (page.len() as u32) < limit;
+        holders.extend(page);
+        if last_page {
+            break;
+        }
+        offset += limit;
+    }
+    Ok(holders)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TokenSupply {
+    current_price: Option<BigDecimal>,
+    total_supply: Option<BigDecimal>,
+}
+
+/// Recompute the holder distribution of a token and persist it, `None` if the token is unknown.
+pub async fn refresh_token_distribution<T: TokenSdk + ?Sized>(
+    pool: &Pool<Postgres>,
+    client: &T,
+    config: &DistributionConfig,
+    address: &str,
+) -> anyhow::Result<Option<Vec<TokenDistributions>>> {
+    let Some(supply) = sqlx::query_as::<_, TokenSupply>(
+        "SELECT current_price, total_supply FROM tokens WHERE token_address = $1",
+    )
+    .bind(address)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let holders = fetch_all_holders(client, address, config.max_holders).await?;
+    let distributions = classify_holders(
+        &holders,
+        config,
+        supply
+            .current_price
+            .and_then(|p| p.to_f64())
+            .unwrap_or_default(),
+        supply
+            .total_supply
+            .and_then(|s| s.to_f64())
+            .unwrap_or_default(),
+    );
+    upsert_token_distribution(pool, address, &distributions).await?;
+    Ok(Some(distributions))
+}
+
+pub async fn upsert_token_distribution(
+    pool: &Pool<Postgres>,
+    address: &str,
+    distributions: &[TokenDistributions],
+) -> Result<(), sqlx::Error> {
+    let mut qb = QueryBuilder::new(
+        "INSERT INTO token_holder_distribution (token_address, category, percentage, amount, updated_at) ",
+    );
+    qb.push_values(distributions, |mut b, item| {
+        b.push_bind(address)
+            .push_bind(item.category)
+            .push_bind(item.percentage)
+            .push_bind(item.amount as i64)
+            .push("NOW()");
+    });
+    qb.push(
+        " ON CONFLICT (token_address, category) DO UPDATE SET \
+         percentage = EXCLUDED.percentage, \
+         amount = EXCLUDED.amount, \
+         updated_at = EXCLUDED.updated_at",
+    );
+    qb.build().execute(pool).await?;
+    Ok(())
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct TokenDistributionRow {
+    category: Category,
+    percentage: f64,
+    amount: i64,
+    updated_at: DateTime<Utc>,
+}
+
+/// Load the persisted distribution of a token together with the time it was computed.
+pub async fn query_token_distribution(
+    pool: &Pool<Postgres>,
+    address: &str,
+) -> Result<Option<(Vec<TokenDistributions>, DateTime<Utc>)>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, TokenDistributionRow>(
+        "SELECT category, percentage, amount, updated_at
+        FROM token_holder_distribution
+        WHERE token_address = $1",
+    )
+    .bind(address)
+    .fetch_all(pool)
+    .await?;
+    let Some(updated_at) = rows.iter().map(|r| r.updated_at).min() else {
+        return Ok(None);
+    };
+    let mut distributions: Vec<TokenDistributions> = rows
+        .into_iter()
+        .map(|r| TokenDistributions {
+            category: r.category,
+            percentage: r.percentage,
+            amount: r.amount.max(0) as u64,
+        })
+        .collect();
+    distributions.sort_by_key(|d| d.category as usize);
+    Ok(Some((distributions, updated_at)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn holder(ui_amount: f64) -> TokenHolder {
+        TokenHolder {
+            amount: ui_amount.to_string(),
+            decimals: 0,
+            mint: "mint".into(),
+            owner: "owner".into(),
+            token_account: "account".into(),
+            ui_amount,
+        }
+    }
+
+    fn config(mode: DistributionMode) -> DistributionConfig {
+        DistributionConfig {
+            mode,
+            usd_cutoffs: "100,1000,10000,100000".parse().unwrap(),
+            supply_cutoffs: "0.01,0.1,0.5,1".parse().unwrap(),
+            max_holders: 1000,
+            refresh_interval_secs: 3600,
+        }
+    }
+
+    #[test]
+    fn should_classify_holders_by_usd_value() {
+        let holders = vec![
+            holder(1.0),
+            holder(50.0),
+            holder(500.0),
+            holder(500.0),
+            holder(60_000.0),
+        ];
+
+        let buckets = classify_holders(&holders, &config(DistributionMode::Usd), 2.0, 100_000.0);
+
+        let amounts: Vec<u64> = buckets.iter().map(|b| b.amount).collect();
+        assert_eq!(amounts, vec![1, 1, 2, 0, 1]);
+        assert_eq!(buckets[4].category, Category::Whale);
+        assert!((buckets[4].percentage - 60.0).abs() < 1e-9);
+        assert!((buckets[2].percentage - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_classify_holders_by_supply_share() {
+        let holders = vec![holder(5.0), holder(50.0), holder(945.0)];
+
+        let buckets = classify_holders(&holders, &config(DistributionMode::Supply), 0.0, 0.0);
+
+        let amounts: Vec<u64> = buckets.iter().map(|b| b.amount).collect();
+        assert_eq!(amounts, vec![0, 0, 0, 1, 2]);
+        assert!((buckets[4].percentage - 99.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn should_reject_unordered_cutoffs() {
+        assert!("1,2,3".parse::<Cutoffs>().is_err());
+        assert!("1,3,2,4".parse::<Cutoffs>().is_err());
+        assert!("usd".parse::<DistributionMode>().is_ok());
+    }
 }
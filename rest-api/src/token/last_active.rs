@@ -1,5 +1,18 @@
+use serde::Serialize;
 use sqlx::PgPool;
 
+/// Refresh tier of a token in the watch, lower values are refreshed first and more often.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, sqlx::Type)]
+#[serde(rename_all = "camelCase")]
+#[repr(i16)]
+pub enum WatchPriority {
+    /// Traded by a market mover, viewed or trending, until its `priority_until`.
+    Hot = 0,
+    /// Searched or traded within the last hour.
+    Active = 1,
+    LongTail = 2,
+}
+
 pub async fn last_active(pool: &PgPool, token: &[String]) -> Result<(), sqlx::Error> {
     sqlx::query("UPDATE token_watch SET last_active = NOW() WHERE token_address = any($1)")
         .bind(token)
@@ -8,3 +21,44 @@ pub async fn last_active(pool: &PgPool, token: &[String]) -> Result<(), sqlx::Er
     Ok(())
 }
 
+/// Watch `tokens` and raise them to `priority` for `hold_secs`, a higher tier still held by a
+/// token is kept.
+pub async fn promote_token_watch(
+    pool: &PgPool,
+    tokens: &[String],
+    priority: WatchPriority,
+    hold_secs: f64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO token_watch (token_address, last_active, priority, priority_until)
+         SELECT UNNEST($1::text[]), NOW(), $2, NOW() + make_interval(secs => $3)
+         ON CONFLICT (token_address) DO UPDATE SET
+             last_active = NOW(),
+             priority = CASE WHEN token_watch.priority_until > NOW()
+                 THEN LEAST(token_watch.priority, EXCLUDED.priority)
+                 ELSE EXCLUDED.priority END,
+             priority_until = GREATEST(token_watch.priority_until, EXCLUDED.priority_until)",
+    )
+    .bind(tokens)
+    .bind(priority)
+    .bind(hold_secs)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Drop tokens whose promotion expired back to the tier their activity gives them.
+pub async fn reprioritize_token_watch(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE token_watch
+         SET priority = CASE WHEN last_active >= NOW() - INTERVAL '1 hour' THEN $1 ELSE $2 END,
+             priority_until = NULL
+         WHERE (priority_until IS NULL OR priority_until <= NOW())
+           AND priority <> CASE WHEN last_active >= NOW() - INTERVAL '1 hour' THEN $1 ELSE $2 END",
+    )
+    .bind(WatchPriority::Active)
+    .bind(WatchPriority::LongTail)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
@@ -1,13 +1,19 @@
 // use std::path::Path;
+use crate::alpha_move::PaginationQuery;
 use crate::app::AppState;
-use crate::response::HttpResponse;
+use crate::cache::CacheGroup;
+use crate::error::ApiError;
+use crate::response::{HttpPaginationResponse, HttpResponse};
 use crate::time_util;
 use crate::token::{
-    background_job, create_dummy_token_analysis, create_dummy_token_distribution,
-    fetch_token_details, last_active, token_bio, token_by_address, TokenAnalytics,
-    TokenDistributions, TokenOverviewResponse, TokenSdk, TokenVolumeHistory,
+    background_job, fetch_token_details, last_active, promote_token_watch,
+    query_stuck_token_watch, query_token_distribution, refresh_token_distribution,
+    retry_token_watch, token_analytics, token_bio, token_by_address, StuckToken, TokenAnalytics,
+    TokenDistributions, TokenOverviewResponse, TokenVolumeHistory, UnknownToken, WatchPriority,
 };
 use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, Uri};
+use axum::response::Response;
 use axum::{http::StatusCode, Json};
 use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
 use chrono::Utc;
@@ -30,34 +36,40 @@ pub struct TokenMindshare {
 }
 pub async fn mindshare(
     State(app): State<AppState>,
-) -> Result<Json<HttpResponse<Vec<TokenMindshare>>>, (StatusCode, String)> {
-    let vol = query_top_token_volume_history_by_date(
-        &app.pool,
-        100,
-        time_util::get_start_of_day(Utc::now()).timestamp(),
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-    let total_volume: f64 = vol
-        .iter()
-        .map(|v| v.volume24h.to_f64().unwrap_or_default())
-        .sum();
-    let percent = vol
-        .iter()
-        .map(|v| TokenMindshare {
-            token_address: v.token_address.clone(),
-            change_percentage: (v.volume24h.to_f64().unwrap_or_default() / total_volume) * 100.0,
-            logo_url: v.logo_uri.clone().unwrap_or_default(),
-            name: v.name.clone(),
-            symbol: v.symbol.clone(),
-            volume: v.volume24h.clone(),
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    app.cache
+        .respond(CacheGroup::Mindshare, uri.to_string(), &headers, || async {
+            let vol = query_top_token_volume_history_by_date(
+                &app.pool,
+                100,
+                time_util::get_start_of_day(Utc::now()).timestamp(),
+            )
+            .await?;
+            let total_volume: f64 = vol
+                .iter()
+                .map(|v| v.volume24h.to_f64().unwrap_or_default())
+                .sum();
+            let percent = vol
+                .iter()
+                .map(|v| TokenMindshare {
+                    token_address: v.token_address.clone(),
+                    change_percentage: (v.volume24h.to_f64().unwrap_or_default() / total_volume)
+                        * 100.0,
+                    logo_url: v.logo_uri.clone().unwrap_or_default(),
+                    name: v.name.clone(),
+                    symbol: v.symbol.clone(),
+                    volume: v.volume24h.clone(),
+                })
+                .collect::<Vec<_>>();
+            Ok(HttpResponse {
+                code: 200,
+                response: percent,
+                last_updated: Utc::now().timestamp(),
+            })
         })
-        .collect::<Vec<_>>();
-    Ok(Json(HttpResponse {
-        code: 200,
-        response: percent,
-        last_updated: Utc::now().timestamp(),
-    }))
+        .await
 }
 
 // rust
@@ -213,37 +225,40 @@ impl From<&TokenVolumeHistory> for TrendingTokenResponse {
 }
 pub async fn trending_token(
     State(app): State<AppState>,
-) -> Result<Json<HttpResponse<Vec<TrendingTokenResponse>>>, (StatusCode, String)> {
-    let tokens = query_top_token_volume_history_by_date(
-        &app.pool,
-        20,
-        time_util::get_start_of_day(Utc::now()).timestamp(),
-    )
-    .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    .iter()
-    .map(TrendingTokenResponse::from)
-    .collect();
-    Ok(Json(HttpResponse {
-        code: 200,
-        response: tokens,
-        last_updated: Utc::now().timestamp(),
-    }))
+    uri: Uri,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    app.cache
+        .respond(CacheGroup::Mindshare, uri.to_string(), &headers, || async {
+            let tokens: Vec<TrendingTokenResponse> = query_top_token_volume_history_by_date(
+                &app.pool,
+                20,
+                time_util::get_start_of_day(Utc::now()).timestamp(),
+            )
+            .await?
+            .iter()
+            .map(TrendingTokenResponse::from)
+            .collect();
+            Ok(HttpResponse {
+                code: 200,
+                response: tokens,
+                last_updated: Utc::now().timestamp(),
+            })
+        })
+        .await
 }
 
 pub async fn search_token(
     State(app): State<AppState>,
     Query(query): Query<SearchQuery>,
-) -> Result<Json<HttpResponse<Vec<TokenResponse>>>, (StatusCode, String)> {
-    if let Err(validation_errors) = query.validate() {
-        return Err((StatusCode::BAD_REQUEST, validation_errors.to_string()));
-    }
+) -> Result<Json<HttpResponse<Vec<TokenResponse>>>, ApiError> {
+    query.validate()?;
 
     let a = search_tokens(&app.pool, &query.q, query.limit, query.offset)
         .await
         .map_err(|e| {
             error!("Failed to search tokens: {e}");
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            ApiError::from(e)
         })?;
     if !a.is_empty() {
         let tokens = a.iter().map(TokenResponse::from).collect();
@@ -259,9 +274,9 @@ pub async fn search_token(
         }));
     }
 
-    let mut search_result = app.bird_eye_client.search(&query.q).await.map_err(|e| {
+    let mut search_result = app.token_sdk.search(&query.q).await.map_err(|e| {
         error!("Failed to search tokens: {e}");
-        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        ApiError::UpstreamUnavailable("BirdEye token search failed".into())
     })?;
 
     if let Err(e) = last_active(&app.pool, &[query.q]).await {
@@ -287,7 +302,7 @@ pub async fn search_token(
         let mut logo_uri = logo_uri.clone();
         if logo_uri.is_none() {
             info!("token {address} missing logo {:?}", logo_uri);
-            if let Ok(a) = app.bird_eye_client.overview(address).await {
+            if let Ok(a) = app.token_sdk.overview(address).await {
                 logo_uri = a.logo_uri.clone();
                 token.logo_uri = a.logo_uri;
             }
@@ -361,99 +376,137 @@ pub struct TokenDetailResponse {
     pub logo_uri: String,
     pub website_url: String,
     pub metadata: serde_json::Value,
-    pub decimals: i32,
-    pub marketcap: BigDecimal,
+    pub decimals: Option<i32>,
+    pub marketcap: Option<BigDecimal>,
     pub history24h_price: BigDecimal,
     pub price_change24h_percent: BigDecimal,
     pub holders: i32,
     pub liquidity: BigDecimal,
     pub volume_24h: BigDecimal,
     pub volume_24h_change: BigDecimal,
-    pub mun_score: f64,
-    pub total_supply: BigDecimal,
-    pub current_price: BigDecimal,
-    pub risk_score: f64,
+    pub mun_score: Option<f64>,
+    pub total_supply: Option<BigDecimal>,
+    pub current_price: Option<BigDecimal>,
+    pub risk_score: Option<f64>,
 }
 
 impl From<TokenOverviewResponse> for TokenDetailResponse {
-        fn from(value: TokenOverviewResponse) -> Self {
-            Self {
-                token_address: value.token_address,
-                name: value.name,
-                symbol: value.symbol,
-                logo_uri: value.logo_uri.unwrap_or_default(),
-                website_url: value.website_url.unwrap_or_default(),
-                metadata: value.metadata.unwrap_or_default(),
-                decimals: value.decimals.unwrap(),
-                marketcap: value.marketcap.unwrap(),
-                history24h_price: value.history24h_price.unwrap_or_default(),
-                price_change24h_percent: value.price_change24h_percent.unwrap_or_default(),
-                holders: value.holders.unwrap_or(0),
-                liquidity: value.liquidity.unwrap_or_default(),
-                volume_24h: value.volume_24h.unwrap_or_default(),
-                volume_24h_change: value.volume_24h_change.unwrap_or_default(),
-                mun_score: value.mun_score.unwrap().to_f64().unwrap(),
-                total_supply: value.total_supply.unwrap(),
-                current_price: value.current_price.unwrap(),
-                risk_score: value.risk_score.unwrap().to_f64().unwrap(),
-            }
+    fn from(value: TokenOverviewResponse) -> Self {
+        Self {
+            token_address: value.token_address,
+            name: value.name,
+            symbol: value.symbol,
+            logo_uri: value.logo_uri.unwrap_or_default(),
+            website_url: value.website_url.unwrap_or_default(),
+            metadata: value.metadata.unwrap_or_default(),
+            decimals: value.decimals,
+            marketcap: value.marketcap,
+            history24h_price: value.history24h_price.unwrap_or_default(),
+            price_change24h_percent: value.price_change24h_percent.unwrap_or_default(),
+            holders: value.holders.unwrap_or(0),
+            liquidity: value.liquidity.unwrap_or_default(),
+            volume_24h: value.volume_24h.unwrap_or_default(),
+            volume_24h_change: value.volume_24h_change.unwrap_or_default(),
+            mun_score: value.mun_score.and_then(|v| v.to_f64()),
+            total_supply: value.total_supply,
+            current_price: value.current_price,
+            risk_score: value.risk_score.and_then(|v| v.to_f64()),
+        }
+    }
+}
+
+/// A freshly fetched token, not scored yet.
+impl From<TokenOverview> for TokenDetailResponse {
+    fn from(token: TokenOverview) -> Self {
+        let decimal = |value: Option<f64>| value.and_then(BigDecimal::from_f64);
+        Self {
+            metadata: serde_json::to_value(token.extensions.unwrap_or_default())
+                .unwrap_or_default(),
+            decimals: token.decimals.to_i32(),
+            marketcap: decimal(token.marketcap),
+            history24h_price: decimal(token.history24h_price).unwrap_or_default(),
+            price_change24h_percent: decimal(token.price_change24h_percent).unwrap_or_default(),
+            holders: token.holder.unwrap_or_default(),
+            liquidity: decimal(token.liquidity).unwrap_or_default(),
+            volume_24h: decimal(token.volume24h).unwrap_or_default(),
+            volume_24h_change: decimal(token.volume_24h_change).unwrap_or_default(),
+            mun_score: None,
+            total_supply: decimal(token.total_supply),
+            current_price: decimal(token.price),
+            risk_score: None,
+            token_address: token.address,
+            name: token.name,
+            symbol: token.symbol,
+            logo_uri: token.logo_uri.unwrap_or_default(),
+            website_url: token.website_url.unwrap_or_default(),
         }
     }
+}
+
+/// Reject anything but a base58 encoded 32 byte Solana address, `kind` names it in the error.
+pub fn validate_address(address: &str, kind: &str) -> Result<(), ApiError> {
+    match bs58::decode(address).into_vec() {
+        Ok(bytes) if bytes.len() == 32 => Ok(()),
+        _ => Err(ApiError::BadRequest(format!(
+            "{address} is not a valid Solana {kind} address"
+        ))),
+    }
+}
+
+/// A token mint address, see [`validate_address`].
+pub fn validate_mint(address: &str) -> Result<(), ApiError> {
+    validate_address(address, "mint")
+}
 
 pub async fn get_token_bio(
     State(app): State<AppState>,
     Path(address): Path<String>,
-) -> Result<Json<HttpResponse<TokenDetailResponse>>, (StatusCode, String)> {
+) -> Result<Json<HttpResponse<TokenDetailResponse>>, ApiError> {
+    validate_mint(&address)?;
     let missing = token_by_address(&app.pool, vec![address.clone()])
         .await
         .map_err(|e| {
             error!("Failed to fetch token by address: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            ApiError::from(e)
         })?;
-    let resp: TokenDetailResponse = if !missing.is_empty() {
+    let resp = if !missing.is_empty() {
         let token = fetch_token_details(&app, &address).await.map_err(|e| {
+            if e.is::<UnknownToken>() {
+                return ApiError::NotFound(format!("token {address} not found"));
+            }
             error!("Failed to fetch token details: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+            ApiError::UpstreamUnavailable(format!("BirdEye token {address} lookup failed"))
         })?;
 
         background_job::insert_token(&app.pool, &token)
             .await
             .map_err(|e| {
                 error!("Failed to insert token: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-            })
-            .map(|_| {
-                TokenDetailResponse {
-                    token_address: token.address,
-                    name: token.name,
-                    symbol: token.symbol,
-                    logo_uri: token.logo_uri.unwrap().to_string(),
-                    website_url: token.website_url.unwrap_or_default().to_string(),
-                    metadata: serde_json::to_value(token.extensions.unwrap_or_default()).unwrap_or_default(),
-                    decimals: token.decimals.to_i32().unwrap(),
-                    marketcap: BigDecimal::from_f64(token.marketcap.unwrap()).unwrap(),
-                    history24h_price: BigDecimal::from_f64(token.history24h_price.unwrap_or_default()).unwrap(),
-                    price_change24h_percent: BigDecimal::from_f64(token.price_change24h_percent.unwrap_or_default()).unwrap(),
-                    holders: token.holder.unwrap_or_default(),
-                    liquidity: BigDecimal::from_f64(token.liquidity.unwrap_or_default()).unwrap(),
-                    volume_24h: BigDecimal::from_f64(token.volume24h.unwrap_or_default()).unwrap(),
-                    volume_24h_change: BigDecimal::from_f64(token.volume_24h_change.unwrap_or_default()).unwrap(),
-                    mun_score: 0.0,
-                    total_supply: BigDecimal::from_f64(token.total_supply.unwrap_or_default()).unwrap(),
-                    current_price: BigDecimal::from_f64(token.price.unwrap()).unwrap(),
-                    risk_score: 0.0,
-                }
-            })?
+                ApiError::from(e)
+            })?;
+        TokenDetailResponse::from(token)
     } else {
         let token_bio_response = token_bio(&app.pool, &address)
             .await
             .map_err(|e| {
                 error!("Failed to fetch token bio: {}", e);
-                (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
-            })?;
+                ApiError::from(e)
+            })?
+            .ok_or_else(|| ApiError::NotFound(format!("token {address} not found")))?;
         TokenDetailResponse::from(token_bio_response)
     };
 
+    if let Err(e) = promote_token_watch(
+        &app.pool,
+        &[address],
+        WatchPriority::Hot,
+        app.token_watch_config.hot_hold_secs,
+    )
+    .await
+    {
+        error!("Failed to promote token in watch: {e}");
+    }
+
     Ok(Json(HttpResponse {
         code: 200,
         response: resp,
@@ -462,10 +515,17 @@ pub async fn get_token_bio(
 }
 
 pub async fn get_token_analytics(
-    State(_app): State<AppState>,
-    Path(_address): Path<String>,
-) -> Result<Json<HttpResponse<TokenAnalytics>>, (StatusCode, String)> {
-    let resp: TokenAnalytics = create_dummy_token_analysis();
+    State(app): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<HttpResponse<TokenAnalytics>>, ApiError> {
+    validate_mint(&address)?;
+    let resp = token_analytics(&app.pool, &address, Utc::now())
+        .await
+        .map_err(|e| {
+            error!("Failed to build token analytics: {}", e);
+            ApiError::from(e)
+        })?
+        .ok_or_else(|| ApiError::NotFound(format!("token {address} not found")))?;
     Ok(Json(HttpResponse {
         code: 200,
         response: resp,
@@ -474,10 +534,36 @@ pub async fn get_token_analytics(
 }
 
 pub async fn get_token_distributions(
-    State(_app): State<AppState>,
-    Path(_address): Path<String>,
-) -> Result<Json<HttpResponse<Vec<TokenDistributions>>>, (StatusCode, String)> {
-    let resp: Vec<TokenDistributions> = create_dummy_token_distribution();
+    State(app): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<HttpResponse<Vec<TokenDistributions>>>, ApiError> {
+    validate_mint(&address)?;
+    let stored = query_token_distribution(&app.pool, &address)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch token distribution: {}", e);
+            ApiError::from(e)
+        })?;
+    if let Some((resp, updated_at)) = stored {
+        return Ok(Json(HttpResponse {
+            code: 200,
+            response: resp,
+            last_updated: updated_at.timestamp(),
+        }));
+    }
+
+    let resp = refresh_token_distribution(
+        &app.pool,
+        app.token_sdk.as_ref(),
+        &app.distribution_config,
+        &address,
+    )
+    .await
+    .map_err(|e| {
+        error!("Failed to refresh token distribution: {}", e);
+        ApiError::UpstreamUnavailable(format!("BirdEye holders of {address} lookup failed"))
+    })?
+    .ok_or_else(|| ApiError::NotFound(format!("token {address} not found")))?;
 
     Ok(Json(HttpResponse {
         code: 200,
@@ -485,3 +571,39 @@ pub async fn get_token_distributions(
         last_updated: Utc::now().timestamp(),
     }))
 }
+
+/// Tokens of the watch that failed to refresh, including the dead-lettered ones.
+pub async fn get_stuck_tokens(
+    State(app): State<AppState>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<Json<HttpPaginationResponse<Vec<StuckToken>>>, ApiError> {
+    query.validate()?;
+    let (tokens, total) = query_stuck_token_watch(&app.pool, query.limit, query.offset)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch stuck tokens: {}", e);
+            ApiError::from(e)
+        })?;
+    Ok(Json(HttpPaginationResponse {
+        code: 200,
+        response: tokens,
+        last_updated: Utc::now().timestamp(),
+        total,
+    }))
+}
+
+/// Clear the failures of a watched token so that the next `token_watch` run picks it up.
+pub async fn retry_stuck_token(
+    State(app): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let found = retry_token_watch(&app.pool, &address).await.map_err(|e| {
+        error!("Failed to retry token {address}: {}", e);
+        ApiError::from(e)
+    })?;
+    if !found {
+        return Err(ApiError::NotFound(format!("token {address} is not watched")));
+    }
+    info!("Token {address} is queued for retry");
+    Ok(StatusCode::ACCEPTED)
+}
@@ -1,9 +1,10 @@
-use axum::{http::StatusCode, Json};
+use axum::Json;
 
 use super::HealthyResponse;
+use crate::error::ApiError;
 
 #[allow(dead_code)]
-pub async fn search_token() -> Result<Json<HealthyResponse>, (StatusCode, String)> {
+pub async fn search_token() -> Result<Json<HealthyResponse>, ApiError> {
     Ok(Json(HealthyResponse {
         message: "Healthy".into(),
     }))
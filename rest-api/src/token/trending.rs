@@ -1,9 +1,11 @@
-use bigdecimal::{BigDecimal, Zero};
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres, QueryBuilder};
 use std::collections::HashMap;
+use thiserror::Error;
 
-use super::{market::{self, TradeData}, trade::MarketData, TokenMindshare};
+use super::{market::TradeData, trade::MarketData};
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -71,16 +73,28 @@ pub struct TokenHolder {
     pub ui_amount: f64,
 }
 
+/// The provider has no data about the token, e.g. the address is not a mint.
+#[derive(Debug, Error)]
+#[error("token {0} is unknown to the provider")]
+pub struct UnknownToken(pub String);
+
 #[allow(dead_code)]
-pub trait TokenSdk {
+#[async_trait]
+pub trait TokenSdk: Send + Sync {
     async fn get_trending(&self, offset: i32, limit: i32) -> Result<Vec<Trending>, anyhow::Error>;
     async fn token_meta_multiple(
         &self,
         addresses: Vec<String>,
     ) -> Result<Vec<TokenMetadata>, anyhow::Error>;
+    /// Fails with [`UnknownToken`] when the provider doesn't know `address`.
     async fn overview(&self, address: &str) -> Result<TokenOverview, anyhow::Error>;
 
-    async fn holders(&self, address: &str) -> Result<Vec<TokenHolder>, anyhow::Error>;
+    async fn holders(
+        &self,
+        address: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Vec<TokenHolder>, anyhow::Error>;
     async fn search(&self, address: &str) -> Result<Vec<TokenOverview>, anyhow::Error>;
     async fn trade_data(&self, address: &str) -> Result<TradeData, anyhow::Error>;
     async fn market_data(&self, address: &str) -> Result<MarketData, anyhow::Error>;
@@ -88,7 +102,7 @@ pub trait TokenSdk {
 
 pub async fn upsert_token_meta(
     pool: &Pool<Postgres>,
-    trending_list: &Vec<Trending>,
+    trending_list: &[Trending],
 ) -> Result<(), sqlx::Error> {
     let mut qb = QueryBuilder::new(
         "INSERT INTO tokens (token_address, name, symbol, decimals, image_url, current_price, updated_at, marketcap, volume_24h, volume_24h_change, price_change24h_percent) ",
@@ -180,7 +194,6 @@ pub async fn query_top_token_volume_history_by_date(
         t.price_change24h_percent as price24h_percent
         FROM token_volume_history tvh
         INNER JOIN tokens t ON t.token_address = tvh.token_address
-        LEFT JOIN token_metrics tm ON tm.token_address = tvh.token_address
         WHERE record_date = $2
         ORDER BY tvh.volume24h DESC LIMIT $1"#,
     )
@@ -191,6 +204,7 @@ pub async fn query_top_token_volume_history_by_date(
     Ok(records)
 }
 
+#[allow(dead_code)]
 pub async fn query_top_token_volume_history(
     pool: &Pool<Postgres>,
     limit: i64,
@@ -236,6 +250,7 @@ pub async fn token_by_address(
         .collect();
     Ok(missing)
 }
+#[allow(dead_code)]
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Token {
     pub address: String,
@@ -269,7 +284,7 @@ pub struct TokenOverviewResponse {
 pub async fn token_bio(
     pool: &Pool<Postgres>,
     address: &str,
-) -> anyhow::Result<TokenOverviewResponse> {
+) -> anyhow::Result<Option<TokenOverviewResponse>> {
     let token = sqlx::query_as::<_, TokenOverviewResponse>(
         "
         SELECT
@@ -298,7 +313,7 @@ pub async fn token_bio(
         ",
     )
     .bind(address)
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await?;
     Ok(token)
 }
@@ -311,7 +326,7 @@ mod internal_test {
 
     #[test]
     fn test_dese() {
-        let a : BirdEyeResponse<HashMap<String, TokenMetadata>> = serde_json::from_str(r#"
+        let _: BirdEyeResponse<HashMap<String, TokenMetadata>> = serde_json::from_str(r#"
 {
   "data": {
     "Kruj63Qx9EQX9QzukLCBgx5g9AGW69gPDsSK25FRZAi": {
@@ -1,3 +0,0 @@
-pub mod whale;
-
-pub use whale::*;
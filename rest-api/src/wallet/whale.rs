@@ -1,5 +0,0 @@
-#[derive(Debug)]
-pub struct Whale {
-    pub address: String,
-    pub name: String,
-}
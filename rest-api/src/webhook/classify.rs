@@ -0,0 +1,405 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use helius::types::TokenStandard;
+
+use super::EnhancedTransaction;
+use crate::app::SOL_ADDRESS;
+
+pub const USDC_ADDRESS: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+const LAMPORTS_PER_SOL: f64 = 1_000_000_000.0;
+/// Net SOL moved by plain transfers below this is fees, tips and rent rather than a trade leg.
+const DUST_SOL: f64 = 0.01;
+/// A sender crediting a token to this many wallets in one transaction is airdropping it.
+const AIRDROP_FAN_OUT: usize = 3;
+const LP_ADD_TYPES: [&str; 3] = ["ADD_LIQUIDITY", "ADD_TO_POOL", "CREATE_POOL"];
+const LP_REMOVE_TYPES: [&str; 3] = ["WITHDRAW_LIQUIDITY", "REMOVE_LIQUIDITY", "REMOVE_FROM_POOL"];
+
+/// What a market mover did with a token, stored in `market_movers_transaction.transaction_type`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Buy,
+    Sell,
+    Transfer,
+    Airdrop,
+    LpAdd,
+    LpRemove,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Buy => "buy",
+            Action::Sell => "sell",
+            Action::Transfer => "transfer",
+            Action::Airdrop => "airdrop",
+            Action::LpAdd => "lp_add",
+            Action::LpRemove => "lp_remove",
+        }
+    }
+}
+
+/// A row of `market_movers_transaction`, the move of `token_address` by `wallet_address`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classified {
+    pub token_address: String,
+    pub wallet_address: String,
+    pub action: Action,
+    /// Tokens moved, always positive.
+    pub amount: f64,
+    /// Asset on the other side of a trade or of a liquidity position, SOL or USDC when they
+    /// are part of it. Native and wrapped SOL are both [`SOL_ADDRESS`].
+    pub counter_asset: Option<String>,
+    /// Amount of `counter_asset` paid or received, always positive.
+    pub counter_amount: Option<f64>,
+}
+
+fn is_quote(mint: &str) -> bool {
+    mint == SOL_ADDRESS || mint == USDC_ADDRESS
+}
+
+/// The assets of `side` other than SOL and USDC.
+fn tokens<'a>(side: &[(&'a str, f64)]) -> Vec<(&'a str, f64)> {
+    side.iter()
+        .filter(|(mint, _)| !is_quote(mint))
+        .copied()
+        .collect()
+}
+
+fn is_nft(standard: &TokenStandard) -> bool {
+    matches!(
+        standard,
+        TokenStandard::NonFungible
+            | TokenStandard::NonFungibleEdition
+            | TokenStandard::ProgrammableNonFungible
+    )
+}
+
+/// The moves of the `tracked` wallets in `transaction`, one per wallet and token. NFT sales and
+/// moves between SOL and USDC only are not market mover moves and give no row.
+pub fn classify(transaction: &EnhancedTransaction, tracked: &HashSet<&str>) -> Vec<Classified> {
+    let kind = transaction.transaction_type.as_deref().unwrap_or_default();
+    if kind.contains("NFT") {
+        return vec![];
+    }
+    let swapper = transaction
+        .events
+        .as_ref()
+        .and_then(|events| events.swap.as_ref())
+        .and_then(|swap| swap.user());
+    let wallets: BTreeSet<&str> = transaction
+        .token_transfers
+        .iter()
+        .flatten()
+        .filter(|transfer| !is_nft(&transfer.token_standard))
+        .flat_map(|transfer| {
+            let accounts = &transfer.user_accounts;
+            [&accounts.from_user_account, &accounts.to_user_account]
+        })
+        .filter_map(|account| account.as_deref())
+        .chain(swapper)
+        .filter(|wallet| tracked.contains(wallet))
+        .collect();
+    wallets
+        .into_iter()
+        .flat_map(|wallet| classify_wallet(transaction, kind, wallet))
+        .collect()
+}
+
+fn classify_wallet(transaction: &EnhancedTransaction, kind: &str, wallet: &str) -> Vec<Classified> {
+    let flows = net_flows(transaction, wallet);
+    let received: Vec<(&str, f64)> = flows
+        .iter()
+        .filter(|(_, delta)| **delta > 0.0)
+        .map(|(mint, delta)| (mint.as_str(), *delta))
+        .collect();
+    let given: Vec<(&str, f64)> = flows
+        .iter()
+        .filter(|(_, delta)| **delta < 0.0)
+        .map(|(mint, delta)| (mint.as_str(), -*delta))
+        .collect();
+    let row = |(token, amount): (&str, f64), action, counter: &[(&str, f64)]| {
+        // SOL or USDC when they are on the other side, any other asset otherwise
+        let counter = counter
+            .iter()
+            .filter(|(mint, _)| *mint != token)
+            .min_by_key(|(mint, _)| !is_quote(mint));
+        Classified {
+            token_address: token.into(),
+            wallet_address: wallet.into(),
+            action,
+            amount,
+            counter_asset: counter.map(|(mint, _)| mint.to_string()),
+            counter_amount: counter.map(|(_, amount)| *amount),
+        }
+    };
+    if LP_ADD_TYPES.contains(&kind) {
+        return tokens(&given)
+            .into_iter()
+            .map(|token| row(token, Action::LpAdd, &given))
+            .collect();
+    }
+    if LP_REMOVE_TYPES.contains(&kind) {
+        return tokens(&received)
+            .into_iter()
+            .map(|token| row(token, Action::LpRemove, &received))
+            .collect();
+    }
+    if !received.is_empty() && !given.is_empty() {
+        let buys = tokens(&received)
+            .into_iter()
+            .map(|token| row(token, Action::Buy, &given));
+        let sells = tokens(&given)
+            .into_iter()
+            .map(|token| row(token, Action::Sell, &received));
+        return buys.chain(sells).collect();
+    }
+    let received = tokens(&received).into_iter().map(|token| {
+        let airdrop = kind == "TOKEN_MINT" || is_airdrop(transaction, wallet, token.0);
+        let action = if airdrop {
+            Action::Airdrop
+        } else {
+            Action::Transfer
+        };
+        row(token, action, &[])
+    });
+    let given = tokens(&given)
+        .into_iter()
+        .map(|token| row(token, Action::Transfer, &[]));
+    received.chain(given).collect()
+}
+
+/// Net amount of every asset `wallet` received, positive, or gave, negative, in `transaction`.
+///
+/// The swap event is authoritative for the wallet that swapped, the other wallets are read from
+/// the transfers.
+fn net_flows(transaction: &EnhancedTransaction, wallet: &str) -> BTreeMap<String, f64> {
+    let mut flows: BTreeMap<String, f64> = BTreeMap::new();
+    let swap = transaction
+        .events
+        .as_ref()
+        .and_then(|events| events.swap.as_ref())
+        .filter(|swap| swap.user() == Some(wallet));
+    if let Some(swap) = swap {
+        for (native, sign) in [(&swap.native_input, -1.0), (&swap.native_output, 1.0)] {
+            if let Some(native) = native {
+                *flows.entry(SOL_ADDRESS.into()).or_default() +=
+                    sign * native.amount as f64 / LAMPORTS_PER_SOL;
+            }
+        }
+        for (tokens, sign) in [(&swap.token_inputs, -1.0), (&swap.token_outputs, 1.0)] {
+            for token in tokens.iter().filter(|token| token.user_account == wallet) {
+                *flows.entry(token.mint.clone()).or_default() +=
+                    sign * token.raw_token_amount.ui_amount();
+            }
+        }
+    } else {
+        for transfer in transaction.token_transfers.iter().flatten() {
+            if is_nft(&transfer.token_standard) {
+                continue;
+            }
+            let amount = transfer.token_amount.as_f64().unwrap_or_default();
+            let accounts = &transfer.user_accounts;
+            if accounts.from_user_account.as_deref() == Some(wallet) {
+                *flows.entry(transfer.mint.clone()).or_default() -= amount;
+            }
+            if accounts.to_user_account.as_deref() == Some(wallet) {
+                *flows.entry(transfer.mint.clone()).or_default() += amount;
+            }
+        }
+        let mut native = 0.0;
+        for transfer in transaction.native_transfers.iter().flatten() {
+            let amount = transfer.amount.as_f64().unwrap_or_default() / LAMPORTS_PER_SOL;
+            let accounts = &transfer.user_accounts;
+            if accounts.from_user_account.as_deref() == Some(wallet) {
+                native -= amount;
+            }
+            if accounts.to_user_account.as_deref() == Some(wallet) {
+                native += amount;
+            }
+        }
+        if native.abs() >= DUST_SOL {
+            *flows.entry(SOL_ADDRESS.into()).or_default() += native;
+        }
+    }
+    flows.retain(|_, delta| delta.abs() > f64::EPSILON);
+    flows
+}
+
+/// Whether `mint` was minted to `wallet` or sent by a wallet crediting it to many others too.
+fn is_airdrop(transaction: &EnhancedTransaction, wallet: &str, mint: &str) -> bool {
+    let transfers: Vec<_> = transaction
+        .token_transfers
+        .iter()
+        .flatten()
+        .filter(|transfer| transfer.mint == mint)
+        .collect();
+    transfers
+        .iter()
+        .filter(|transfer| transfer.user_accounts.to_user_account.as_deref() == Some(wallet))
+        .any(|credit| match &credit.user_accounts.from_user_account {
+            None => true,
+            Some(sender) => {
+                let recipients: HashSet<_> = transfers
+                    .iter()
+                    .filter(|transfer| {
+                        transfer.user_accounts.from_user_account.as_ref() == Some(sender)
+                    })
+                    .filter_map(|transfer| transfer.user_accounts.to_user_account.as_deref())
+                    .collect();
+                recipients.len() >= AIRDROP_FAN_OUT
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const WHALE: &str = "WhaLe1111111111111111111111111111111111111";
+    const OTHER_WHALE: &str = "WhaLe2222222222222222222222222222222222222";
+    const POOL: &str = "PooL111111111111111111111111111111111111111";
+    const TOKEN: &str = "61V8vBaqAGMpgDQi4JcAwo1dmBGHsyhzodcPqnEVpump";
+
+    fn transaction(json: serde_json::Value) -> EnhancedTransaction {
+        let mut transaction = serde_json::json!({
+            "signature": "sig",
+            "slot": 1,
+            "timestamp": 1743465600,
+            "nativeTransfers": [],
+            "tokenTransfers": [],
+        });
+        transaction
+            .as_object_mut()
+            .unwrap()
+            .extend(json.as_object().unwrap().clone());
+        serde_json::from_value(transaction).unwrap()
+    }
+
+    fn token_transfer(from: Option<&str>, to: &str, mint: &str, amount: f64) -> serde_json::Value {
+        serde_json::json!({
+            "fromUserAccount": from,
+            "toUserAccount": to,
+            "mint": mint,
+            "tokenAmount": amount,
+            "tokenStandard": "Fungible",
+        })
+    }
+
+    fn classify_for(transaction: &EnhancedTransaction, wallets: &[&str]) -> Vec<Classified> {
+        classify(transaction, &wallets.iter().copied().collect())
+    }
+
+    #[test]
+    fn should_read_buy_from_swap_event() {
+        let swap = transaction(serde_json::json!({
+            "type": "SWAP",
+            "source": "JUPITER",
+            "tokenTransfers": [token_transfer(Some(POOL), WHALE, TOKEN, 1500.0)],
+            "events": {"swap": {
+                "nativeInput": {"account": WHALE, "amount": "2500000000"},
+                "nativeOutput": null,
+                "tokenInputs": [],
+                "tokenOutputs": [{
+                    "userAccount": WHALE,
+                    "tokenAccount": "ata",
+                    "mint": TOKEN,
+                    "rawTokenAmount": {"tokenAmount": "1500000000", "decimals": 6},
+                }],
+                "tokenFees": [],
+                "nativeFees": [],
+                "innerSwaps": [],
+            }},
+        }));
+
+        assert_eq!(
+            classify_for(&swap, &[WHALE]),
+            vec![Classified {
+                token_address: TOKEN.into(),
+                wallet_address: WHALE.into(),
+                action: Action::Buy,
+                amount: 1500.0,
+                counter_asset: Some(SOL_ADDRESS.into()),
+                counter_amount: Some(2.5),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_read_sell_for_usdc_from_transfers() {
+        let sell = transaction(serde_json::json!({
+            "type": "UNKNOWN",
+            "nativeTransfers": [{"fromUserAccount": WHALE, "toUserAccount": POOL, "amount": 5000}],
+            "tokenTransfers": [
+                token_transfer(Some(WHALE), POOL, TOKEN, 800.0),
+                token_transfer(Some(POOL), WHALE, USDC_ADDRESS, 40.0),
+            ],
+        }));
+
+        let rows = classify_for(&sell, &[WHALE]);
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].action, Action::Sell);
+        assert_eq!(rows[0].amount, 800.0);
+        assert_eq!(rows[0].counter_asset.as_deref(), Some(USDC_ADDRESS));
+        assert_eq!(rows[0].counter_amount, Some(40.0));
+    }
+
+    #[test]
+    fn should_tell_transfers_from_airdrops() {
+        let internal = transaction(serde_json::json!({
+            "type": "TRANSFER",
+            "nativeTransfers": [
+                {"fromUserAccount": WHALE, "toUserAccount": OTHER_WHALE, "amount": 2039280}
+            ],
+            "tokenTransfers": [token_transfer(Some(WHALE), OTHER_WHALE, TOKEN, 10.0)],
+        }));
+        let actions: Vec<_> = classify_for(&internal, &[WHALE, OTHER_WHALE])
+            .into_iter()
+            .map(|row| (row.wallet_address, row.action, row.counter_asset))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (WHALE.to_string(), Action::Transfer, None),
+                (OTHER_WHALE.to_string(), Action::Transfer, None),
+            ]
+        );
+
+        let credits = ["a", "b", WHALE].map(|to| token_transfer(Some(POOL), to, TOKEN, 1.0));
+        let airdrop = transaction(serde_json::json!({
+            "type": "TRANSFER",
+            "tokenTransfers": credits,
+        }));
+        assert_eq!(classify_for(&airdrop, &[WHALE])[0].action, Action::Airdrop);
+    }
+
+    #[test]
+    fn should_read_liquidity_moves() {
+        let deposit = transaction(serde_json::json!({
+            "type": "ADD_LIQUIDITY",
+            "tokenTransfers": [
+                token_transfer(Some(WHALE), POOL, TOKEN, 1000.0),
+                token_transfer(Some(WHALE), POOL, SOL_ADDRESS, 3.0),
+                token_transfer(Some(POOL), WHALE, "LpMint", 50.0),
+            ],
+        }));
+
+        let rows = classify_for(&deposit, &[WHALE]);
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].action, Action::LpAdd);
+        assert_eq!(rows[0].token_address, TOKEN);
+        assert_eq!(rows[0].counter_asset.as_deref(), Some(SOL_ADDRESS));
+        assert_eq!(rows[0].counter_amount, Some(3.0));
+    }
+
+    #[test]
+    fn should_skip_nft_sales() {
+        let sale = transaction(serde_json::json!({
+            "type": "NFT_SALE",
+            "tokenTransfers": [token_transfer(Some(POOL), WHALE, TOKEN, 1.0)],
+        }));
+
+        assert!(classify_for(&sale, &[WHALE]).is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashSet;
+
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use tracing::{error, info};
+
+use super::process_events;
+use crate::app::AppState;
+use crate::error::ApiError;
+
+/// Events replayed at once, each page is processed in one database transaction.
+const REPLAY_PAGE_SIZE: i64 = 200;
+
+/// A transaction of a webhook call as received, a row of `webhook_events`.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct RawEvent {
+    pub signature: String,
+    pub payload: Value,
+}
+
+impl RawEvent {
+    /// The transactions of a webhook call, the first one of a repeated signature only.
+    pub fn from_payload(payload: Vec<Value>) -> Result<Vec<Self>, ApiError> {
+        let mut signatures = HashSet::new();
+        let mut events = vec![];
+        for payload in payload {
+            let signature = payload
+                .get("signature")
+                .and_then(Value::as_str)
+                .ok_or_else(|| ApiError::BadRequest("transaction without signature".into()))?
+                .to_string();
+            if signatures.insert(signature.clone()) {
+                events.push(Self { signature, payload });
+            }
+        }
+        Ok(events)
+    }
+}
+
+/// Store `events` and return those to process: the new ones and those not processed yet. The
+/// events already processed are deliveries retried by Helius and are skipped.
+pub async fn store(pool: &PgPool, events: &[RawEvent]) -> Result<Vec<RawEvent>, sqlx::Error> {
+    let signatures: Vec<&str> = events.iter().map(|e| e.signature.as_str()).collect();
+    let payloads: Vec<Value> = events.iter().map(|e| e.payload.clone()).collect();
+    let block_times: Vec<Option<i64>> = events
+        .iter()
+        .map(|e| e.payload.get("timestamp").and_then(Value::as_i64))
+        .collect();
+    let pending: HashSet<String> = sqlx::query_scalar(
+        "INSERT INTO webhook_events (signature, payload, block_time)
+        SELECT * FROM UNNEST($1::VARCHAR[], $2::JSONB[], $3::INT8[])
+        ON CONFLICT (signature) DO UPDATE
+        SET payload = EXCLUDED.payload, block_time = EXCLUDED.block_time
+        WHERE webhook_events.processed_at IS NULL
+        RETURNING signature",
+    )
+    .bind(&signatures)
+    .bind(&payloads)
+    .bind(&block_times)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+    Ok(events
+        .iter()
+        .filter(|e| pending.contains(&e.signature))
+        .cloned()
+        .collect())
+}
+
+/// Mark `signatures` processed, within the transaction that stored their moves.
+pub async fn mark_processed(
+    tx: &mut Transaction<'_, Postgres>,
+    signatures: &[&str],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE webhook_events
+        SET processed_at = NOW(), attempts = attempts + 1, last_error = NULL
+        WHERE signature = ANY($1)",
+    )
+    .bind(signatures)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Keep the parse error of the `(signature, error)` events, left unprocessed until replayed.
+pub async fn mark_unparsed(
+    tx: &mut Transaction<'_, Postgres>,
+    unparsed: &[(String, String)],
+) -> Result<(), sqlx::Error> {
+    let (signatures, errors): (Vec<&str>, Vec<&str>) = unparsed
+        .iter()
+        .map(|(signature, error)| (signature.as_str(), error.as_str()))
+        .unzip();
+    sqlx::query(
+        "UPDATE webhook_events e
+        SET processed_at = NULL, attempts = e.attempts + 1, last_error = u.error
+        FROM UNNEST($1::VARCHAR[], $2::TEXT[]) AS u(signature, error)
+        WHERE e.signature = u.signature",
+    )
+    .bind(&signatures)
+    .bind(&errors)
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+/// Count a failed processing of `events`, the changes of which were rolled back.
+pub async fn record_failure(
+    pool: &PgPool,
+    events: &[RawEvent],
+    error: &str,
+) -> Result<(), sqlx::Error> {
+    let signatures: Vec<&str> = events.iter().map(|e| e.signature.as_str()).collect();
+    sqlx::query(
+        "UPDATE webhook_events SET attempts = attempts + 1, last_error = $2
+        WHERE signature = ANY($1)",
+    )
+    .bind(&signatures)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Events replayed by [`replay`].
+#[derive(Debug, Default, PartialEq)]
+pub struct ReplayReport {
+    pub events: u64,
+    /// Events that still do not parse.
+    pub unparsed: u64,
+    /// Events of the pages that failed to process.
+    pub failed: u64,
+}
+
+/// Process again the stored events of block time in `[from, to)`, replacing the moves stored
+/// for them, e.g. once the parser is fixed. The moves are not published again.
+pub async fn replay(
+    app: &AppState,
+    from: Option<i64>,
+    to: Option<i64>,
+) -> anyhow::Result<ReplayReport> {
+    let mut report = ReplayReport::default();
+    let mut after = String::new();
+    loop {
+        let page: Vec<RawEvent> = sqlx::query_as(
+            "SELECT signature, payload FROM webhook_events
+            WHERE signature > $1
+              AND ($2::INT8 IS NULL OR block_time >= $2)
+              AND ($3::INT8 IS NULL OR block_time < $3)
+            ORDER BY signature
+            LIMIT $4",
+        )
+        .bind(&after)
+        .bind(from)
+        .bind(to)
+        .bind(REPLAY_PAGE_SIZE)
+        .fetch_all(&app.pool)
+        .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = last.signature.clone();
+        report.events += page.len() as u64;
+        match process_events(app, &page).await {
+            Ok(processed) => report.unparsed += processed.unparsed as u64,
+            Err(err) => {
+                error!("replay of {} webhook events failed: {err:?}", page.len());
+                record_failure(&app.pool, &page, &format!("{err:#}")).await?;
+                report.failed += page.len() as u64;
+            }
+        }
+    }
+    info!("webhook replay {report:?}");
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn should_keep_first_transaction_of_each_signature() {
+        let payload = vec![
+            json!({"signature": "a", "slot": 1}),
+            json!({"signature": "b"}),
+            json!({"signature": "a", "slot": 2}),
+        ];
+        let events = RawEvent::from_payload(payload).unwrap();
+        assert_eq!(
+            events,
+            vec![
+                RawEvent {
+                    signature: "a".into(),
+                    payload: json!({"signature": "a", "slot": 1}),
+                },
+                RawEvent {
+                    signature: "b".into(),
+                    payload: json!({"signature": "b"}),
+                },
+            ]
+        );
+
+        let unsigned = RawEvent::from_payload(vec![json!({"slot": 1})]);
+        assert!(matches!(unsigned, Err(ApiError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,495 @@
+use axum::extract::{Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Json;
+use futures::stream::{self, StreamExt};
+use helius::types::TokenTransfer;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::HashSet;
+use tracing::{error, info, warn};
+
+use crate::app::AppState;
+use crate::error::ApiError;
+use crate::events::Event;
+use crate::token::{promote_token_watch, WatchPriority};
+
+mod classify;
+mod event_log;
+mod valuation;
+
+use classify::Classified;
+pub use event_log::replay;
+use event_log::RawEvent;
+use valuation::Valuation;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EnhancedTransaction {
+    pub native_transfers: Option<Vec<helius::types::NativeTransfer>>,
+    pub token_transfers: Option<Vec<TokenTransfer>>,
+    pub slot: i64,
+    pub signature: String,
+    pub timestamp: i64,
+    /// Helius transaction type, e.g. `SWAP`, `TRANSFER` or `NFT_SALE`.
+    #[serde(rename = "type")]
+    pub transaction_type: Option<String>,
+    /// Program or marketplace the transaction went through, e.g. `JUPITER`.
+    pub source: Option<String>,
+    pub events: Option<TransactionEvents>,
+}
+
+/// `events` of an enhanced transaction, only the swap is read.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TransactionEvents {
+    pub swap: Option<SwapEvent>,
+}
+
+/// What the swapping wallet gave, the inputs, and received, the outputs.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapEvent {
+    pub native_input: Option<NativeAmount>,
+    pub native_output: Option<NativeAmount>,
+    #[serde(default)]
+    pub token_inputs: Vec<TokenAmount>,
+    #[serde(default)]
+    pub token_outputs: Vec<TokenAmount>,
+}
+
+impl SwapEvent {
+    /// The wallet that swapped.
+    pub fn user(&self) -> Option<&str> {
+        self.native_input
+            .iter()
+            .chain(&self.native_output)
+            .map(|native| native.account.as_str())
+            .chain(
+                self.token_inputs
+                    .iter()
+                    .chain(&self.token_outputs)
+                    .map(|token| token.user_account.as_str()),
+            )
+            .next()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NativeAmount {
+    pub account: String,
+    /// Lamports, Helius sends them as a string.
+    #[serde(deserialize_with = "lamports")]
+    pub amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAmount {
+    pub user_account: String,
+    pub mint: String,
+    pub raw_token_amount: RawTokenAmount,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RawTokenAmount {
+    pub token_amount: String,
+    pub decimals: u32,
+}
+
+impl RawTokenAmount {
+    pub fn ui_amount(&self) -> f64 {
+        self.token_amount.parse::<f64>().unwrap_or_default() / 10f64.powi(self.decimals as i32)
+    }
+}
+
+fn lamports<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Lamports {
+        Number(u64),
+        String(String),
+    }
+    match Lamports::deserialize(deserializer)? {
+        Lamports::Number(lamports) => Ok(lamports),
+        Lamports::String(lamports) => lamports.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Reject the webhook calls without one of the configured secrets in their `Authorization`
+/// header before their body is read, and log every rejected call.
+pub async fn authenticate(
+    State(app): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let origin = request
+        .headers()
+        .get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string();
+    let rejection = match request.headers().get(AUTHORIZATION) {
+        None => Some("missing auth header"),
+        Some(value)
+            if !value
+                .to_str()
+                .is_ok_and(|value| app.webhook_config.secrets.accepts(value)) =>
+        {
+            Some("invalid auth header")
+        }
+        Some(_) => None,
+    };
+    if let Some(rejection) = rejection {
+        warn!("rejected webhook call from {origin}: {rejection}");
+        return Err(ApiError::Unauthorized(rejection.into()));
+    }
+
+    let response = next.run(request).await;
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        warn!(
+            "rejected webhook call from {origin}: payload over {} bytes",
+            app.webhook_config.max_payload_bytes
+        );
+    }
+    Ok(response)
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct MarketMover {
+    pub wallet_address: String,
+    // additional fields if needed
+}
+
+// Helper function to extract unique wallet addresses from the payload
+fn extract_wallets(transactions: &[EnhancedTransaction]) -> Vec<String> {
+    let mut wallets = HashSet::new();
+    for trans in transactions {
+        if let Some(token_transfers) = trans.token_transfers.as_ref() {
+            for transfer in token_transfers {
+                if let Some(from) = transfer.user_accounts.from_user_account.as_ref() {
+                    wallets.insert(from.clone());
+                }
+
+                if let Some(from) = transfer.user_accounts.to_user_account.as_ref() {
+                    wallets.insert(from.clone());
+                }
+            }
+        }
+    }
+    wallets.into_iter().collect()
+}
+
+// Helper function to load market movers whose wallet addresses appear in the payload
+async fn load_wallets_by_list(
+    pool: &PgPool,
+    wallet_addresses: &[String],
+) -> Result<Vec<MarketMover>, sqlx::Error> {
+    // Using ANY($1) requires passing a slice
+    sqlx::query_as::<_, MarketMover>(
+        "SELECT wallet_address FROM market_mover WHERE wallet_address = ANY($1)",
+    )
+    .bind(wallet_addresses)
+    .fetch_all(pool)
+    .await
+}
+
+/// Store the transactions of a Helius webhook call in `webhook_events`, then the moves of the
+/// market movers in them. Transactions that do not parse are kept for a replay, a failure to
+/// store is answered with an error so that Helius delivers the call again.
+pub async fn webhook_handler(
+    State(app): State<AppState>,
+    Json(payload): Json<Vec<Value>>,
+) -> Result<&'static str, ApiError> {
+    let events = RawEvent::from_payload(payload)?;
+    let pending = event_log::store(&app.pool, &events).await?;
+    if pending.len() < events.len() {
+        info!(
+            "Skipping {} processed transactions",
+            events.len() - pending.len()
+        );
+    }
+    if pending.is_empty() {
+        return Ok("Webhook received");
+    }
+
+    match process_events(&app, &pending).await {
+        Ok(processed) => {
+            for event in processed.published {
+                app.events.publish(event).await;
+            }
+            Ok("Webhook received")
+        }
+        Err(err) => {
+            error!("Failed to process webhook transactions: {:?}", err);
+            if let Err(e) =
+                event_log::record_failure(&app.pool, &pending, &format!("{err:#}")).await
+            {
+                error!("Failed to record webhook failure: {:?}", e);
+            }
+            Err(ApiError::from(err))
+        }
+    }
+}
+
+/// A move of [`process_events`] with the transaction it was read from.
+struct Move<'a> {
+    transaction: &'a EnhancedTransaction,
+    row: Classified,
+    valuation: Option<Valuation>,
+}
+
+/// Value `row` at the block time of `transaction`, a move that cannot be valued is kept without
+/// a value.
+async fn value_move<'a>(
+    app: &AppState,
+    transaction: &'a EnhancedTransaction,
+    row: Classified,
+) -> Move<'a> {
+    let valuation = match valuation::value(
+        &app.pool,
+        app.price_sdk.as_ref(),
+        app.price_config.historical_max_distance_secs,
+        &row,
+        transaction.timestamp,
+    )
+    .await
+    {
+        Ok(valuation) => valuation,
+        Err(e) => {
+            warn!("Failed to value {}: {:?}", transaction.signature, e);
+            None
+        }
+    };
+    Move {
+        transaction,
+        row,
+        valuation,
+    }
+}
+
+/// Outcome of [`process_events`].
+struct Processed {
+    /// The stored moves, to publish.
+    published: Vec<Event>,
+    /// Events that do not parse.
+    unparsed: usize,
+}
+
+/// Store the moves of the tracked wallets in `events` in one database transaction, replacing
+/// those stored for the same signatures, and mark the events processed.
+async fn process_events(app: &AppState, events: &[RawEvent]) -> anyhow::Result<Processed> {
+    let mut payload = vec![];
+    let mut unparsed = vec![];
+    for event in events {
+        match serde_json::from_value::<EnhancedTransaction>(event.payload.clone()) {
+            Ok(transaction) => payload.push(transaction),
+            Err(e) => {
+                warn!("Failed to parse transaction {}: {}", event.signature, e);
+                unparsed.push((event.signature.clone(), e.to_string()));
+            }
+        }
+    }
+
+    // Extract distinct wallet addresses from payload token transfers.
+    let wallet_list = extract_wallets(&payload);
+    info!("Wallets from payload: {:?}", wallet_list);
+
+    // Load only wallets that are present in the payload.
+    let wallets = load_wallets_by_list(&app.pool, &wallet_list).await?;
+    info!("Wallets from payload: {:?}", wallets);
+    let tracked: HashSet<&str> = wallets.iter().map(|w| w.wallet_address.as_str()).collect();
+
+    // Classify the moves of the tracked wallets in each transaction and value them concurrently,
+    // so that the Birdeye calls of a large call do not add up.
+    let valuations: Vec<_> = payload
+        .iter()
+        .flat_map(|transaction| {
+            classify::classify(transaction, &tracked)
+                .into_iter()
+                .map(move |row| value_move(app, transaction, row))
+        })
+        .collect();
+    let moves: Vec<Move> = stream::iter(valuations)
+        .buffered(app.price_config.valuation_concurrency.max(1))
+        .collect()
+        .await;
+
+    let signatures: Vec<&str> = payload.iter().map(|t| t.signature.as_str()).collect();
+    let mut tx = app.pool.begin().await?;
+    sqlx::query("DELETE FROM market_movers_transaction WHERE signature = ANY($1)")
+        .bind(&signatures)
+        .execute(&mut *tx)
+        .await?;
+    insert_transactions(&mut tx, &moves).await?;
+    event_log::mark_processed(&mut tx, &signatures).await?;
+    event_log::mark_unparsed(&mut tx, &unparsed).await?;
+    tx.commit().await?;
+
+    let token_addresses: Vec<String> = moves.iter().map(|m| m.row.token_address.clone()).collect();
+    if let Err(err) = promote_token_watch(
+        &app.pool,
+        token_addresses.as_slice(),
+        WatchPriority::Hot,
+        app.token_watch_config.hot_hold_secs,
+    )
+    .await
+    {
+        error!("insert token watch failed: {:?}", err);
+    }
+
+    let published = moves
+        .into_iter()
+        .map(|m| Event::MarketMoverTransaction {
+            signature: m.transaction.signature.clone(),
+            token_address: m.row.token_address,
+            wallet_address: m.row.wallet_address,
+            transaction_type: m.row.action.as_str().into(),
+            amount: m.row.amount,
+            counter_asset: m.row.counter_asset,
+            counter_amount: m.row.counter_amount,
+            value_usd: m.valuation.map(|v| v.value_usd),
+            block_time: m.transaction.timestamp,
+        })
+        .collect();
+    Ok(Processed {
+        published,
+        unparsed: unparsed.len(),
+    })
+}
+
+#[allow(dead_code)]
+pub async fn token_watch_exists(pool: &PgPool, token_address: &str) -> Result<bool, sqlx::Error> {
+    let exists: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM token_watch WHERE token_address = $1)")
+            .bind(token_address)
+            .fetch_one(pool)
+            .await?;
+    Ok(exists)
+}
+
+// async fn fetch_missing(app: &AppState, missing: Vec<String>) {
+//     match app.bird_eye_client.token_meta_multiple(missing).await {
+//         Ok(token_meta_list) if token_meta_list.is_empty() => {
+//             info!("birdeye no tokens");
+//         }
+//         Ok(token_meta_list) => {
+//             info!("Fetched token metadata: {:?}", token_meta_list);
+//             // Map the token metadata list to a list of Trending records.
+//             let trending: Vec<token::Trending> = token_meta_list
+//                 .into_iter()
+//                 .map(|tm| token::Trending {
+//                     address: tm.address,
+//                     decimals: tm.decimals,
+//                     logo_uri: Some(tm.logo_uri),
+//                     name: tm.name,
+//                     symbol: tm.symbol,
+//                     volume24h_usd: 0.0, // No volume data in overview
+//                     rank: 0,            // No rank data in overview
+//                     price: 0.0,
+//                 })
+//                 .collect();
+//
+//             if let Err(e) = crate::token::upsert_token_meta(&app.pool, &trending).await {
+//                 error!("Failed to upsert token metadata: {:?}", e);
+//             }
+//         }
+//         Err(e) => {
+//             tracing::warn!("Failed to fetch token metadata from BirdEye: {:?}", e);
+//         }
+//     }
+// }
+
+async fn insert_transactions(
+    tx: &mut Transaction<'_, Postgres>,
+    moves: &[Move<'_>],
+) -> Result<(), sqlx::Error> {
+    let column = |f: fn(&Move) -> Option<f64>| moves.iter().map(f).collect::<Vec<_>>();
+    sqlx::query(
+        "INSERT INTO market_movers_transaction
+         (signature, token_address, wallet_address, transaction_type, amount, block_time, slot,
+          counter_asset, counter_amount, price_usd, value_usd, price_source)
+         SELECT * FROM UNNEST($1::VARCHAR[], $2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[],
+                              $5::FLOAT8[], $6::INT8[], $7::INT8[], $8::VARCHAR[],
+                              $9::FLOAT8[], $10::FLOAT8[], $11::FLOAT8[], $12::VARCHAR[])
+         ON CONFLICT (signature, wallet_address, token_address) DO UPDATE
+         SET transaction_type = EXCLUDED.transaction_type,
+             amount = EXCLUDED.amount,
+             block_time = EXCLUDED.block_time,
+             slot = EXCLUDED.slot,
+             additional = EXCLUDED.additional,
+             counter_asset = EXCLUDED.counter_asset,
+             counter_amount = EXCLUDED.counter_amount,
+             price_usd = EXCLUDED.price_usd,
+             value_usd = EXCLUDED.value_usd,
+             price_source = EXCLUDED.price_source",
+    )
+    .bind(
+        moves
+            .iter()
+            .map(|m| &m.transaction.signature)
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        moves
+            .iter()
+            .map(|m| &m.row.token_address)
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        moves
+            .iter()
+            .map(|m| &m.row.wallet_address)
+            .collect::<Vec<_>>(),
+    )
+    .bind(
+        moves
+            .iter()
+            .map(|m| m.row.action.as_str())
+            .collect::<Vec<_>>(),
+    )
+    .bind(moves.iter().map(|m| m.row.amount).collect::<Vec<_>>())
+    .bind(
+        moves
+            .iter()
+            .map(|m| m.transaction.timestamp)
+            .collect::<Vec<_>>(),
+    )
+    .bind(moves.iter().map(|m| m.transaction.slot).collect::<Vec<_>>())
+    .bind(
+        moves
+            .iter()
+            .map(|m| m.row.counter_asset.as_deref())
+            .collect::<Vec<_>>(),
+    )
+    .bind(column(|m| m.row.counter_amount))
+    .bind(column(|m| m.valuation.map(|v| v.price_usd)))
+    .bind(column(|m| m.valuation.map(|v| v.value_usd)))
+    .bind(
+        moves
+            .iter()
+            .map(|m| m.valuation.map(|v| v.source.as_str()))
+            .collect::<Vec<_>>(),
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::webhook::{classify, EnhancedTransaction};
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_deser_enhanced_trans() {
+        let enhanced_trans = r#"[{"accountData":[{"account":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","nativeBalanceChange":-72938049280,"tokenBalanceChanges":[]},{"account":"NTYeYJ1wr4bpM5xo6zx5En44SvJFAd35zTxxNoERYqd","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"AAaTGaA3uVqikfVEwoSG7EwkCb4bBDsMEyueiVUS5CaU","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"autMW8SgBkVYeBgqYiTuJZnkvDZMVU2MHJh9Jh7CSQ2","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"D8TxfGwdu9MiNMoJmUoC9wQfNfNT7Lnm6DzifQHRTy6B","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"5DxD5ViWjvRZEkxQEaJHZw2sBsso6xoXx3wGFNKgXUzE","nativeBalanceChange":71860273440,"tokenBalanceChanges":[]},{"account":"25DTUAd1roBFoUQaxJQByL6Qy2cKQCBp4bK9sgfy9UiM","nativeBalanceChange":-2039280,"tokenBalanceChanges":[{"mint":"FdsNQE5EeCe57tbEYCRV1JwW5dzNCof7MUTaGWhmzYqu","rawTokenAmount":{"decimals":0,"tokenAmount":"-1"},"tokenAccount":"25DTUAd1roBFoUQaxJQByL6Qy2cKQCBp4bK9sgfy9UiM","userAccount":"1BWutmTvYPwDtmw9abTkS4Ssr8no61spGAvW1X6NDix"}]},{"account":"DTYuh7gAGGZg2okM7hdFfU1yMY9LUemCiPyD5Z5GCs6Z","nativeBalanceChange":2039280,"tokenBalanceChanges":[{"mint":"FdsNQE5EeCe57tbEYCRV1JwW5dzNCof7MUTaGWhmzYqu","rawTokenAmount":{"decimals":0,"tokenAmount":"1"},"tokenAccount":"DTYuh7gAGGZg2okM7hdFfU1yMY9LUemCiPyD5Z5GCs6Z","userAccount":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX"}]},{"account":"rFqFJ9g7TGBD8Ed7TPDnvGKZ5pWLPDyxLcvcH2eRCtt","nativeBalanceChange":1080000000,"tokenBalanceChanges":[]},{"account":"CgXS5xC3qAGSg9txD9bS7BUgugZwshivGXpCJcGmdwrd","nativeBalanceChange":-2234160,"tokenBalanceChanges":[]},{"account":"M2mx93ekt1fmXSVkTrUL9xVFHkmME8HTUi5Cyc5aF7K","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"E8cU1WiRWjanGxmn96ewBgk9vPTcL6AEZ1t6F6fkgUWe","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"11111111111111111111111111111111","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"FdsNQE5EeCe57tbEYCRV1JwW5dzNCof7MUTaGWhmzYqu","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"AYZsWahcrSnkwqbA1ji7wEzgAnGjLNJhVUMDPfACECZf","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"SysvarRent111111111111111111111111111111111","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL","nativeBalanceChange":0,"tokenBalanceChanges":[]},{"account":"1BWutmTvYPwDtmw9abTkS4Ssr8no61spGAvW1X6NDix","nativeBalanceChange":0,"tokenBalanceChanges":[]}],"description":"5DxD5ViWjvRZEkxQEaJHZw2sBsso6xoXx3wGFNKgXUzE sold Fox #7637 to CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX for 72 SOL on MAGIC_EDEN.","events":{"nft":{"amount":72000000000,"buyer":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","description":"5DxD5ViWjvRZEkxQEaJHZw2sBsso6xoXx3wGFNKgXUzE sold Fox #7637 to CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX for 72 SOL on MAGIC_EDEN.","fee":10000,"feePayer":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","nfts":[{"mint":"FdsNQE5EeCe57tbEYCRV1JwW5dzNCof7MUTaGWhmzYqu","tokenStandard":"NonFungible"}],"saleType":"INSTANT_SALE","seller":"5DxD5ViWjvRZEkxQEaJHZw2sBsso6xoXx3wGFNKgXUzE","signature":"5nNtjezQMYBHvgSQmoRmJPiXGsPAWmJPoGSa64xanqrauogiVzFyGQhKeFataHGXq51jR2hjbzNTkPUpP787HAmL","slot":171942732,"source":"MAGIC_EDEN","staker":"","timestamp":1673445241,"type":"NFT_SALE"}},"fee":10000,"feePayer":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","nativeTransfers":[{"amount":72936000000,"fromUserAccount":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","toUserAccount":"AAaTGaA3uVqikfVEwoSG7EwkCb4bBDsMEyueiVUS5CaU"},{"amount":2011440,"fromUserAccount":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","toUserAccount":"D8TxfGwdu9MiNMoJmUoC9wQfNfNT7Lnm6DzifQHRTy6B"},{"amount":71856000000,"fromUserAccount":"AAaTGaA3uVqikfVEwoSG7EwkCb4bBDsMEyueiVUS5CaU","toUserAccount":"5DxD5ViWjvRZEkxQEaJHZw2sBsso6xoXx3wGFNKgXUzE"},{"amount":1080000000,"fromUserAccount":"AAaTGaA3uVqikfVEwoSG7EwkCb4bBDsMEyueiVUS5CaU","toUserAccount":"rFqFJ9g7TGBD8Ed7TPDnvGKZ5pWLPDyxLcvcH2eRCtt"},{"amount":2039280,"fromUserAccount":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","toUserAccount":"DTYuh7gAGGZg2okM7hdFfU1yMY9LUemCiPyD5Z5GCs6Z"}],"signature":"5nNtjezQMYBHvgSQmoRmJPiXGsPAWmJPoGSa64xanqrauogiVzFyGQhKeFataHGXq51jR2hjbzNTkPUpP787HAmL","slot":171942732,"source":"MAGIC_EDEN","timestamp":1673445241,"tokenTransfers":[{"fromTokenAccount":"25DTUAd1roBFoUQaxJQByL6Qy2cKQCBp4bK9sgfy9UiM","fromUserAccount":"1BWutmTvYPwDtmw9abTkS4Ssr8no61spGAvW1X6NDix","mint":"FdsNQE5EeCe57tbEYCRV1JwW5dzNCof7MUTaGWhmzYqu","toTokenAccount":"DTYuh7gAGGZg2okM7hdFfU1yMY9LUemCiPyD5Z5GCs6Z","toUserAccount":"CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX","tokenAmount":1,"tokenStandard":"NonFungible"}],"type":"NFT_SALE"}]"#;
+        let transactions: Vec<EnhancedTransaction> = serde_json::from_str(enhanced_trans).unwrap();
+        println!("aaaa {transactions:?}");
+        let buyer = "CKs1E69a2e9TmH4mKKLrXFF8kD3ZnwKjoEuXa6sz9WqX";
+        assert!(classify::classify(&transactions[0], &HashSet::from([buyer])).is_empty());
+    }
+}
@@ -0,0 +1,163 @@
+use sqlx::PgPool;
+
+use super::classify::{Action, Classified, USDC_ADDRESS};
+use crate::price::{self, PriceSdk, PriceSource};
+
+/// Where the price of a move comes from, stored in `market_movers_transaction.price_source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValuationSource {
+    /// Implied by the SOL or USDC leg of the trade itself.
+    Trade,
+    /// Nearest price stored in `token_prices` or `token_metrics`.
+    Database,
+    /// Birdeye candle of the block time.
+    Provider,
+}
+
+impl ValuationSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValuationSource::Trade => "trade",
+            ValuationSource::Database => "database",
+            ValuationSource::Provider => "provider",
+        }
+    }
+}
+
+impl From<PriceSource> for ValuationSource {
+    fn from(source: PriceSource) -> Self {
+        match source {
+            PriceSource::Database => ValuationSource::Database,
+            PriceSource::Provider => ValuationSource::Provider,
+        }
+    }
+}
+
+/// USD price of the token and value of a move at its block time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Valuation {
+    pub price_usd: f64,
+    pub value_usd: f64,
+    pub source: ValuationSource,
+}
+
+/// Value `row` at `block_time`. A buy or sell against SOL or USDC is valued by what was paid or
+/// received, every other move by the price of the token nearest to the block time.
+pub async fn value<T: PriceSdk + ?Sized>(
+    pool: &PgPool,
+    client: &T,
+    max_distance_secs: i64,
+    row: &Classified,
+    block_time: i64,
+) -> anyhow::Result<Option<Valuation>> {
+    if row.amount <= 0.0 {
+        return Ok(None);
+    }
+    let trade = matches!(row.action, Action::Buy | Action::Sell);
+    if let (true, Some(asset), Some(amount)) = (trade, &row.counter_asset, row.counter_amount) {
+        let quote = if asset == USDC_ADDRESS {
+            Some(1.0)
+        } else {
+            price::price_at(pool, client, asset, block_time, max_distance_secs)
+                .await?
+                .map(|(price, _)| price)
+        };
+        if let Some(quote) = quote {
+            let value_usd = amount * quote;
+            return Ok(Some(Valuation {
+                price_usd: value_usd / row.amount,
+                value_usd,
+                source: ValuationSource::Trade,
+            }));
+        }
+    }
+    let price = price::price_at(
+        pool,
+        client,
+        &row.token_address,
+        block_time,
+        max_distance_secs,
+    )
+    .await?;
+    Ok(price.map(|(price_usd, source)| Valuation {
+        price_usd,
+        value_usd: price_usd * row.amount,
+        source: source.into(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::test::{test_pool, unique_name};
+    use crate::app::SOL_ADDRESS;
+    use crate::thirdparty::mock::MockClient;
+    use crate::thirdparty::{Items, PriceHistory};
+
+    fn buy(counter_asset: &str, counter_amount: f64) -> Classified {
+        Classified {
+            token_address: unique_name("valuation"),
+            wallet_address: "wallet".into(),
+            action: Action::Buy,
+            amount: 2000.0,
+            counter_asset: Some(counter_asset.into()),
+            counter_amount: Some(counter_amount),
+        }
+    }
+
+    #[tokio::test]
+    async fn should_value_usdc_trade_by_its_leg() {
+        // the USDC leg needs no price, the pool is never used
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://localhost/unused")
+            .unwrap();
+        let valuation = value(&pool, &MockClient, 3600, &buy(USDC_ADDRESS, 50.0), 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            valuation,
+            Some(Valuation {
+                price_usd: 0.025,
+                value_usd: 50.0,
+                source: ValuationSource::Trade,
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn should_value_sol_trade_at_stored_price_and_transfer_at_provider_price() {
+        let pool = test_pool().await;
+        // far in the past so that no other test stored a SOL price around it
+        let block_time = 946684800;
+        let items = vec![Items {
+            value: 150.0,
+            unix_time: block_time - 30,
+        }];
+        price::insert_token_prices(&pool, PriceHistory { items }, SOL_ADDRESS)
+            .await
+            .unwrap();
+
+        let row = buy(SOL_ADDRESS, 0.5);
+        let valuation = value(&pool, &MockClient, 3600, &row, block_time)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(valuation.value_usd, 75.0);
+        assert_eq!(valuation.price_usd, 75.0 / 2000.0);
+        assert_eq!(valuation.source, ValuationSource::Trade);
+
+        let transfer = Classified {
+            action: Action::Transfer,
+            counter_asset: None,
+            counter_amount: None,
+            ..row
+        };
+        let valuation = value(&pool, &MockClient, 3600, &transfer, block_time)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(valuation.source, ValuationSource::Provider);
+        assert_eq!(valuation.value_usd, valuation.price_usd * 2000.0);
+    }
+}